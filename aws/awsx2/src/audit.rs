@@ -0,0 +1,80 @@
+//! Append-only audit trail: one JSON object per line in `audit.log`, written
+//! for every state-changing action whether it came from the CLI or the TUI.
+//!
+//! Hooks sit where the action is performed (the `aws`, `tunnel` and `vpn`
+//! helpers), not in the front ends, so both are covered by the same calls.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::error::Result;
+
+/// One line of `audit.log`; fields are written in this order.
+#[derive(Serialize)]
+struct Entry<'a> {
+    ts: String,
+    profile: String,
+    region: String,
+    action: &'a str,
+    target: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<&'a str>,
+    /// `"ok"` or `"error"`.
+    result: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+pub fn audit_path() -> PathBuf {
+    crate::config::config_dir().join("audit.log")
+}
+
+/// Record `action` on `target` with its outcome. `detail` carries extra
+/// context such as the new instance type. Nothing is written with
+/// `audit_log = false` or under `--dry-run`, and a failed write is ignored:
+/// the audit trail must never block the action itself.
+pub fn record<T>(action: &str, target: &str, detail: Option<&str>, profile: Option<&str>, result: &Result<T>) {
+    if !crate::config::settings().audit_log || crate::aws::dry_run() {
+        return;
+    }
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let entry = Entry {
+        ts: crate::aws::unix_to_iso8601(now),
+        profile: profile.map(String::from).unwrap_or_else(crate::aws::get_profile),
+        region: region(profile),
+        action,
+        target,
+        detail,
+        result: if result.is_ok() { "ok" } else { "error" },
+        error: result.as_ref().err().map(|e| crate::error::redact(&e.to_string())),
+    };
+    let Ok(json) = serde_json::to_string(&entry) else { return };
+    // One write per line: O_APPEND keeps concurrent writers from interleaving.
+    let line = format!("{}\n", json);
+    let _ = std::fs::create_dir_all(crate::config::config_dir());
+    if let Ok(mut f) = std::fs::OpenOptions::new().create(true).append(true).open(audit_path()) {
+        let _ = f.write_all(line.as_bytes());
+    }
+}
+
+/// `aws::get_region`, remembered per profile: without `--region` or the
+/// environment variables it shells out to `aws configure`, and some actions
+/// (stopping a tunnel in the TUI) are recorded on the UI thread.
+fn region(profile: Option<&str>) -> String {
+    static CACHE: Mutex<Option<HashMap<String, String>>> = Mutex::new(None);
+    if let Some(r) = crate::aws::region_override() {
+        return r;
+    }
+    let key = profile.map(String::from).unwrap_or_else(crate::aws::get_profile);
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache
+        .get_or_insert_with(HashMap::new)
+        .entry(key)
+        .or_insert_with(|| crate::aws::get_region(profile))
+        .clone()
+}
@@ -3,10 +3,168 @@
 
 use std::collections::{HashMap, HashSet};
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
 
 use crate::error::{AppError, Result};
 use crate::models::*;
 
+// ── Region override ───────────────────────────────────────────────────────────
+
+/// Region forced by `--region` (or the TUI "Switch Region" tool). When set it is
+/// passed to every `aws` invocation and wins over env vars and profile config.
+static REGION_OVERRIDE: RwLock<Option<String>> = RwLock::new(None);
+
+pub fn set_region_override(region: Option<String>) {
+    *REGION_OVERRIDE.write().unwrap_or_else(|e| e.into_inner()) =
+        region.filter(|r| !r.is_empty());
+}
+
+pub fn region_override() -> Option<String> {
+    REGION_OVERRIDE.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+// ── Dry run ───────────────────────────────────────────────────────────────────
+
+/// Set by the global `--dry-run` flag. Mutating `aws` calls are printed to
+/// stderr and skipped with a synthetic success; read-only calls (describe-*,
+/// list-*, get-*) still run so later steps see real IDs and states.
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+pub fn set_dry_run(on: bool) {
+    DRY_RUN.store(on, Ordering::Relaxed);
+}
+
+pub fn dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// Shell-style rendering of a command, single-quoting args that need it.
+pub fn command_line(cmd: &Command) -> String {
+    shell_join(command_words(cmd))
+}
+
+fn command_words(cmd: &Command) -> Vec<String> {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect()
+}
+
+fn shell_join(words: Vec<String>) -> String {
+    let quote = |s: &str| {
+        if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=,@+".contains(c)) {
+            s.to_string()
+        } else {
+            format!("'{}'", s.replace('\'', r"'\''"))
+        }
+    };
+    words.iter().map(|w| quote(w)).collect::<Vec<_>>().join(" ")
+}
+
+// ── Verbose ───────────────────────────────────────────────────────────────────
+
+/// Where `--verbose` (or `AWSX2_VERBOSE`) logs each external command before
+/// running it. The TUI logs to a file so the screen stays intact.
+#[derive(Debug, Clone)]
+pub enum VerboseLog {
+    Stderr,
+    File(std::path::PathBuf),
+}
+
+static VERBOSE: RwLock<Option<VerboseLog>> = RwLock::new(None);
+
+pub fn set_verbose(log: Option<VerboseLog>) {
+    *VERBOSE.write().unwrap_or_else(|e| e.into_inner()) = log;
+}
+
+/// Flags whose value is a secret. Credential files (openvpn `--auth-user-pass`)
+/// are passed by path, so their contents never reach the log.
+const SECRET_FLAGS: &[&str] = &["--password", "--secret-access-key", "--session-token"];
+
+/// Log `cmd` as `command_line` renders it, with secret values masked.
+pub fn log_command(cmd: &Command) {
+    let Some(log) = VERBOSE.read().unwrap_or_else(|e| e.into_inner()).clone() else { return };
+    let mut words = command_words(cmd);
+    for i in 1..words.len() {
+        words[i] = if SECRET_FLAGS.contains(&words[i - 1].as_str()) {
+            "<redacted>".into()
+        } else {
+            crate::error::redact(&words[i])
+        };
+    }
+    let line = format!("[verbose] {}", shell_join(words));
+    match log {
+        VerboseLog::Stderr => eprintln!("\x1b[90m{}\x1b[0m", line),
+        VerboseLog::File(path) => {
+            use std::io::Write as _;
+            if let Ok(mut f) = std::fs::OpenOptions::new().create(true).append(true).open(path) {
+                let _ = writeln!(f, "{}", line);
+            }
+        }
+    }
+}
+
+/// In dry-run mode print `cmd`, and return true if the caller must not run it.
+pub fn dry_run_skip(cmd: &Command) -> bool {
+    if !dry_run() {
+        return false;
+    }
+    let args: Vec<String> = cmd.get_args().map(|a| a.to_string_lossy().into_owned()).collect();
+    // `<service> <operation>` follow the global options aws_cmd adds.
+    let mut words = Vec::new();
+    let mut it = args.iter();
+    while let Some(a) = it.next() {
+        if a == "--profile" || a == "--region" {
+            it.next();
+        } else if !a.starts_with('-') {
+            words.push(a.as_str());
+            if words.len() == 2 { break; }
+        }
+    }
+    let read_only = match words[..] {
+        ["configure", "get"] => true,
+        [_, op] => ["describe-", "list-", "get-"].iter().any(|p| op.starts_with(p)),
+        _ => false,
+    };
+    if read_only {
+        eprintln!("\x1b[90m[dry-run] (read-only, running) {}\x1b[0m", command_line(cmd));
+        false
+    } else {
+        eprintln!("[dry-run] {}", command_line(cmd));
+        true
+    }
+}
+
+// ── External tools ────────────────────────────────────────────────────────────
+
+/// Needed by every SSM session (tunnels, shells); `aws` fails without it.
+pub const SSM_PLUGIN: &str = "session-manager-plugin";
+
+/// Whether an executable called `name` is in one of the `PATH` directories.
+pub fn on_path(name: &str) -> bool {
+    let Some(path) = std::env::var_os("PATH") else { return false };
+    std::env::split_paths(&path).any(|dir| {
+        let file = dir.join(name);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.metadata().is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        }
+        #[cfg(not(unix))]
+        { file.is_file() || file.with_extension("exe").is_file() }
+    })
+}
+
+/// `MissingDependency` for the first of `names` not on PATH.
+pub fn check_dependencies(names: &[&str]) -> Result<()> {
+    match names.iter().find(|n| !on_path(n)) {
+        Some(missing) => Err(AppError::MissingDependency(missing.to_string())),
+        None => Ok(()),
+    }
+}
+
 // ── Internal helpers ──────────────────────────────────────────────────────────
 
 fn aws_cmd(profile: Option<&str>) -> Command {
@@ -17,62 +175,197 @@ fn aws_cmd(profile: Option<&str>) -> Command {
     if let Some(p) = p {
         cmd.args(["--profile", &p]);
     }
+    if let Some(r) = region_override() {
+        cmd.args(["--region", &r]);
+    }
     cmd
 }
 
-fn run_aws(args: &[&str], profile: Option<&str>) -> Result<String> {
-    let output = aws_cmd(profile)
-        .args(args)
-        .args(["--output", "json"])
-        .output()
-        .map_err(|e| {
+/// stderr fragments of throttling / rate-limit errors worth retrying.
+const THROTTLE_MARKERS: &[&str] = &[
+    "Throttling",
+    "RequestLimitExceeded",
+    "TooManyRequestsException",
+    "Rate exceeded",
+    "SlowDown",
+];
+
+/// stderr fragments the aws CLI emits when the SSO session or STS token lapsed.
+const EXPIRED_MARKERS: &[&str] = &[
+    "ExpiredToken",
+    "Token has expired",
+    "SSO session associated with this profile has expired",
+    "security token included in the request is expired",
+];
+
+/// Backoff before each retry of a throttled call.
+const THROTTLE_BACKOFF: [Duration; 3] = [
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+    Duration::from_secs(4),
+];
+
+/// Run `cmd` to completion, retrying with backoff while AWS reports
+/// throttling. Other failures are returned immediately.
+fn output_with_retry(cmd: &mut Command) -> Result<std::process::Output> {
+    let mut delays = THROTTLE_BACKOFF.iter();
+    loop {
+        log_command(cmd);
+        let output = cmd.output().map_err(|e| {
             if e.kind() == std::io::ErrorKind::NotFound {
-                AppError::AwsCli("aws CLI not found — install it or check your PATH".into())
+                AppError::MissingDependency("aws".into())
             } else {
                 AppError::Io(e)
             }
         })?;
-    if !output.status.success() {
-        return Err(AppError::AwsCli(
-            String::from_utf8_lossy(&output.stderr).trim().to_string(),
-        ));
+        if output.status.success() {
+            return Ok(output);
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if EXPIRED_MARKERS.iter().any(|m| stderr.contains(m)) {
+            return Err(AppError::CredentialsExpired);
+        }
+        let throttled = THROTTLE_MARKERS.iter().any(|m| stderr.contains(m));
+        match delays.next() {
+            Some(delay) if throttled => std::thread::sleep(*delay),
+            _ => return Err(AppError::AwsCli(stderr)),
+        }
+    }
+}
+
+fn run_aws(args: &[&str], profile: Option<&str>) -> Result<String> {
+    let mut cmd = aws_cmd(profile);
+    cmd.args(args).args(["--output", "json"]);
+    if dry_run_skip(&cmd) {
+        return Ok("{}".to_string());
     }
+    let output = output_with_retry(&mut cmd)?;
     Ok(String::from_utf8_lossy(&output.stdout).to_string())
 }
 
 fn run_aws_silent(args: &[&str], profile: Option<&str>) -> Result<()> {
-    let output = aws_cmd(profile)
-        .args(args)
-        .output()
-        .map_err(|e| {
-            if e.kind() == std::io::ErrorKind::NotFound {
-                AppError::AwsCli("aws CLI not found — install it or check your PATH".into())
-            } else {
-                AppError::Io(e)
-            }
-        })?;
-    if !output.status.success() {
-        return Err(AppError::AwsCli(
-            String::from_utf8_lossy(&output.stderr).trim().to_string(),
-        ));
+    let mut cmd = aws_cmd(profile);
+    cmd.args(args);
+    if dry_run_skip(&cmd) {
+        return Ok(());
     }
-    Ok(())
+    output_with_retry(&mut cmd).map(|_| ())
 }
 
 // ── Public API ────────────────────────────────────────────────────────────────
 
+// ── Instance cache ────────────────────────────────────────────────────────────
+
+const INSTANCE_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Last `list_instances` result, keyed by (profile, region override) so that
+/// switching either never serves another account's/region's instances.
+struct InstanceCache {
+    key: (Option<String>, Option<String>),
+    at: Instant,
+    instances: Vec<Instance>,
+}
+
+static INSTANCE_CACHE: Mutex<Option<InstanceCache>> = Mutex::new(None);
+
+fn cache_key(profile: Option<&str>) -> (Option<String>, Option<String>) {
+    let p = profile
+        .map(|s| s.to_string())
+        .or_else(|| std::env::var("AWS_PROFILE").ok().filter(|s| !s.is_empty()));
+    (p, region_override())
+}
+
+/// Drop the cached instance list so the next lookup hits AWS.
+pub fn invalidate_cache() {
+    *INSTANCE_CACHE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Like `list_instances`, but reuses a result younger than 10 s. For read-only
+/// lookups (name resolution, bastion discovery, DNS matching).
+pub fn list_instances_cached(profile: Option<&str>) -> Result<Vec<Instance>> {
+    let key = cache_key(profile);
+    if let Some(c) = INSTANCE_CACHE.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        if c.key == key && c.at.elapsed() < INSTANCE_CACHE_TTL {
+            return Ok(c.instances.clone());
+        }
+    }
+    list_instances(profile)
+}
+
+/// Always queries AWS; the result refreshes the cache.
 pub fn list_instances(profile: Option<&str>) -> Result<Vec<Instance>> {
-    let json = run_aws(
-        &["ec2", "describe-instances", "--query", "Reservations[*].Instances[*]"],
-        profile,
-    )?;
-    let raw: Vec<Vec<RawInstance>> = serde_json::from_str(&json)?;
+    list_instances_filtered(&[], profile)
+}
+
+/// Turn `key=value[,value…]` into a describe-instances `--filters` entry, e.g.
+/// `tag:Environment=prod` → `Name=tag:Environment,Values=prod`.
+pub fn parse_instance_filter(spec: &str) -> Result<String> {
+    match spec.split_once('=') {
+        Some((name, values)) if !name.trim().is_empty() && !values.trim().is_empty() => {
+            Ok(format!("Name={},Values={}", name.trim(), values.trim()))
+        }
+        _ => Err(AppError::Other(format!(
+            "Invalid filter '{}' — expected key=value, e.g. tag:Environment=prod", spec
+        ))),
+    }
+}
+
+/// `list_instances_filtered` for every profile in `list_profiles()`, queried in
+/// parallel. Each profile keeps its own result so one failure (e.g. an expired
+/// SSO session) doesn't hide the others.
+pub fn list_instances_all_profiles(filters: &[String]) -> Vec<(String, Result<Vec<Instance>>)> {
+    let profiles = list_profiles();
+    std::thread::scope(|s| {
+        let handles: Vec<_> = profiles
+            .iter()
+            .map(|p| s.spawn(move || list_instances_filtered(filters, Some(p))))
+            .collect();
+        profiles
+            .iter()
+            .zip(handles)
+            .map(|(p, h)| {
+                let result = h
+                    .join()
+                    .unwrap_or_else(|_| Err(AppError::Other("instance listing worker panicked".into())));
+                (p.clone(), result)
+            })
+            .collect()
+    })
+}
+
+/// Query AWS with server-side `filters` (`key=value` specs). Only unfiltered
+/// results refresh the cache, since name lookups need the whole fleet.
+pub fn list_instances_filtered(filters: &[String], profile: Option<&str>) -> Result<Vec<Instance>> {
+    let filter_args = filters
+        .iter()
+        .map(|f| parse_instance_filter(f))
+        .collect::<Result<Vec<_>>>()?;
+    let mut args = vec!["ec2", "describe-instances", "--query", "Reservations[*].Instances[*]"];
+    if !filter_args.is_empty() {
+        args.push("--filters");
+        args.extend(filter_args.iter().map(String::as_str));
+    }
+
+    // The SSM inventory doesn't depend on the EC2 listing — fetch both at once.
+    let (json, ssm_map) = std::thread::scope(|s| {
+        let ssm = s.spawn(|| get_ssm_status(profile).unwrap_or_default());
+        let json = run_aws(&args, profile);
+        (json, ssm.join().unwrap_or_default())
+    });
+    let raw: Vec<Vec<RawInstance>> = serde_json::from_str(&json?)?;
     let flat: Vec<RawInstance> = raw.into_iter().flatten().collect();
-    let ssm_map = get_ssm_status(profile).unwrap_or_default();
-    Ok(flat.into_iter().map(|r| raw_to_instance(r, &ssm_map)).collect())
+    let instances: Vec<Instance> = flat.into_iter().map(|r| raw_to_instance(r, &ssm_map)).collect();
+    if filters.is_empty() {
+        *INSTANCE_CACHE.lock().unwrap_or_else(|e| e.into_inner()) = Some(InstanceCache {
+            key: cache_key(profile),
+            at: Instant::now(),
+            instances: instances.clone(),
+        });
+    }
+    Ok(instances)
 }
 
-fn raw_to_instance(raw: RawInstance, ssm_map: &HashMap<String, String>) -> Instance {
+fn raw_to_instance(raw: RawInstance, ssm_map: &HashMap<String, SsmInstanceInfo>) -> Instance {
     let name = raw
         .tags
         .as_ref()
@@ -80,7 +373,8 @@ fn raw_to_instance(raw: RawInstance, ssm_map: &HashMap<String, String>) -> Insta
         .map(|t| t.value.clone())
         .unwrap_or_default();
 
-    let ssm_status = match ssm_map.get(&raw.instance_id).map(|s| s.as_str()) {
+    let ssm = ssm_map.get(&raw.instance_id);
+    let ssm_status = match ssm.map(|s| s.ping_status.as_str()) {
         Some("Online")  => SsmStatus::Online,
         Some("Offline") => SsmStatus::Offline,
         _               => SsmStatus::Unknown,
@@ -89,6 +383,10 @@ fn raw_to_instance(raw: RawInstance, ssm_map: &HashMap<String, String>) -> Insta
     let sgs = raw.security_groups.unwrap_or_default();
     let security_group_ids = sgs.iter().map(|sg| sg.group_id.clone()).collect();
     let security_groups = sgs.into_iter().map(|sg| sg.group_name).collect();
+    let ipv6_addresses = raw.network_interfaces.unwrap_or_default().into_iter()
+        .flat_map(|eni| eni.ipv6_addresses.unwrap_or_default())
+        .map(|a| a.address)
+        .collect();
 
     Instance {
         id: raw.instance_id,
@@ -97,43 +395,374 @@ fn raw_to_instance(raw: RawInstance, ssm_map: &HashMap<String, String>) -> Insta
         state: InstanceState::from_str(&raw.state.name),
         private_ip: raw.private_ip,
         public_ip: raw.public_ip,
+        ipv6_addresses,
         ssm_status,
+        ssm_last_ping: ssm.and_then(|s| s.last_ping.clone()),
+        ssm_agent_version: ssm.and_then(|s| s.agent_version.clone()),
         tunnel: None,
         security_groups,
         security_group_ids,
+        availability_zone: raw.placement.and_then(|p| p.availability_zone),
+        launch_time: raw.launch_time,
+        tags: raw.tags.unwrap_or_default().into_iter().map(|t| (t.key, t.value)).collect(),
     }
 }
 
-pub fn get_ssm_status(profile: Option<&str>) -> Result<HashMap<String, String>> {
+pub fn get_ssm_status(profile: Option<&str>) -> Result<HashMap<String, SsmInstanceInfo>> {
     let json = run_aws(&["ssm", "describe-instance-information"], profile)?;
     let resp: SsmDescribeResponse = serde_json::from_str(&json)?;
     Ok(resp
         .instance_information_list
         .into_iter()
-        .map(|i| (i.instance_id, i.ping_status))
+        .map(|i| (i.instance_id.clone(), i))
         .collect())
 }
 
+/// SSM agents ping every few minutes; an "Online" agent silent for longer
+/// than this is probably wedged.
+pub const SSM_PING_STALE_SECS: u64 = 15 * 60;
+
+/// Seconds since the instance's SSM agent last pinged, if known.
+pub fn ssm_ping_age(inst: &Instance) -> Option<u64> {
+    let ts = parse_iso8601_to_unix(inst.ssm_last_ping.as_deref()?)?;
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).ok()?.as_secs_f64();
+    Some((now - ts).max(0.0) as u64)
+}
+
+/// Seconds a running instance has been up. `LaunchTime` is reset by every
+/// start, so this is time since the last start; None when not running.
+pub fn uptime_secs(inst: &Instance) -> Option<u64> {
+    if inst.state != InstanceState::Running {
+        return None;
+    }
+    let ts = parse_iso8601_to_unix(inst.launch_time.as_deref()?)?;
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).ok()?.as_secs_f64();
+    Some((now - ts).max(0.0) as u64)
+}
+
+/// Up for longer than `long_running_days` (0 turns the flag off).
+pub fn long_running(inst: &Instance) -> bool {
+    let days = crate::config::settings().long_running_days;
+    days > 0 && uptime_secs(inst).is_some_and(|up| up > days * 86_400)
+}
+
+/// Whether an "Online" agent has not pinged for `SSM_PING_STALE_SECS`.
+pub fn ssm_ping_stale(inst: &Instance) -> bool {
+    inst.ssm_status == SsmStatus::Online && ssm_ping_age(inst).is_some_and(|age| age > SSM_PING_STALE_SECS)
+}
+
+/// "Online (2m ago)", "Online (40m ago, stale)", "Offline (3d ago)"; add the
+/// agent version with `verbose`.
+pub fn ssm_health(inst: &Instance, verbose: bool) -> String {
+    let mut details = Vec::new();
+    if let Some(age) = ssm_ping_age(inst) {
+        details.push(format!("{} ago", short_age(age)));
+    }
+    if ssm_ping_stale(inst) {
+        details.push("stale".to_string());
+    }
+    if verbose {
+        if let Some(v) = &inst.ssm_agent_version {
+            details.push(format!("agent {}", v));
+        }
+    }
+    if details.is_empty() {
+        inst.ssm_status.as_str().to_string()
+    } else {
+        format!("{} ({})", inst.ssm_status.as_str(), details.join(", "))
+    }
+}
+
 pub fn start_instance(id: &str, profile: Option<&str>) -> Result<()> {
-    run_aws_silent(&["ec2", "start-instances", "--instance-ids", id], profile)
+    let result = run_aws_silent(&["ec2", "start-instances", "--instance-ids", id], profile);
+    invalidate_cache();
+    crate::audit::record("start", id, None, profile, &result);
+    result
 }
 
 pub fn stop_instance(id: &str, force: bool, profile: Option<&str>) -> Result<()> {
     let mut args = vec!["ec2", "stop-instances", "--instance-ids", id];
     if force { args.push("--force"); }
-    run_aws_silent(&args, profile)
+    let result = run_aws_silent(&args, profile);
+    invalidate_cache();
+    crate::audit::record(if force { "force-stop" } else { "stop" }, id, None, profile, &result);
+    result
 }
 
+/// Current state straight from `describe-instances` (bypasses the list cache).
+pub fn get_instance_state(id: &str, profile: Option<&str>) -> Result<InstanceState> {
+    let json = run_aws(
+        &["ec2", "describe-instances", "--instance-ids", id,
+          "--query", "Reservations[0].Instances[0].State.Name"],
+        profile,
+    )?;
+    let name: String = serde_json::from_str(&json)?;
+    Ok(InstanceState::from_str(&name))
+}
+
+const WAIT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Poll until the instance reaches `target`. Fails on timeout, or early if the
+/// instance is terminated.
+pub fn wait_for_state(
+    id: &str,
+    target: InstanceState,
+    timeout: Duration,
+    profile: Option<&str>,
+) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let state = get_instance_state(id, profile)?;
+        if state == target {
+            invalidate_cache();
+            return Ok(());
+        }
+        if dry_run() {
+            eprintln!("[dry-run] wait until {} is {} (currently {})", id, target.as_str(), state.as_str());
+            return Ok(());
+        }
+        if matches!(state.as_str(), "terminated" | "shutting-down") {
+            return Err(AppError::Other(format!(
+                "{} is {} — it will never become {}", id, state.as_str(), target.as_str(),
+            )));
+        }
+        if Instant::now() >= deadline {
+            return Err(AppError::Other(format!(
+                "Timed out after {}s waiting for {} to become {} (currently {})",
+                timeout.as_secs(), id, target.as_str(), state.as_str(),
+            )));
+        }
+        std::thread::sleep(WAIT_POLL_INTERVAL);
+    }
+}
+
+/// Poll SSM until the instance's agent reports `Online`.
+pub fn wait_for_ssm_online(id: &str, timeout: Duration, profile: Option<&str>) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    let filter = format!("Key=InstanceIds,Values={}", id);
+    loop {
+        let json = run_aws(&["ssm", "describe-instance-information", "--filters", &filter], profile)?;
+        let resp: SsmDescribeResponse = serde_json::from_str(&json)?;
+        let ping = resp.instance_information_list.into_iter().next().map(|i| i.ping_status);
+        if ping.as_deref() == Some("Online") {
+            invalidate_cache();
+            return Ok(());
+        }
+        if dry_run() {
+            eprintln!("[dry-run] wait until the SSM agent on {} is Online", id);
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(AppError::Other(format!(
+                "Timed out after {}s waiting for the SSM agent on {} (currently {})",
+                timeout.as_secs(), id, ping.as_deref().unwrap_or("not registered"),
+            )));
+        }
+        std::thread::sleep(WAIT_POLL_INTERVAL);
+    }
+}
+
+/// Interactive `aws ssm start-session` on the inherited terminal; blocks until
+/// the session ends. Ctrl-C goes to the session instead of killing awsx2.
+pub fn ssm_shell(id: &str, profile: Option<&str>) -> Result<std::process::ExitStatus> {
+    let mut cmd = aws_cmd(profile);
+    cmd.args(["ssm", "start-session", "--target", id]);
+    if dry_run_skip(&cmd) {
+        return Err(AppError::DryRun);
+    }
+    log_command(&cmd);
+
+    #[cfg(unix)]
+    let prev = unsafe {
+        use std::os::unix::process::CommandExt;
+        // SIG_IGN survives exec, so put the default back in the child.
+        cmd.pre_exec(|| {
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+            Ok(())
+        });
+        libc::signal(libc::SIGINT, libc::SIG_IGN)
+    };
+    let status = cmd.status();
+    #[cfg(unix)]
+    unsafe { libc::signal(libc::SIGINT, prev); }
+
+    status.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            AppError::MissingDependency("aws".into())
+        } else {
+            AppError::Io(e)
+        }
+    })
+}
+
+/// Shown when `get_console_output` returns None.
+pub const NO_CONSOLE_OUTPUT: &str =
+    "No console output yet. EC2 captures it a few minutes after boot (stopped instances keep the last capture); try again shortly.";
+
+/// The instance's serial console log (the aws CLI base64-decodes `Output`).
+/// Ok(None) while EC2 has nothing captured yet, e.g. shortly after launch.
+pub fn get_console_output(id: &str, profile: Option<&str>) -> Result<Option<String>> {
+    let args = ["ec2", "get-console-output", "--instance-id", id];
+    // `--latest` (the live buffer, not the last boot snapshot) is Nitro-only.
+    let json = match run_aws(&[&args[..], &["--latest"]].concat(), profile) {
+        Err(AppError::AwsCli(e)) if e.contains("UnsupportedOperation") => run_aws(&args, profile)?,
+        other => other?,
+    };
+    let val: serde_json::Value = serde_json::from_str(&json)?;
+    Ok(val["Output"].as_str().filter(|s| !s.trim().is_empty()).map(String::from))
+}
+
+pub fn reboot_instance(id: &str, profile: Option<&str>) -> Result<()> {
+    let result = run_aws_silent(&["ec2", "reboot-instances", "--instance-ids", id], profile);
+    invalidate_cache();
+    crate::audit::record("reboot", id, None, profile, &result);
+    result
+}
+
+pub fn terminate_instance(id: &str, profile: Option<&str>) -> Result<()> {
+    let result = run_aws_silent(&["ec2", "terminate-instances", "--instance-ids", id], profile);
+    invalidate_cache();
+    crate::audit::record("terminate", id, None, profile, &result);
+    result
+}
+
+/// Launch one instance from a launch template and tag it with `name`.
+/// `template` is a template ID (`lt-…`) or name. Returns the new instance ID.
+pub fn launch_from_template(
+    template: &str,
+    name: &str,
+    instance_type: Option<&str>,
+    profile: Option<&str>,
+) -> Result<String> {
+    let spec = if template.starts_with("lt-") {
+        format!("LaunchTemplateId={}", template)
+    } else {
+        format!("LaunchTemplateName={}", template)
+    };
+    let tags = name_tag_specification(name);
+    let mut args = vec![
+        "ec2", "run-instances",
+        "--launch-template", &spec,
+        "--count", "1",
+        "--tag-specifications", &tags,
+        "--query", "Instances[0].InstanceId",
+    ];
+    if let Some(t) = instance_type {
+        args.extend(["--instance-type", t]);
+    }
+    let json = run_aws(&args, profile);
+    invalidate_cache();
+    if dry_run() {
+        return Ok("i-(dry-run)".to_string());
+    }
+    let result = json.and_then(|j| Ok(serde_json::from_str::<String>(j.trim())?));
+    let detail = format!("name={} template={}", name, template);
+    let target = result.as_deref().unwrap_or(name);
+    crate::audit::record("launch", target, Some(&detail), profile, &result);
+    result
+}
+
+/// `--tag-specifications` naming a new instance. JSON rather than shorthand,
+/// so the name may contain `,`, `=`, `]` or `}`.
+fn name_tag_specification(name: &str) -> String {
+    serde_json::json!([{
+        "ResourceType": "instance",
+        "Tags": [{ "Key": "Name", "Value": name }],
+    }])
+    .to_string()
+}
+
+/// Resolve a `switch` target: the gpu/cpu aliases, or any `family.size` type.
+pub fn resolve_instance_type(target: &str) -> Result<String> {
+    let target = target.trim().to_lowercase();
+    match target.as_str() {
+        "gpu" => return Ok(crate::config::settings().gpu_instance_type.clone()),
+        "cpu" => return Ok(crate::config::settings().cpu_instance_type.clone()),
+        _ => {}
+    }
+    let valid = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit());
+    match target.split_once('.') {
+        Some((family, size)) if valid(family) && valid(size) => Ok(target),
+        _ => Err(AppError::Other(format!(
+            "Invalid instance type '{}'. Use 'gpu', 'cpu', or a type like c6i.xlarge.",
+            target,
+        ))),
+    }
+}
+
+/// Change the type of a stopped instance. On success the type goes to the
+/// front of the recently-used list offered by `switch`.
 pub fn modify_instance_type(id: &str, new_type: &str, profile: Option<&str>) -> Result<()> {
-    run_aws_silent(
+    let result = run_aws_silent(
         &["ec2", "modify-instance-attribute", "--instance-id", id, "--instance-type", new_type],
         profile,
-    )
+    );
+    invalidate_cache();
+    crate::audit::record("switch", id, Some(new_type), profile, &result);
+    if result.is_ok() && !dry_run() {
+        crate::config::remember_instance_type(new_type);
+    }
+    result
+}
+
+/// Split `Key=Value` into a tag pair. The value may be empty or contain `=`.
+pub fn parse_tag(spec: &str) -> Result<(String, String)> {
+    match spec.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(AppError::Other(format!(
+            "Invalid tag '{}' — expected Key=Value, e.g. Name=my-server", spec
+        ))),
+    }
+}
+
+/// Add or overwrite tags on an instance.
+pub fn create_tags(id: &str, tags: &[(String, String)], profile: Option<&str>) -> Result<()> {
+    // JSON rather than `Key=…,Value=…` shorthand, so values may contain commas.
+    let tags_json = serde_json::Value::from(
+        tags.iter()
+            .map(|(k, v)| serde_json::json!({ "Key": k, "Value": v }))
+            .collect::<Vec<_>>(),
+    ).to_string();
+    let result = run_aws_silent(&["ec2", "create-tags", "--resources", id, "--tags", &tags_json], profile);
+    invalidate_cache();
+    let detail = tags.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join(" ");
+    crate::audit::record("tag", id, Some(&detail), profile, &result);
+    result
+}
+
+/// Every instance whose Name contains `pattern` (case-insensitive), in any
+/// state. Errors only when nothing matches.
+pub fn find_instances_by_name(pattern: &str, profile: Option<&str>) -> Result<Vec<Instance>> {
+    let instances = list_instances_cached(profile)?;
+    let pat_lower = pattern.to_lowercase();
+    let matches: Vec<Instance> = instances
+        .into_iter()
+        .filter(|i| i.name.to_lowercase().contains(&pat_lower))
+        .collect();
+    if matches.is_empty() {
+        return Err(AppError::NoInstance(pattern.to_string()));
+    }
+    Ok(matches)
 }
 
+/// Resolve one instance from user input, most specific first: an instance
+/// id (`i-…`), then a Name equal to the input (ignoring case), then a Name
+/// containing it. An exact Name therefore wins even when it is a prefix of
+/// other Names (`web` vs `web-2`).
 pub fn find_instance_by_name(pattern: &str, profile: Option<&str>) -> Result<Instance> {
-    let instances = list_instances(profile)?;
+    let instances = list_instances_cached(profile)?;
+    if pattern.starts_with("i-") {
+        if let Some(inst) = instances.iter().find(|i| i.id == pattern) {
+            return Ok(inst.clone());
+        }
+    }
     let pat_lower = pattern.to_lowercase();
+    let mut exact = instances.iter().filter(|i| i.name.to_lowercase() == pat_lower);
+    if let (Some(inst), None) = (exact.next(), exact.next()) {
+        return Ok(inst.clone());
+    }
     let matches: Vec<Instance> = instances
         .into_iter()
         .filter(|i| i.name.to_lowercase().contains(&pat_lower))
@@ -141,32 +770,123 @@ pub fn find_instance_by_name(pattern: &str, profile: Option<&str>) -> Result<Ins
     match matches.len() {
         0 => Err(AppError::NoInstance(pattern.to_string())),
         1 => Ok(matches.into_iter().next().unwrap()),
-        n => Err(AppError::MultipleInstances(format!("{} ({} matches)", pattern, n))),
+        n => Err(AppError::MultipleInstances(format!(
+            "{} ({} matches: {}) — use the full Name or the instance id",
+            pattern,
+            n,
+            matches.iter().map(|i| i.name.as_str()).collect::<Vec<_>>().join(", ")
+        ))),
     }
 }
 
+/// Running instances that `bastion_name_patterns` / `bastion_tag` mark as bastions.
 pub fn find_bastions(profile: Option<&str>) -> Result<Vec<BastionInfo>> {
-    let ssm_map = get_ssm_status(profile).unwrap_or_default();
-    let instances = list_instances(profile)?;
+    let instances = list_instances_cached(profile)?;
     Ok(instances
         .into_iter()
-        .filter(|i| i.name.to_lowercase().contains("bastion") && i.state == InstanceState::Running)
+        .filter(|i| i.state == InstanceState::Running && crate::config::settings().is_bastion(&i.name, &i.tags))
         .map(|i| {
-            let ssm_online = ssm_map.get(&i.id).map(|s| s == "Online").unwrap_or(false);
-            BastionInfo { id: i.id, name: i.name, ssm_online }
+            let ssm_online = i.ssm_status == SsmStatus::Online;
+            BastionInfo { id: i.id, name: i.name, ssm_online, availability_zone: i.availability_zone }
         })
         .collect())
 }
 
+/// Upper bound on the bastion pre-ranking round trip.
+pub const BASTION_RANK_TIMEOUT: Duration = Duration::from_secs(8);
+
+/// Measure every bastion's SSM round trip in parallel and order them fastest
+/// first; bastions that did not answer keep their relative order at the end.
+/// Among those that answered, bastions in `prefer_az` (the target's AZ) come
+/// first: same-AZ traffic is faster and has no cross-AZ data charge.
+pub fn rank_bastions(
+    bastions: Vec<BastionInfo>,
+    prefer_az: Option<&str>,
+    profile: Option<&str>,
+) -> Vec<(BastionInfo, Option<Duration>)> {
+    let timings: Vec<Option<Duration>> = std::thread::scope(|s| {
+        let handles: Vec<_> = bastions
+            .iter()
+            .map(|b| s.spawn(|| ssm_round_trip(&b.id, BASTION_RANK_TIMEOUT, profile)))
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap_or(None)).collect()
+    });
+    let mut ranked: Vec<_> = bastions.into_iter().zip(timings).collect();
+    ranked.sort_by_key(|(b, rtt)| (rtt.is_none(), !in_az(b.availability_zone.as_deref(), prefer_az), *rtt));
+    ranked
+}
+
+fn in_az(az: Option<&str>, prefer_az: Option<&str>) -> bool {
+    prefer_az.is_some() && az == prefer_az
+}
+
+/// AZ of a tunnel target (instance ID, IP, or hostname): a matching instance's
+/// AZ, or else the AZ of the ENI holding the address. None when unknown, e.g.
+/// an internal name that only the VPC resolver knows.
+pub fn target_availability_zone(target: &str, profile: Option<&str>) -> Option<String> {
+    target_availability_zones(&[target.to_string()], profile).remove(target)
+}
+
+/// `target_availability_zone` for several targets at once: one instance
+/// listing, then a single ENI lookup for the addresses no instance holds.
+/// Targets whose AZ is unknown are left out.
+pub fn target_availability_zones(targets: &[String], profile: Option<&str>) -> HashMap<String, String> {
+    let mut zones = HashMap::new();
+    let Ok(instances) = list_instances_cached(profile) else { return zones };
+    let mut unplaced: Vec<(&String, Vec<String>)> = Vec::new();
+    for target in targets {
+        let az = if target.starts_with("i-") {
+            instances.iter().find(|i| &i.id == target).and_then(|i| i.availability_zone.clone())
+        } else {
+            let ips: Vec<String> = resolve_host(target).iter().map(|ip| ip.to_string()).collect();
+            let az = ips.iter().find_map(|ip| instances.iter().find(|i| i.has_ip(ip))?.availability_zone.clone());
+            if az.is_none() && !ips.is_empty() {
+                unplaced.push((target, ips));
+            }
+            az
+        };
+        if let Some(az) = az {
+            zones.insert(target.clone(), az);
+        }
+    }
+    if unplaced.is_empty() {
+        return zones;
+    }
+
+    let ips: Vec<&str> = unplaced.iter().flat_map(|(_, ips)| ips.iter().map(String::as_str)).collect();
+    let filter = format!("Name=addresses.private-ip-address,Values={}", ips.join(","));
+    let Some(val) = run_aws(&["ec2", "describe-network-interfaces", "--filters", &filter], profile)
+        .ok()
+        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+    else {
+        return zones;
+    };
+    let mut eni_zones: HashMap<&str, &str> = HashMap::new();
+    for eni in val["NetworkInterfaces"].as_array().into_iter().flatten() {
+        let Some(az) = eni["AvailabilityZone"].as_str() else { continue };
+        for addr in eni["PrivateIpAddresses"].as_array().into_iter().flatten() {
+            if let Some(ip) = addr["PrivateIpAddress"].as_str() {
+                eni_zones.insert(ip, az);
+            }
+        }
+    }
+    for (target, ips) in unplaced {
+        if let Some(az) = ips.iter().find_map(|ip| eni_zones.get(ip.as_str())) {
+            zones.insert(target.clone(), az.to_string());
+        }
+    }
+    zones
+}
+
 pub fn resolve_dns_to_target(input: &str, profile: Option<&str>) -> Result<TunnelTarget> {
     let host = strip_url_to_host(input);
-    let addrs = dns_lookup(&host);
-    let instances = list_instances(profile)?;
+    let addrs = resolve_host(&host);
+    let instances = list_instances_cached(profile)?;
 
     // Direct local IP → EC2 match
     for addr in &addrs {
         let addr_str = addr.to_string();
-        if let Some(inst) = instances.iter().find(|i| i.private_ip.as_deref() == Some(&addr_str)) {
+        if let Some(inst) = instances.iter().find(|i| i.has_ip(&addr_str)) {
             return Ok(TunnelTarget::Ec2 { instance_id: inst.id.clone(), name: inst.name.clone() });
         }
     }
@@ -175,7 +895,13 @@ pub fn resolve_dns_to_target(input: &str, profile: Option<&str>) -> Result<Tunne
     // forwarding using the hostname as-is — the bastion's DNS will resolve it.
     let bastions = find_bastions(profile)?;
     let bastion = bastions.into_iter().find(|b| b.ssm_online).ok_or(AppError::NoBastions)?;
-    let target_host = addrs.first().map(|a| a.to_string()).unwrap_or_else(|| host.to_string());
+    let ips: Vec<String> = addrs.iter().map(|a| a.to_string()).collect();
+    // Unmatched IPs are often Fargate tasks; name the task if we can see it.
+    let ecs_task = find_ecs_tasks_by_ip(&ips, profile).unwrap_or_default().into_iter().next();
+    let target_host = match &ecs_task {
+        Some(task) => task.private_ip.clone(),
+        None => ips.first().cloned().unwrap_or_else(|| host.to_string()),
+    };
     let target_port = if input.starts_with("https://") { 443u16 } else { 80u16 };
 
     Ok(TunnelTarget::RemoteViaBastion {
@@ -183,6 +909,7 @@ pub fn resolve_dns_to_target(input: &str, profile: Option<&str>) -> Result<Tunne
         bastion_name: bastion.name,
         target_host,
         target_port,
+        ecs_task,
     })
 }
 
@@ -199,82 +926,233 @@ pub fn strip_url_to_host(input: &str) -> String {
         .to_string()
 }
 
+/// Resolve with the system resolver; if that only yields loopback (an
+/// `/etc/hosts` override, e.g. from `--proxy`) or nothing, ask external DNS.
+/// Use this for every host lookup so `--proxy` never skews the answer.
+fn resolve_host(host: &str) -> Vec<std::net::IpAddr> {
+    let local = dns_lookup(host);
+    if local.iter().all(|ip| ip.is_loopback()) {
+        let external = dns_lookup_external(host);
+        if !external.is_empty() {
+            return external;
+        }
+    }
+    local
+}
+
+/// Both address families, IPv4 first (callers that need a single target take
+/// the first), without duplicates.
 fn dns_lookup(host: &str) -> Vec<std::net::IpAddr> {
     use std::net::ToSocketAddrs;
     match (host, 80u16).to_socket_addrs() {
-        Ok(addrs) => addrs.map(|a| a.ip()).collect(),
+        Ok(addrs) => ipv4_first(addrs.map(|a| a.ip())),
         Err(_) => vec![],
     }
 }
 
+fn ipv4_first(addrs: impl Iterator<Item = std::net::IpAddr>) -> Vec<std::net::IpAddr> {
+    let mut out: Vec<std::net::IpAddr> = Vec::new();
+    for addr in addrs {
+        if !out.contains(&addr) { out.push(addr); }
+    }
+    out.sort_by_key(|a| a.is_ipv6());
+    out
+}
+
 /// Resolve a hostname using an external DNS server (dig @8.8.8.8) to bypass
-/// /etc/hosts overrides (e.g. from --proxy).
+/// /etc/hosts overrides (e.g. from --proxy). Asks for both A and AAAA.
 fn dns_lookup_external(host: &str) -> Vec<std::net::IpAddr> {
     let output = match std::process::Command::new("dig")
-        .args(["+short", "@8.8.8.8", host])
+        .args(["+short", "@8.8.8.8", host, "A", host, "AAAA"])
         .output()
     {
         Ok(o) if o.status.success() => o,
         _ => return vec![],
     };
-    String::from_utf8_lossy(&output.stdout)
-        .lines()
-        .filter_map(|line| line.trim().parse::<std::net::IpAddr>().ok())
-        .collect()
+    ipv4_first(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.trim().parse::<std::net::IpAddr>().ok()),
+    )
 }
 
-/// Resolve a hostname from inside a bastion using SSM send-command + dig.
-/// Returns the resolved IPs (one per line) or an error.
-/// Note: run_aws always appends --output json, so we parse JSON throughout.
-pub fn resolve_via_bastion(bastion_id: &str, host: &str, profile: Option<&str>) -> Result<String> {
-    // Send command — run_aws appends --output json, CommandId lives at .Command.CommandId
+// ── SSM commands ──────────────────────────────────────────────────────────────
+
+/// Instance IDs with an active Session Manager session (shells and port
+/// forwards), from anyone in the account.
+pub fn active_session_targets(profile: Option<&str>) -> Result<HashSet<String>> {
+    let out = run_aws(
+        &["ssm", "describe-sessions", "--state", "Active", "--query", "Sessions[].Target"],
+        profile,
+    )?;
+    Ok(serde_json::from_str::<Vec<String>>(&out)?.into_iter().collect())
+}
+
+/// Default time to wait for `run_ssm_command` results.
+pub const SSM_COMMAND_TIMEOUT: Duration = Duration::from_secs(23);
+
+/// (profile, region override, document name).
+type DocumentKey = (Option<String>, Option<String>, String);
+
+/// `document_parameter_names` results. Bastion retries and reconnects start
+/// many sessions with the same document.
+static DOCUMENT_PARAMS: Mutex<Option<HashMap<DocumentKey, Vec<String>>>> = Mutex::new(None);
+
+/// Names of the parameters an SSM document declares, described once per run.
+pub fn document_parameter_names(document: &str, profile: Option<&str>) -> Result<Vec<String>> {
+    let (p, r) = cache_key(profile);
+    let key = (p, r, document.to_string());
+    if let Some(names) = DOCUMENT_PARAMS.lock().unwrap_or_else(|e| e.into_inner()).as_ref().and_then(|m| m.get(&key)) {
+        return Ok(names.clone());
+    }
+    let json = run_aws(
+        &["ssm", "describe-document", "--name", document, "--query", "Document.Parameters[].Name"],
+        profile,
+    )?;
+    let names: Option<Vec<String>> = serde_json::from_str(&json)?;
+    let names = names.unwrap_or_default();
+    DOCUMENT_PARAMS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(HashMap::new)
+        .insert(key, names.clone());
+    Ok(names)
+}
+
+/// Run a shell command on an instance via SSM send-command and poll
+/// get-command-invocation until it finishes. SSM truncates stdout to 24 000
+/// characters.
+pub fn run_ssm_command_output(
+    instance_id: &str,
+    command: &str,
+    timeout: Duration,
+    profile: Option<&str>,
+) -> Result<SsmCommandOutput> {
+    // Give SSM a moment to dispatch before polling
+    ssm_command_poll(instance_id, command, timeout, Duration::from_secs(3), Duration::from_secs(2), profile)
+}
+
+fn ssm_command_poll(
+    instance_id: &str,
+    command: &str,
+    timeout: Duration,
+    first_wait: Duration,
+    interval: Duration,
+    profile: Option<&str>,
+) -> Result<SsmCommandOutput> {
+    // JSON parameters so quotes and commas in `command` survive intact.
+    let params = serde_json::json!({ "commands": [command] }).to_string();
+    // run_aws appends --output json, CommandId lives at .Command.CommandId
     let send_json = run_aws(
         &[
             "ssm", "send-command",
-            "--instance-ids", bastion_id,
+            "--instance-ids", instance_id,
             "--document-name", "AWS-RunShellScript",
-            "--parameters", &format!(
-                "commands=[\"dig +short {h} 2>/dev/null || host {h} 2>/dev/null | awk '/has address/{{print $4}}' || echo FAIL\"]",
-                h = host
-            ),
+            "--parameters", &params,
         ],
         profile,
     )?;
+    if dry_run() {
+        return Ok(SsmCommandOutput {
+            status: "Success".to_string(),
+            exit_code: Some(0),
+            stdout: String::new(),
+            stderr: String::new(),
+        });
+    }
     let send_val: serde_json::Value = serde_json::from_str(&send_json)?;
     let command_id = send_val["Command"]["CommandId"]
         .as_str()
         .ok_or_else(|| AppError::AwsCli("send-command: no CommandId".to_string()))?
         .to_string();
 
-    // Give SSM a moment to dispatch before polling
-    std::thread::sleep(std::time::Duration::from_secs(3));
+    let deadline = Instant::now() + timeout;
+    std::thread::sleep(first_wait.min(timeout));
 
-    // Poll up to 10 × 2s = 20s
-    for _ in 0..10 {
-        let inv_json = run_aws(
+    loop {
+        // Errors (e.g. InvocationDoesNotExist right after sending) just mean "not yet".
+        let inv = run_aws(
             &[
                 "ssm", "get-command-invocation",
                 "--command-id", &command_id,
-                "--instance-id", bastion_id,
+                "--instance-id", instance_id,
             ],
             profile,
-        );
-        if let Ok(j) = inv_json {
-            if let Ok(val) = serde_json::from_str::<serde_json::Value>(&j) {
-                let status = val["Status"].as_str().unwrap_or("");
-                if status == "Success" || status == "Failed" {
-                    let out = val["StandardOutputContent"].as_str().unwrap_or("").trim().to_string();
-                    return Ok(out);
-                }
+        )
+        .ok()
+        .and_then(|j| serde_json::from_str::<serde_json::Value>(&j).ok());
+        if let Some(val) = inv {
+            let status = val["Status"].as_str().unwrap_or("");
+            if matches!(status, "Success" | "Failed" | "Cancelled" | "TimedOut") {
+                return Ok(SsmCommandOutput {
+                    status: status.to_string(),
+                    exit_code: val["ResponseCode"].as_i64().filter(|c| *c >= 0).map(|c| c as i32),
+                    stdout: val["StandardOutputContent"].as_str().unwrap_or("").to_string(),
+                    stderr: val["StandardErrorContent"].as_str().unwrap_or("").to_string(),
+                });
             }
         }
-        std::thread::sleep(std::time::Duration::from_secs(2));
+        if Instant::now() >= deadline {
+            return Err(AppError::AwsCli(format!(
+                "SSM command {} timed out after {} s", command_id, timeout.as_secs(),
+            )));
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+/// Trimmed stdout of `command` run on the instance (whether or not it failed).
+pub fn run_ssm_command(instance_id: &str, command: &str, profile: Option<&str>) -> Result<String> {
+    run_ssm_command_output(instance_id, command, SSM_COMMAND_TIMEOUT, profile)
+        .map(|out| out.stdout.trim().to_string())
+}
+
+/// Time for a no-op SSM command to come back from the instance, polled finely
+/// enough to compare agents. None if it did not succeed within `timeout`.
+pub fn ssm_round_trip(instance_id: &str, timeout: Duration, profile: Option<&str>) -> Option<Duration> {
+    let started = Instant::now();
+    let poll = Duration::from_millis(500);
+    match ssm_command_poll(instance_id, "true", timeout, poll, poll, profile) {
+        Ok(out) if out.status == "Success" => Some(started.elapsed()),
+        _ => None,
     }
-    Err(AppError::AwsCli("SSM command timed out after 23 s".to_string()))
+}
+
+/// Reject a host that is not a plain hostname or IP before it is pasted into a
+/// shell script run as root on a bastion.
+fn check_remote_host(host: &str) -> Result<()> {
+    let plain = !host.is_empty()
+        && !host.starts_with('-')
+        && host.chars().all(|c| c.is_ascii_alphanumeric() || ".-:".contains(c));
+    if plain {
+        Ok(())
+    } else {
+        Err(AppError::Other(format!("Invalid remote host '{}': use a hostname or IP address.", host)))
+    }
+}
+
+/// Resolve a hostname from inside a bastion using SSM send-command + dig.
+/// Returns the resolved IPs (one per line) or an error.
+pub fn resolve_via_bastion(bastion_id: &str, host: &str, profile: Option<&str>) -> Result<String> {
+    check_remote_host(host)?;
+    run_ssm_command(
+        bastion_id,
+        &format!(
+            "dig +short {h} A {h} AAAA 2>/dev/null || host {h} 2>/dev/null | awk '/has (IPv6 )?address/{{print $NF}}' || echo FAIL",
+            h = host
+        ),
+        profile,
+    )
 }
 
 pub fn sso_login(profile: Option<&str>) -> Result<()> {
-    let status = aws_cmd(profile).args(["sso", "login"]).status()?;
+    let mut cmd = aws_cmd(profile);
+    cmd.args(["sso", "login"]);
+    if dry_run_skip(&cmd) {
+        return Ok(());
+    }
+    log_command(&cmd);
+    let status = cmd.status()?;
     if !status.success() {
         return Err(AppError::AwsCli("aws sso login failed".to_string()));
     }
@@ -292,6 +1170,7 @@ pub fn get_caller_identity(profile: Option<&str>) -> Result<String> {
 }
 
 pub fn get_region(profile: Option<&str>) -> String {
+    if let Some(r) = region_override() { return r; }
     if let Ok(r) = std::env::var("AWS_DEFAULT_REGION") { if !r.is_empty() { return r; } }
     if let Ok(r) = std::env::var("AWS_REGION") { if !r.is_empty() { return r; } }
     if let Ok(o) = aws_cmd(profile).args(["configure", "get", "region"]).output() {
@@ -329,20 +1208,172 @@ pub fn list_profiles() -> Vec<String> {
     profiles.into_iter().collect()
 }
 
+/// Enabled regions are re-fetched after a week.
+const REGION_CACHE_TTL_SECS: u64 = 7 * 24 * 3600;
+
+/// Regions enabled for the account, sorted. `describe-regions` only lists
+/// opted-in regions by default. Cached per profile in `regions.json`.
+pub fn enabled_regions(profile: Option<&str>) -> Result<Vec<String>> {
+    let key = profile.map(str::to_string).unwrap_or_else(get_profile);
+    let path = crate::config::config_dir().join("regions.json");
+    let mut cache: HashMap<String, (u64, Vec<String>)> = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    if let Some((at, regions)) = cache.get(&key) {
+        if now.saturating_sub(*at) < REGION_CACHE_TTL_SECS && !regions.is_empty() {
+            return Ok(regions.clone());
+        }
+    }
+
+    let out = run_aws(&["ec2", "describe-regions", "--query", "Regions[].RegionName"], profile)?;
+    let mut regions: Vec<String> = serde_json::from_str(&out)?;
+    regions.sort();
+    if !regions.is_empty() {
+        cache.insert(key, (now, regions.clone()));
+        if let Ok(json) = serde_json::to_string_pretty(&cache) {
+            let _ = std::fs::create_dir_all(crate::config::config_dir()).and_then(|_| std::fs::write(&path, json));
+        }
+    }
+    Ok(regions)
+}
+
+// ── EBS ──────────────────────────────────────────────────────────────────────
+
+/// EBS volumes attached to an instance, ordered by device name.
+pub fn get_instance_volumes(instance_id: &str, profile: Option<&str>) -> Result<Vec<VolumeInfo>> {
+    let filter = format!("Name=attachment.instance-id,Values={}", instance_id);
+    let json = run_aws(&["ec2", "describe-volumes", "--filters", &filter], profile)?;
+    let val: serde_json::Value = serde_json::from_str(&json)?;
+    let mut volumes: Vec<VolumeInfo> = val["Volumes"].as_array().into_iter().flatten()
+        .map(|v| VolumeInfo {
+            device: v["Attachments"].as_array().into_iter().flatten()
+                .find(|a| a["InstanceId"] == instance_id)
+                .and_then(|a| a["Device"].as_str())
+                .unwrap_or("-")
+                .to_string(),
+            volume_id: v["VolumeId"].as_str().unwrap_or_default().to_string(),
+            size_gib: v["Size"].as_u64().unwrap_or(0),
+            volume_type: v["VolumeType"].as_str().unwrap_or("-").to_string(),
+            iops: v["Iops"].as_u64(),
+            state: v["State"].as_str().unwrap_or("-").to_string(),
+        })
+        .collect();
+    volumes.sort_by(|a, b| a.device.cmp(&b.device));
+    Ok(volumes)
+}
+
+/// Start a snapshot of a volume and return its ID. It completes in the
+/// background, but captures the data as of this call.
+pub fn create_snapshot(volume_id: &str, description: &str, profile: Option<&str>) -> Result<String> {
+    let json = run_aws(
+        &["ec2", "create-snapshot", "--volume-id", volume_id,
+          "--description", description, "--query", "SnapshotId"],
+        profile,
+    );
+    if dry_run() {
+        return Ok("snap-(dry-run)".to_string());
+    }
+    let result = json.and_then(|j| Ok(serde_json::from_str::<String>(j.trim())?));
+    crate::audit::record("snapshot", volume_id, result.as_deref().ok(), profile, &result);
+    result
+}
+
+/// `(snapshot_id, state, progress)` for each snapshot, e.g.
+/// `("snap-…", "pending", "42%")`.
+pub fn snapshot_progress(ids: &[String], profile: Option<&str>) -> Result<Vec<(String, String, String)>> {
+    let mut args = vec!["ec2", "describe-snapshots", "--snapshot-ids"];
+    args.extend(ids.iter().map(String::as_str));
+    let json = run_aws(&args, profile)?;
+    let val: serde_json::Value = serde_json::from_str(&json)?;
+    Ok(val["Snapshots"].as_array().into_iter().flatten()
+        .map(|s| (
+            s["SnapshotId"].as_str().unwrap_or_default().to_string(),
+            s["State"].as_str().unwrap_or("-").to_string(),
+            s["Progress"].as_str().unwrap_or("-").to_string(),
+        ))
+        .collect())
+}
+
+// ── ECS / Fargate ────────────────────────────────────────────────────────────
+
+/// `describe-tasks` accepts at most this many task ARNs per call.
+const ECS_DESCRIBE_BATCH: usize = 100;
+
+/// Running ECS tasks (across all clusters) whose ENI private IP is in `ips`.
+pub fn find_ecs_tasks_by_ip(ips: &[String], profile: Option<&str>) -> Result<Vec<EcsTask>> {
+    if ips.is_empty() {
+        return Ok(vec![]);
+    }
+    let clusters: serde_json::Value = serde_json::from_str(&run_aws(&["ecs", "list-clusters"], profile)?)?;
+    let mut found = Vec::new();
+
+    for cluster in clusters["clusterArns"].as_array().into_iter().flatten().filter_map(|c| c.as_str()) {
+        let tasks: serde_json::Value = serde_json::from_str(&run_aws(
+            &["ecs", "list-tasks", "--cluster", cluster, "--desired-status", "RUNNING"],
+            profile,
+        )?)?;
+        let arns: Vec<&str> = tasks["taskArns"].as_array().into_iter().flatten()
+            .filter_map(|t| t.as_str())
+            .collect();
+
+        for batch in arns.chunks(ECS_DESCRIBE_BATCH) {
+            let mut args = vec!["ecs", "describe-tasks", "--cluster", cluster, "--tasks"];
+            args.extend_from_slice(batch);
+            let described: serde_json::Value = serde_json::from_str(&run_aws(&args, profile)?)?;
+            for task in described["tasks"].as_array().into_iter().flatten() {
+                let Some(ip) = ecs_task_ips(task).into_iter()
+                    .find(|own| ips.iter().any(|ip| same_ip(own, ip)))
+                    else { continue };
+                let task_arn = task["taskArn"].as_str().unwrap_or_default().to_string();
+                found.push(EcsTask {
+                    cluster: cluster.rsplit('/').next().unwrap_or(cluster).to_string(),
+                    task_id: task_arn.rsplit('/').next().unwrap_or_default().to_string(),
+                    task_arn,
+                    service: task["group"].as_str()
+                        .and_then(|g| g.strip_prefix("service:"))
+                        .map(String::from),
+                    launch_type: task["launchType"].as_str().unwrap_or("ECS").to_string(),
+                    private_ip: ip,
+                });
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// Private IPv4 and IPv6 addresses of a task's ENI (awsvpc mode: Fargate, and
+/// EC2 tasks using it), IPv4 first.
+fn ecs_task_ips(task: &serde_json::Value) -> Vec<String> {
+    let mut ips: Vec<String> = task["attachments"].as_array().into_iter().flatten()
+        .flat_map(|a| a["details"].as_array().into_iter().flatten())
+        .filter(|d| d["name"] == "privateIPv4Address" || d["name"] == "ipv6Address")
+        .filter_map(|d| d["value"].as_str())
+        .map(String::from)
+        .collect();
+    if ips.is_empty() {
+        ips = task["containers"].as_array().into_iter().flatten()
+            .flat_map(|c| c["networkInterfaces"].as_array().into_iter().flatten())
+            .flat_map(|n| [n["privateIpv4Address"].as_str(), n["ipv6Address"].as_str()])
+            .flatten()
+            .map(String::from)
+            .collect();
+    }
+    ips.sort();
+    ips.dedup();
+    ips.sort_by_key(|ip| ip.contains(':'));
+    ips
+}
+
 // ── ALB-aware tunnel resolution ──────────────────────────────────────────────
 
 /// Find an ALB whose DNS resolves to the same IPs as the given hostname.
 /// Returns the ALB ARN if found.
 pub fn find_alb_for_hostname(host: &str, profile: Option<&str>) -> Result<Option<String>> {
-    let mut resolved = dns_lookup(host);
-    // If /etc/hosts overrides to loopback (e.g. from --proxy), use external DNS
-    if resolved.iter().all(|ip| ip.is_loopback()) {
-        let external = dns_lookup_external(host);
-        if !external.is_empty() {
-            resolved = external;
-        }
-    }
-    let target_ips: HashSet<String> = resolved
+    let target_ips: HashSet<String> = resolve_host(host)
         .into_iter()
         .filter(|a| !a.is_loopback())
         .map(|a| a.to_string())
@@ -359,7 +1390,7 @@ pub fn find_alb_for_hostname(host: &str, profile: Option<&str>) -> Result<Option
     for alb in albs {
         let dns_name = alb["DNSName"].as_str().unwrap_or("");
         if dns_name.is_empty() { continue; }
-        let alb_ips: HashSet<String> = dns_lookup(dns_name)
+        let alb_ips: HashSet<String> = resolve_host(dns_name)
             .into_iter()
             .map(|a| a.to_string())
             .collect();
@@ -386,37 +1417,76 @@ pub fn get_alb_healthy_targets(
     )?;
     let val: serde_json::Value = serde_json::from_str(&json)?;
     let empty = Vec::new();
-    let tgs = val["TargetGroups"].as_array().unwrap_or(&empty);
+    let tgs: Vec<(&str, u16)> = val["TargetGroups"]
+        .as_array()
+        .unwrap_or(&empty)
+        .iter()
+        .filter_map(|tg| {
+            let arn = tg["TargetGroupArn"].as_str()?;
+            Some((arn, tg["Port"].as_u64().unwrap_or(0) as u16))
+        })
+        .collect();
+    if tgs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // One describe-target-health per group — fan them out over a few workers,
+    // then concatenate in the original group order.
+    let chunk_size = tgs.len().div_ceil(HEALTH_WORKERS);
+    let per_chunk: Vec<Result<Vec<(String, u16)>>> = std::thread::scope(|s| {
+        let handles: Vec<_> = tgs
+            .chunks(chunk_size)
+            .map(|chunk| s.spawn(move || {
+                let mut out = Vec::new();
+                for &(tg_arn, tg_port) in chunk {
+                    out.extend(healthy_targets_in_group(tg_arn, tg_port, remote_port, profile)?);
+                }
+                Ok(out)
+            }))
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap_or_else(|_| Err(AppError::Other("target health worker panicked".into()))))
+            .collect()
+    });
 
     let mut targets = Vec::new();
-    for tg in tgs {
-        let tg_arn = match tg["TargetGroupArn"].as_str() {
-            Some(a) => a,
+    for chunk in per_chunk {
+        targets.extend(chunk?);
+    }
+    Ok(targets)
+}
+
+/// Max concurrent `describe-target-health` calls in `get_alb_healthy_targets`.
+const HEALTH_WORKERS: usize = 8;
+
+fn healthy_targets_in_group(
+    tg_arn: &str,
+    tg_port: u16,
+    remote_port: Option<u16>,
+    profile: Option<&str>,
+) -> Result<Vec<(String, u16)>> {
+    let health_json = run_aws(
+        &["elbv2", "describe-target-health", "--target-group-arn", tg_arn],
+        profile,
+    )?;
+    let health_val: serde_json::Value = serde_json::from_str(&health_json)?;
+    let empty = Vec::new();
+    let descs = health_val["TargetHealthDescriptions"].as_array().unwrap_or(&empty);
+
+    let mut targets = Vec::new();
+    for desc in descs {
+        let state = desc["TargetHealth"]["State"].as_str().unwrap_or("");
+        if state != "healthy" { continue; }
+        let id = match desc["Target"]["Id"].as_str() {
+            Some(id) => id.to_string(),
             None => continue,
         };
-        let tg_port = tg["Port"].as_u64().unwrap_or(0) as u16;
-
-        let health_json = run_aws(
-            &["elbv2", "describe-target-health", "--target-group-arn", tg_arn],
-            profile,
-        )?;
-        let health_val: serde_json::Value = serde_json::from_str(&health_json)?;
-        let empty2 = Vec::new();
-        let descs = health_val["TargetHealthDescriptions"].as_array().unwrap_or(&empty2);
-
-        for desc in descs {
-            let state = desc["TargetHealth"]["State"].as_str().unwrap_or("");
-            if state != "healthy" { continue; }
-            let id = match desc["Target"]["Id"].as_str() {
-                Some(id) => id.to_string(),
-                None => continue,
-            };
-            let port = desc["Target"]["Port"].as_u64().unwrap_or(tg_port as u64) as u16;
-            if let Some(rp) = remote_port {
-                if port != rp { continue; }
-            }
-            targets.push((id, port));
+        let port = desc["Target"]["Port"].as_u64().unwrap_or(tg_port as u64) as u16;
+        if let Some(rp) = remote_port {
+            if port != rp { continue; }
         }
+        targets.push((id, port));
     }
     Ok(targets)
 }
@@ -493,14 +1563,189 @@ pub fn get_allowed_source_sgs(sg_ids: &[String], port: u16, profile: Option<&str
 }
 
 /// Find an SSM-online, running EC2 instance that belongs to one of the given
-/// security groups.
-pub fn find_ssm_hop_by_sgs(allowed_sg_ids: &[String], profile: Option<&str>) -> Result<Option<Instance>> {
+/// security groups, preferring one in `prefer_az`.
+pub fn find_ssm_hop_by_sgs(
+    allowed_sg_ids: &[String],
+    prefer_az: Option<&str>,
+    profile: Option<&str>,
+) -> Result<Option<Instance>> {
     let allowed_set: HashSet<&str> = allowed_sg_ids.iter().map(|s| s.as_str()).collect();
-    let instances = list_instances(profile)?;
-    Ok(instances
+    let instances = list_instances_cached(profile)?;
+    let mut hops: Vec<Instance> = instances
+        .into_iter()
+        .filter(|i| i.ssm_status == SsmStatus::Online && i.state == InstanceState::Running)
+        .filter(|i| i.security_group_ids.iter().any(|sg| allowed_set.contains(sg.as_str())))
+        .collect();
+    hops.sort_by_key(|i| !in_az(i.availability_zone.as_deref(), prefer_az));
+    Ok(hops.into_iter().next())
+}
+
+/// An instance to forward through to `host:port` when there is no bastion:
+/// one whose security groups the target's SGs allow on `port`, else any
+/// running SSM-online instance. Both prefer the target's AZ. The flag is true
+/// when the hop was found through SG rules.
+pub fn find_ssm_hop_for_host(host: &str, port: u16, profile: Option<&str>) -> Result<Option<(Instance, bool)>> {
+    let target_az = target_availability_zone(host, profile);
+    let target_sgs = resolve_host(host)
+        .iter()
+        .find_map(|ip| get_target_sg_ids(&ip.to_string(), profile).ok().filter(|sgs| !sgs.is_empty()))
+        .unwrap_or_default();
+    let allowed = get_allowed_source_sgs(&target_sgs, port, profile).unwrap_or_default();
+    if let Some(hop) = find_ssm_hop_by_sgs(&allowed, target_az.as_deref(), profile)? {
+        return Ok(Some((hop, true)));
+    }
+    let mut online: Vec<Instance> = list_instances_cached(profile)?
         .into_iter()
         .filter(|i| i.ssm_status == SsmStatus::Online && i.state == InstanceState::Running)
-        .find(|i| i.security_group_ids.iter().any(|sg| allowed_set.contains(sg.as_str()))))
+        .collect();
+    online.sort_by_key(|i| !in_az(i.availability_zone.as_deref(), target_az.as_deref()));
+    Ok(online.into_iter().next().map(|i| (i, false)))
+}
+
+/// One step of the ALB → healthy target → target SGs → allowed source SGs →
+/// SSM hop walk, as `resolve_alb_hop` reports it.
+pub enum AlbStep<'a> {
+    /// The ALB sharing an IP with the host, if any.
+    Alb(Option<&'a str>),
+    HealthyTargets(usize),
+    Target { target: &'a str, port: u16 },
+    TargetSgs(&'a [String]),
+    AllowedSgs { port: u16, sgs: &'a [String] },
+    TargetAz(&'a str),
+    /// No running, SSM-online instance in the allowed SGs.
+    NoHop,
+    /// A lookup failed; the walk skips to the next target, or gives up
+    /// before any target is reached.
+    Failed { step: &'static str, error: &'a AppError },
+}
+
+/// The instance an ALB-fronted host is tunneled through, and the healthy
+/// target behind the ALB it forwards to.
+pub struct AlbHop {
+    pub hop: Instance,
+    pub target: String,
+    pub port: u16,
+    pub target_az: Option<String>,
+}
+
+/// Resolve the hop URL tunnels try before bastions: the first healthy target
+/// of the host's ALB whose SGs admit an SSM-online instance, preferring one in
+/// the target's AZ. `step` sees each step; `cancelled` is checked between
+/// targets. Ok(None) when there is no ALB path; only expired credentials are
+/// an error.
+pub fn resolve_alb_hop<F, C>(
+    host: &str,
+    remote_port: Option<u16>,
+    profile: Option<&str>,
+    mut step: F,
+    cancelled: C,
+) -> Result<Option<AlbHop>>
+where
+    F: FnMut(AlbStep),
+    C: Fn() -> bool,
+{
+    let alb_arn = match find_alb_for_hostname(host, profile) {
+        Ok(arn) => arn,
+        Err(AppError::CredentialsExpired) => return Err(AppError::CredentialsExpired),
+        Err(error) => {
+            step(AlbStep::Failed { step: "ALB lookup", error: &error });
+            return Ok(None);
+        }
+    };
+    step(AlbStep::Alb(alb_arn.as_deref()));
+    let Some(alb_arn) = alb_arn else { return Ok(None) };
+
+    let targets = get_alb_healthy_targets(&alb_arn, remote_port, profile).unwrap_or_else(|error| {
+        step(AlbStep::Failed { step: "Target health lookup", error: &error });
+        Vec::new()
+    });
+    step(AlbStep::HealthyTargets(targets.len()));
+    let ids: Vec<String> = targets.iter().map(|(target, _)| target.clone()).collect();
+    let zones = if ids.is_empty() { HashMap::new() } else { target_availability_zones(&ids, profile) };
+
+    for (target, port) in targets {
+        if cancelled() {
+            return Ok(None);
+        }
+        step(AlbStep::Target { target: &target, port });
+        let target_sgs = match get_target_sg_ids(&target, profile) {
+            Ok(sgs) => sgs,
+            Err(error) => { step(AlbStep::Failed { step: "Target SGs", error: &error }); continue; }
+        };
+        step(AlbStep::TargetSgs(&target_sgs));
+        if target_sgs.is_empty() { continue; }
+        let allowed = match get_allowed_source_sgs(&target_sgs, port, profile) {
+            Ok(sgs) => sgs,
+            Err(error) => { step(AlbStep::Failed { step: "Allowed source SGs", error: &error }); continue; }
+        };
+        step(AlbStep::AllowedSgs { port, sgs: &allowed });
+        if allowed.is_empty() { continue; }
+        let target_az = zones.get(&target).cloned();
+        if let Some(az) = &target_az {
+            step(AlbStep::TargetAz(az));
+        }
+        match find_ssm_hop_by_sgs(&allowed, target_az.as_deref(), profile) {
+            Ok(Some(hop)) => return Ok(Some(AlbHop { hop, target, port, target_az })),
+            Ok(None) => step(AlbStep::NoHop),
+            Err(error) => step(AlbStep::Failed { step: "Hop", error: &error }),
+        }
+    }
+    Ok(None)
+}
+
+/// Walk the chain `resolve_alb_hop` follows for URL tunnels, reporting each
+/// step instead of tunneling. Errors the tunnel path silently skips are
+/// shown inline.
+pub fn explain_alb_path(input: &str, profile: Option<&str>) -> Result<String> {
+    use std::fmt::Write as _;
+    let host = strip_url_to_host(input);
+    let mut out = String::new();
+    writeln!(out, "ALB path for: {}", host).ok();
+    let ips: Vec<String> = resolve_host(&host).iter().map(|ip| ip.to_string()).collect();
+    writeln!(out, "  DNS (local): {}",
+        if ips.is_empty() { "not resolvable".to_string() } else { ips.join(", ") }).ok();
+
+    let mut reached_targets = false;
+    let resolved = resolve_alb_hop(&host, None, profile, |step| {
+        match step {
+            AlbStep::Alb(Some(arn)) => writeln!(out, "  ALB: {}", arn),
+            AlbStep::Alb(None) => writeln!(out, "  ALB: none shares an IP with {}", host),
+            AlbStep::HealthyTargets(0) => writeln!(out, "  Healthy targets: none"),
+            AlbStep::HealthyTargets(n) => {
+                reached_targets = true;
+                writeln!(out, "  Healthy targets: {}", n)
+            }
+            AlbStep::Target { target, port } => writeln!(out, "\n  Target {}:{}", target, port),
+            AlbStep::TargetSgs([]) => writeln!(out, "    Target SGs: none found (no ENI?)"),
+            AlbStep::TargetSgs(sgs) => writeln!(out, "    Target SGs: {}", sgs.join(", ")),
+            AlbStep::AllowedSgs { port, sgs: [] } => {
+                writeln!(out, "    Allowed source SGs on {}: none (only CIDR rules?)", port)
+            }
+            AlbStep::AllowedSgs { port, sgs } => {
+                writeln!(out, "    Allowed source SGs on {}: {}", port, sgs.join(", "))
+            }
+            AlbStep::TargetAz(az) => writeln!(out, "    Target AZ: {}", az),
+            AlbStep::NoHop => writeln!(out, "    Hop: no running, SSM-online instance in those SGs"),
+            AlbStep::Failed { step, error } if reached_targets => {
+                writeln!(out, "    {}: lookup failed: {}", step, error)
+            }
+            AlbStep::Failed { step, error } => writeln!(out, "  {} failed: {}", step, error),
+        }
+        .ok();
+    }, || false)?;
+
+    match resolved {
+        Some(AlbHop { hop, target, port, .. }) => {
+            writeln!(out, "    Hop: {} ({}, {})", hop.name, hop.id,
+                hop.availability_zone.as_deref().unwrap_or("AZ unknown")).ok();
+            writeln!(out, "\n  → Tunnels go via {} to {}:{}.", hop.name, target, port).ok();
+        }
+        None if reached_targets => {
+            writeln!(out, "\n  → No target has a usable hop; tunnels fall back to bastions.").ok();
+        }
+        None => { writeln!(out, "\n  → Tunnels fall back to bastions.").ok(); }
+    }
+    Ok(out)
 }
 
 /// Common service ports to probe when auto-detecting.
@@ -515,6 +1760,7 @@ pub fn probe_ports_via_bastion(
     ports: &[u16],
     profile: Option<&str>,
 ) -> Result<Vec<u16>> {
+    check_remote_host(host)?;
     let checks: Vec<String> = ports
         .iter()
         .map(|p| format!(
@@ -524,51 +1770,11 @@ pub fn probe_ports_via_bastion(
         .collect();
     let script = checks.join("; ");
 
-    let send_json = run_aws(
-        &[
-            "ssm", "send-command",
-            "--instance-ids", bastion_id,
-            "--document-name", "AWS-RunShellScript",
-            "--parameters", &format!("commands=[\"{}\"]", script),
-        ],
-        profile,
-    )?;
-    let send_val: serde_json::Value = serde_json::from_str(&send_json)?;
-    let command_id = send_val["Command"]["CommandId"]
-        .as_str()
-        .ok_or_else(|| AppError::AwsCli("send-command: no CommandId".to_string()))?
-        .to_string();
-
-    std::thread::sleep(std::time::Duration::from_secs(3));
-
-    for _ in 0..10 {
-        let inv_json = run_aws(
-            &[
-                "ssm", "get-command-invocation",
-                "--command-id", &command_id,
-                "--instance-id", bastion_id,
-            ],
-            profile,
-        );
-        if let Ok(j) = inv_json {
-            if let Ok(val) = serde_json::from_str::<serde_json::Value>(&j) {
-                let status = val["Status"].as_str().unwrap_or("");
-                if status == "Success" || status == "Failed" {
-                    let out = val["StandardOutputContent"]
-                        .as_str()
-                        .unwrap_or("")
-                        .trim()
-                        .to_string();
-                    return Ok(out
-                        .lines()
-                        .filter_map(|line| line.trim().parse::<u16>().ok())
-                        .collect());
-                }
-            }
-        }
-        std::thread::sleep(std::time::Duration::from_secs(2));
-    }
-    Err(AppError::AwsCli("Port probe timed out after 23 s".to_string()))
+    let out = run_ssm_command(bastion_id, &script, profile)?;
+    Ok(out
+        .lines()
+        .filter_map(|line| line.trim().parse::<u16>().ok())
+        .collect())
 }
 
 // ── ECR ───────────────────────────────────────────────────────────────────────
@@ -600,38 +1806,53 @@ impl EcrImage {
 
     /// Relative time string like "3 weeks ago".
     pub fn relative_pushed_at(&self) -> String {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_secs_f64())
-            .unwrap_or(self.pushed_at);
-        let secs = (now - self.pushed_at).max(0.0) as u64;
-        if secs < 60 {
-            "just now".to_string()
-        } else if secs < 3_600 {
-            let m = secs / 60;
-            format!("{} minute{} ago", m, if m == 1 { "" } else { "s" })
-        } else if secs < 86_400 {
-            let h = secs / 3_600;
-            format!("{} hour{} ago", h, if h == 1 { "" } else { "s" })
-        } else if secs < 604_800 {
-            let d = secs / 86_400;
-            format!("{} day{} ago", d, if d == 1 { "" } else { "s" })
-        } else if secs < 2_592_000 {
-            let w = secs / 604_800;
-            format!("{} week{} ago", w, if w == 1 { "" } else { "s" })
-        } else if secs < 31_536_000 {
-            let mo = secs / 2_592_000;
-            format!("{} month{} ago", mo, if mo == 1 { "" } else { "s" })
-        } else {
-            let y = secs / 31_536_000;
-            format!("{} year{} ago", y, if y == 1 { "" } else { "s" })
-        }
+        relative_time(self.pushed_at)
+    }
+}
+
+/// Compact age like "45s", "2m", "3h" or "4d".
+pub fn short_age(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3_599 => format!("{}m", secs / 60),
+        3_600..=86_399 => format!("{}h", secs / 3_600),
+        _ => format!("{}d", secs / 86_400),
+    }
+}
+
+/// Relative time string like "3 weeks ago" for a Unix timestamp in seconds.
+pub fn relative_time(ts: f64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(ts);
+    let secs = (now - ts).max(0.0) as u64;
+    if secs < 60 {
+        "just now".to_string()
+    } else if secs < 3_600 {
+        let m = secs / 60;
+        format!("{} minute{} ago", m, if m == 1 { "" } else { "s" })
+    } else if secs < 86_400 {
+        let h = secs / 3_600;
+        format!("{} hour{} ago", h, if h == 1 { "" } else { "s" })
+    } else if secs < 604_800 {
+        let d = secs / 86_400;
+        format!("{} day{} ago", d, if d == 1 { "" } else { "s" })
+    } else if secs < 2_592_000 {
+        let w = secs / 604_800;
+        format!("{} week{} ago", w, if w == 1 { "" } else { "s" })
+    } else if secs < 31_536_000 {
+        let mo = secs / 2_592_000;
+        format!("{} month{} ago", mo, if mo == 1 { "" } else { "s" })
+    } else {
+        let y = secs / 31_536_000;
+        format!("{} year{} ago", y, if y == 1 { "" } else { "s" })
     }
 }
 
 /// Parse an ISO 8601 datetime string (e.g. `"2024-01-15T10:30:00+00:00"`) to a
 /// Unix timestamp in seconds.  No external crates required.
-fn parse_iso8601_to_unix(s: &str) -> Option<f64> {
+pub fn parse_iso8601_to_unix(s: &str) -> Option<f64> {
     if s.len() < 19 { return None; }
     let year:  i64 = s[0..4].parse().ok()?;
     let month: i64 = s[5..7].parse().ok()?;
@@ -643,7 +1864,7 @@ fn parse_iso8601_to_unix(s: &str) -> Option<f64> {
     // Skip optional fractional seconds, then read timezone
     let rest = &s[19..];
     let tz_rest = if rest.starts_with('.') {
-        let end = rest.find(|c: char| c == '+' || c == '-' || c == 'Z').unwrap_or(rest.len());
+        let end = rest.find(['+', '-', 'Z']).unwrap_or(rest.len());
         &rest[end..]
     } else {
         rest
@@ -672,6 +1893,26 @@ fn parse_iso8601_to_unix(s: &str) -> Option<f64> {
     Some(((days - epoch) * 86_400 + hour * 3_600 + min * 60 + sec - tz_offset_secs) as f64)
 }
 
+/// Format a Unix timestamp as UTC ISO 8601, e.g. `"2024-01-15T10:30:00Z"`.
+pub fn unix_to_iso8601(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    // Civil-from-days (Howard Hinnant), with days counted from 1970-01-01.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, rem / 3_600, rem % 3_600 / 60, rem % 60
+    )
+}
+
 /// Given multiple tags for the same image, return their longest common prefix
 /// with trailing separators (`-`, `_`, `.`) stripped.
 /// E.g. `["v5.4.0-production-3cac2d2", "v5.4.0-production-latest"]` → `"v5.4.0-production"`.
@@ -686,7 +1927,7 @@ fn common_tag_prefix(tags: &[&str]) -> String {
                 prefix_len = prefix_len.min(common);
             }
             first[..prefix_len]
-                .trim_end_matches(|c| c == '-' || c == '_' || c == '.')
+                .trim_end_matches(['-', '_', '.'])
                 .to_string()
         }
     }
@@ -819,86 +2060,196 @@ pub fn list_ecr_images(
     Ok(images)
 }
 
-/// Human-readable DNS → EC2 resolution report.
-pub fn resolve_dns_report(input: &str, profile: Option<&str>) -> Result<String> {
-    use std::fmt::Write as _;
+/// DNS → EC2/ALB resolution, as data. `resolve_dns_report` formats it.
+pub fn resolve_dns_detail(input: &str, profile: Option<&str>) -> Result<ResolveReport> {
     let host = strip_url_to_host(input);
-    let mut out = String::new();
-    writeln!(out, "Resolving: {}", host).ok();
 
-    // ── Step 1: local DNS ────────────────────────────────────────────────────
-    let addrs = dns_lookup(&host);
-    let locally_resolved = !addrs.is_empty();
-
-    if locally_resolved {
-        writeln!(out, "  DNS (local): {}",
-            addrs.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ")).ok();
-    } else {
-        writeln!(out, "  DNS (local): not resolvable — likely an internal hostname").ok();
-    }
+    // ── Step 1: DNS (external if /etc/hosts points at loopback) ──────────────
+    let addrs = resolve_host(&host);
+    let local_ips: Vec<String> = addrs.iter().map(|a| a.to_string()).collect();
 
     // ── Step 2: fetch EC2 + bastions ─────────────────────────────────────────
-    let instances = list_instances(profile)?;
-    let bastions = find_bastions(profile).unwrap_or_default();
-    let online_bastions: Vec<&BastionInfo> = bastions.iter().filter(|b| b.ssm_online).collect();
+    let instances = list_instances_cached(profile)?;
+    let bastions: Vec<BastionInfo> = find_bastions(profile)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|b| b.ssm_online)
+        .collect();
 
-    // ── Step 3: direct IP → EC2 match ────────────────────────────────────────
-    let mut direct_match = false;
-    for addr in &addrs {
-        let addr_str = addr.to_string();
-        for inst in instances.iter().filter(|i| {
-            i.private_ip.as_deref() == Some(&addr_str)
-                || i.public_ip.as_deref() == Some(&addr_str)
-        }) {
-            direct_match = true;
-            writeln!(out, "\n  EC2 match: {} ({})", inst.name, inst.id).ok();
-            writeln!(out, "    type={} state={} ssm={}",
-                inst.instance_type, inst.state.as_str(), inst.ssm_status.as_str()).ok();
+    // ── Step 3: direct IP → EC2 match, and ALB lookup ────────────────────────
+    let ec2_matches: Vec<Instance> = instances
+        .iter()
+        .filter(|i| local_ips.iter().any(|ip| i.has_ip(ip)))
+        .cloned()
+        .collect();
+    let alb_arn = find_alb_for_hostname(&host, profile).unwrap_or(None);
+
+    // ── Step 4: if no local match, try resolving via bastion ─────────────────
+    let via_bastion = match bastions.first() {
+        Some(bastion) if ec2_matches.is_empty() => {
+            let mut res = BastionResolution {
+                bastion_id: bastion.id.clone(),
+                bastion_name: bastion.name.clone(),
+                ips: vec![],
+                ec2_matches: vec![],
+                error: None,
+            };
+            match resolve_via_bastion(&bastion.id, &host, profile) {
+                Ok(result) if !result.is_empty() && result != "FAIL" => {
+                    res.ips = result
+                        .lines()
+                        .map(str::trim)
+                        .filter(|l| l.parse::<std::net::IpAddr>().is_ok())
+                        .map(String::from)
+                        .collect();
+                    res.ec2_matches = instances
+                        .iter()
+                        .filter(|i| res.ips.iter().any(|ip| i.has_ip(ip)))
+                        .cloned()
+                        .collect();
+                }
+                Ok(_) => {}
+                Err(e) => res.error = Some(e.to_string()),
+            }
+            Some(res)
         }
+        _ => None,
+    };
+
+    // Unmatched IPs (locally or from the bastion) may belong to ECS/Fargate tasks.
+    let mut unmatched: Vec<String> = if ec2_matches.is_empty() { local_ips.clone() } else { vec![] };
+    if let Some(res) = via_bastion.as_ref().filter(|r| r.ec2_matches.is_empty()) {
+        unmatched.extend(res.ips.iter().cloned());
     }
+    let ecs_tasks = find_ecs_tasks_by_ip(&unmatched, profile).unwrap_or_default();
 
-    // ── Step 4: if no local match, try resolving via bastion ─────────────────
-    if !direct_match {
-        if online_bastions.is_empty() {
-            writeln!(out, "\n  No SSM-online bastions found to try remote resolution.").ok();
-        } else {
-            writeln!(out, "\n  No direct EC2 IP match.").ok();
+    Ok(ResolveReport { host, local_ips, ec2_matches, alb_arn, ecs_tasks, via_bastion, bastions })
+}
 
-            // Try to resolve hostname from the first available bastion
-            let bastion = online_bastions[0];
-            writeln!(out, "\n  Trying resolution via bastion: {} ({})", bastion.name, bastion.id).ok();
+/// Human-readable DNS → EC2 resolution report.
+pub fn resolve_dns_report(input: &str, profile: Option<&str>) -> Result<String> {
+    use std::fmt::Write as _;
+    let report = resolve_dns_detail(input, profile)?;
+    let mut out = String::new();
+    writeln!(out, "Resolving: {}", report.host).ok();
 
-            match resolve_via_bastion(&bastion.id, &host, profile) {
-                Ok(result) if !result.is_empty() && result != "FAIL" => {
-                    writeln!(out, "  DNS (from bastion): {}", result.lines().next().unwrap_or(&result)).ok();
-                    // Check if bastion-resolved IP matches an EC2 instance
-                    for line in result.lines() {
-                        let trimmed = line.trim();
-                        if trimmed.parse::<std::net::IpAddr>().is_ok() {
-                            if let Some(inst) = instances.iter().find(|i|
-                                i.private_ip.as_deref() == Some(trimmed)
-                            ) {
-                                writeln!(out, "  EC2 match: {} ({}) — reachable via bastion", inst.name, inst.id).ok();
-                            }
-                        }
+    if report.local_ips.is_empty() {
+        writeln!(out, "  DNS (local): not resolvable — likely an internal hostname").ok();
+    } else {
+        writeln!(out, "  DNS (local): {}", report.local_ips.join(", ")).ok();
+    }
+    if let Some(arn) = &report.alb_arn {
+        writeln!(out, "  ALB: {}", arn).ok();
+    }
+
+    for inst in &report.ec2_matches {
+        writeln!(out, "\n  EC2 match: {} ({})", inst.name, inst.id).ok();
+        writeln!(out, "    type={} state={} ssm={}",
+            inst.instance_type, inst.state.as_str(), inst.ssm_status.as_str()).ok();
+    }
+    for task in &report.ecs_tasks {
+        writeln!(out, "\n  ECS match: {}", task.describe()).ok();
+    }
+
+    if report.ec2_matches.is_empty() {
+        match &report.via_bastion {
+            None => {
+                writeln!(out, "\n  No SSM-online bastions found to try remote resolution.").ok();
+            }
+            Some(res) => {
+                writeln!(out, "\n  No direct EC2 IP match.").ok();
+                writeln!(out, "\n  Trying resolution via bastion: {} ({})", res.bastion_name, res.bastion_id).ok();
+                if let Some(e) = &res.error {
+                    writeln!(out, "  Bastion resolution failed: {}", e).ok();
+                } else if let Some(ip) = res.ips.first() {
+                    writeln!(out, "  DNS (from bastion): {}", ip).ok();
+                    for inst in &res.ec2_matches {
+                        writeln!(out, "  EC2 match: {} ({}) — reachable via bastion", inst.name, inst.id).ok();
                     }
+                } else {
+                    writeln!(out, "  Bastion could not resolve {} either (not in VPC DNS?)", report.host).ok();
                 }
-                Ok(_) => {
-                    writeln!(out, "  Bastion could not resolve {} either (not in VPC DNS?)", host).ok();
+
+                // List all available bastions
+                writeln!(out, "\n  Available SSM-online bastions:").ok();
+                for b in &report.bastions {
+                    match &b.availability_zone {
+                        Some(az) => writeln!(out, "    ● {} ({}, {})", b.name, b.id, az).ok(),
+                        None => writeln!(out, "    ● {} ({})", b.name, b.id).ok(),
+                    };
                 }
-                Err(e) => {
-                    writeln!(out, "  Bastion resolution failed: {}", e).ok();
+                if let Some(task) = report.ecs_tasks.first() {
+                    writeln!(out, "  (backend is {} — reachable via bastion)", task.describe()).ok();
                 }
+                writeln!(out, "\n  Tunnel suggestion: awsx2 tunnel-url {} <local_port>", input).ok();
             }
-
-            // List all available bastions
-            writeln!(out, "\n  Available SSM-online bastions:").ok();
-            for b in &online_bastions {
-                writeln!(out, "    ● {} ({})", b.name, b.id).ok();
-            }
-            writeln!(out, "\n  Tunnel suggestion: awsx2 tunnel-url {} <local_port>", input).ok();
         }
     }
 
     Ok(out)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tag_splits_on_first_equals() {
+        assert_eq!(parse_tag(" Name = web ").unwrap(), ("Name".into(), "web".into()));
+        assert_eq!(parse_tag("Query=a=b").unwrap(), ("Query".into(), "a=b".into()));
+        assert_eq!(parse_tag("Empty=").unwrap(), ("Empty".into(), String::new()));
+        assert!(parse_tag("NoEquals").is_err());
+        assert!(parse_tag(" =value").is_err());
+    }
+
+    #[test]
+    fn name_tag_specification_keeps_punctuation_in_value() {
+        let spec: serde_json::Value = serde_json::from_str(&name_tag_specification("web, {prod}=1]")).unwrap();
+        assert_eq!(spec[0]["ResourceType"], "instance");
+        let tags = spec[0]["Tags"].as_array().unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0]["Key"], "Name");
+        assert_eq!(tags[0]["Value"], "web, {prod}=1]");
+    }
+
+    #[test]
+    fn check_remote_host_rejects_shell_syntax() {
+        for ok in ["db.internal", "10.0.1.5", "fd00::1", "my-host-1"] {
+            assert!(check_remote_host(ok).is_ok(), "{}", ok);
+        }
+        for bad in ["", "x;rm -rf /tmp/*", "$(id)", "a b", "-v", "h`id`"] {
+            assert!(check_remote_host(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn short_age_units() {
+        assert_eq!(short_age(0), "0s");
+        assert_eq!(short_age(59), "59s");
+        assert_eq!(short_age(60), "1m");
+        assert_eq!(short_age(3_599), "59m");
+        assert_eq!(short_age(3_600), "1h");
+        assert_eq!(short_age(86_400 * 3 + 5), "3d");
+    }
+
+    #[test]
+    fn iso8601_round_trip() {
+        assert_eq!(unix_to_iso8601(0), "1970-01-01T00:00:00Z");
+        assert_eq!(unix_to_iso8601(1_705_314_600), "2024-01-15T10:30:00Z");
+        // Leap day
+        assert_eq!(unix_to_iso8601(1_709_164_800), "2024-02-29T00:00:00Z");
+        for secs in [0, 951_782_400, 1_705_314_600, 1_709_164_800, 4_102_444_799] {
+            assert_eq!(parse_iso8601_to_unix(&unix_to_iso8601(secs)), Some(secs as f64));
+        }
+    }
+
+    #[test]
+    fn parse_iso8601_offsets_and_fractions() {
+        assert_eq!(parse_iso8601_to_unix("2024-01-15T10:30:00+00:00"), Some(1_705_314_600.0));
+        assert_eq!(parse_iso8601_to_unix("2024-01-15T12:30:00+02:00"), Some(1_705_314_600.0));
+        assert_eq!(parse_iso8601_to_unix("2024-01-15T05:00:00-05:30"), Some(1_705_314_600.0));
+        assert_eq!(parse_iso8601_to_unix("2024-01-15T10:30:00.123Z"), Some(1_705_314_600.0));
+        assert_eq!(parse_iso8601_to_unix("2024-01-15"), None);
+        assert_eq!(parse_iso8601_to_unix("not a timestamp at all"), None);
+    }
+}
@@ -0,0 +1,386 @@
+//! On-disk configuration layout.
+//!
+//! Everything awsx2 persists lives under `~/.config/awsx2/`. With `--env <name>`
+//! the whole set is scoped to `~/.config/awsx2/envs/<name>/`, so separate
+//! contexts (e.g. "work" vs "personal") never share saved state.
+//!
+//! User preferences are read from `config.toml` in that directory; a commented
+//! default file is written there on first run.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, Result};
+
+static ENV_NAME: OnceLock<String> = OnceLock::new();
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Select the named environment for the rest of the process. Call once at startup.
+pub fn set_env(name: &str) -> Result<()> {
+    let name = name.trim();
+    if name.is_empty()
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(AppError::Other(format!(
+            "Invalid env name '{}' (use letters, digits, '-' or '_')",
+            name
+        )));
+    }
+    let _ = ENV_NAME.set(name.to_string());
+    Ok(())
+}
+
+/// The active environment name, if one was selected.
+pub fn env_name() -> Option<&'static str> {
+    ENV_NAME.get().map(|s| s.as_str())
+}
+
+/// Root config directory for the active environment.
+pub fn config_dir() -> PathBuf {
+    let base = dirs::config_dir()
+        .unwrap_or_else(|| {
+            PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| "/root".into()))
+                .join(".config")
+        })
+        .join("awsx2");
+    match env_name() {
+        Some(name) => base.join("envs").join(name),
+        None => base,
+    }
+}
+
+// ── Settings (config.toml) ────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Name prefixes or globs (`*`, `?`) an instance must match to be listed by
+    /// default. Empty means every instance is shown.
+    pub instance_name_include: Vec<String>,
+    /// Running instances whose Name matches one of these are bastions. Plain
+    /// strings match anywhere in the name (case-insensitive); `*` and `?`
+    /// make a glob over the whole name.
+    pub bastion_name_patterns: Vec<String>,
+    /// `Key=Value` (or just `Key`) tag that also marks an instance as a bastion.
+    pub bastion_tag: Option<String>,
+    /// Tag shown as an extra column in `list` and the Instances tab, e.g.
+    /// `Environment`.
+    pub tag_column: Option<String>,
+    /// Default launch template (ID or name) for `launch` / quick-launch.
+    pub launch_template: Option<String>,
+    /// Instance type behind the `switch gpu` alias.
+    pub gpu_instance_type: String,
+    /// Instance type behind the `switch cpu` alias.
+    pub cpu_instance_type: String,
+    /// Remote port for tunnels to an instance when none is given.
+    pub default_remote_port: u16,
+    /// Remote port for tunnels to a host behind a bastion (`tunnel-remote`,
+    /// `tunnel-dns`) when none is given.
+    pub default_remote_host_port: u16,
+    /// SSM document for tunnels to an instance port, instead of
+    /// `AWS-StartPortForwardingSession`.
+    pub ssm_document: Option<String>,
+    /// SSM document for tunnels to a host behind an instance, instead of
+    /// `AWS-StartPortForwardingSessionToRemoteHost`.
+    pub ssm_remote_document: Option<String>,
+    /// Extra parameters passed to a custom `ssm_document` /
+    /// `ssm_remote_document`.
+    pub ssm_document_parameters: BTreeMap<String, String>,
+    /// How often the TUI re-probes tunnels.
+    pub tunnel_refresh_secs: u64,
+    /// Keep every new tunnel's SSM session from idling out, as if
+    /// `--keepalive` were given.
+    pub tunnel_keepalive: bool,
+    /// Seconds between keepalive requests (minimum 10). Must stay below the
+    /// Session Manager idle timeout, 20 minutes by default.
+    pub tunnel_keepalive_secs: u64,
+    /// Tunnel latency at or above this is shown as slow (yellow).
+    pub latency_warn_ms: u64,
+    /// Tunnel latency above this is shown as very slow (red).
+    pub latency_slow_ms: u64,
+    /// Running instances up for more than this many days are flagged as
+    /// long-running; 0 disables the flag.
+    pub long_running_days: u64,
+    /// Local port for the VPN SAML callback when a VPN config doesn't set one.
+    pub saml_listen_port: u16,
+    /// Reopen the TUI on the tab (and with the instance filter) it was quit on.
+    pub restore_tui_state: bool,
+    /// Append every state-changing action to `audit.log`.
+    pub audit_log: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            instance_name_include: vec![],
+            bastion_name_patterns: vec!["bastion".into()],
+            bastion_tag: None,
+            tag_column: None,
+            launch_template: None,
+            gpu_instance_type: "g4dn.4xlarge".into(),
+            cpu_instance_type: "m6i.2xlarge".into(),
+            default_remote_port: 8000,
+            default_remote_host_port: 8501,
+            ssm_document: None,
+            ssm_remote_document: None,
+            ssm_document_parameters: BTreeMap::new(),
+            tunnel_refresh_secs: 15,
+            tunnel_keepalive: false,
+            tunnel_keepalive_secs: 300,
+            latency_warn_ms: 50,
+            latency_slow_ms: 200,
+            long_running_days: 7,
+            saml_listen_port: crate::vpn::DEFAULT_SAML_PORT,
+            restore_tui_state: false,
+            audit_log: true,
+        }
+    }
+}
+
+impl Settings {
+    /// Whether an instance with this Name tag falls inside the configured scope.
+    pub fn includes_instance(&self, name: &str) -> bool {
+        self.instance_name_include.is_empty()
+            || self.instance_name_include.iter().any(|p| name_matches(p, name))
+    }
+
+    /// Whether an instance with this Name and these tags is a bastion.
+    pub fn is_bastion(&self, name: &str, tags: &BTreeMap<String, String>) -> bool {
+        let name = name.to_lowercase();
+        let by_name = self.bastion_name_patterns.iter().any(|p| {
+            let p = p.to_lowercase();
+            if p.contains(['*', '?']) { name_matches(&p, &name) } else { name.contains(&p) }
+        });
+        let by_tag = self.bastion_tag.as_deref().is_some_and(|spec| match spec.split_once('=') {
+            Some((key, value)) => tags.get(key.trim()).is_some_and(|v| v == value.trim()),
+            None => tags.contains_key(spec.trim()),
+        });
+        by_name || by_tag
+    }
+}
+
+pub fn settings_path() -> PathBuf {
+    config_dir().join("config.toml")
+}
+
+/// Written on first run. Every key is commented out, so later changes to the
+/// built-in defaults still apply until the user overrides them.
+const DEFAULT_SETTINGS_FILE: &str = r#"# awsx2 preferences. Uncomment a key to override its default.
+
+# Only list instances whose Name starts with / matches one of these.
+# Plain strings are prefixes; `*` and `?` make a glob over the whole name.
+# instance_name_include = ["team-a-", "shared-*-bastion"]
+
+# Show this tag's value as an extra column in `awsx2 list` and the TUI
+# tag_column = "Environment"
+
+# Which running instances serve as bastions for remote tunnels: a Name
+# containing one of these (`*` / `?` globs match the whole name), or this tag
+# bastion_name_patterns = ["bastion"]
+# bastion_tag = "Role=bastion"
+
+# Default launch template (ID or name) for `awsx2 launch` and the TUI `n` action
+# launch_template = "lt-0123456789abcdef0"
+
+# Custom SSM port-forwarding documents, for accounts that don't allow the
+# AWS-managed ones (same as `--document` on the tunnel commands), and extra
+# parameters they take. awsx2 checks that each declares localPortNumber etc.
+# ssm_document = "Org-PortForwarding"
+# ssm_remote_document = "Org-PortForwardingToRemoteHost"
+# ssm_document_parameters = { reason = "awsx2 tunnel" }
+
+# Instance types behind `awsx2 switch gpu` / `awsx2 switch cpu`
+# gpu_instance_type = "g4dn.4xlarge"
+# cpu_instance_type = "m6i.2xlarge"
+
+# Remote port when none is given: tunnels to an instance, and to a host
+# behind a bastion (tunnel-remote, tunnel-dns)
+# default_remote_port = 8000
+# default_remote_host_port = 8501
+
+# Seconds between tunnel health probes in the TUI
+# tunnel_refresh_secs = 15
+
+# Send a request through every new tunnel this often, so its SSM session
+# does not hit the idle timeout (same as `--keepalive` on the tunnel commands)
+# tunnel_keepalive = false
+# tunnel_keepalive_secs = 300
+
+# Tunnel latency colours in the TUI: green below latency_warn_ms, yellow up
+# to latency_slow_ms, red above it
+# latency_warn_ms = 50
+# latency_slow_ms = 200
+
+# Flag running instances up for more than this many days (0 to disable)
+# long_running_days = 7
+
+# Local port for the VPN SAML callback (a VPN config's saml_port wins)
+# saml_listen_port = 35001
+
+# Reopen the TUI on the tab and instance filter it was last quit on
+# restore_tui_state = false
+
+# Record start/stop/switch/tunnel/vpn actions in audit.log (one JSON object
+# per line) in this directory
+# audit_log = true
+"#;
+
+/// Load `config.toml` for the active environment. Call once at startup, after
+/// `set_env`; a missing file is created with commented defaults, a malformed
+/// one is an error.
+pub fn load_settings() -> Result<()> {
+    let path = settings_path();
+    let settings = match std::fs::read_to_string(&path) {
+        Ok(text) => toml::from_str(&text)
+            .map_err(|e| AppError::Other(format!("{}: {}", path.display(), e)))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            // Best effort: a read-only home still runs on defaults.
+            let _ = std::fs::create_dir_all(config_dir())
+                .and_then(|_| std::fs::write(&path, DEFAULT_SETTINGS_FILE));
+            Settings::default()
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let _ = SETTINGS.set(settings);
+    Ok(())
+}
+
+pub fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(Settings::default)
+}
+
+// ── TUI session state ─────────────────────────────────────────────────────────
+
+/// What the TUI remembers between runs when `restore_tui_state` is set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TuiState {
+    /// Tab title, e.g. "Tunnels".
+    pub tab: String,
+    pub instance_filter: String,
+}
+
+fn tui_state_path() -> PathBuf {
+    config_dir().join("tui_state.json")
+}
+
+/// The state saved by the last TUI session; default when there is none.
+pub fn load_tui_state() -> TuiState {
+    std::fs::read_to_string(tui_state_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// Best effort, like `remember_instance_type`.
+pub fn save_tui_state(state: &TuiState) {
+    if let Ok(json) = serde_json::to_string_pretty(state) {
+        let _ = std::fs::create_dir_all(config_dir()).and_then(|_| std::fs::write(tui_state_path(), json));
+    }
+}
+
+// ── Recently used instance types ──────────────────────────────────────────────
+
+/// How many types `switch` remembers.
+const RECENT_TYPES_MAX: usize = 5;
+
+fn recent_types_path() -> PathBuf {
+    config_dir().join("recent_types.json")
+}
+
+/// Instance types from successful switches, most recent first.
+pub fn recent_instance_types() -> Vec<String> {
+    std::fs::read_to_string(recent_types_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// Move `instance_type` to the front of the recent list. Best effort.
+pub fn remember_instance_type(instance_type: &str) {
+    let mut types = recent_instance_types();
+    types.retain(|t| t != instance_type);
+    types.insert(0, instance_type.to_string());
+    types.truncate(RECENT_TYPES_MAX);
+    let path = recent_types_path();
+    if let Ok(json) = serde_json::to_string_pretty(&types) {
+        let _ = std::fs::create_dir_all(config_dir()).and_then(|_| std::fs::write(path, json));
+    }
+}
+
+/// A pattern without wildcards is a prefix; otherwise it is a glob over the whole name.
+fn name_matches(pattern: &str, name: &str) -> bool {
+    if !pattern.contains(['*', '?']) {
+        return name.starts_with(pattern);
+    }
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    let (mut pi, mut ni) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == n[ni]) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ni));
+            pi += 1;
+        } else if let Some((sp, sn)) = star {
+            pi = sp + 1;
+            ni = sn + 1;
+            star = Some((sp, sn + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_matches_prefix_without_wildcards() {
+        assert!(name_matches("team-a-", "team-a-web"));
+        assert!(!name_matches("team-a-", "shared-team-a-web"));
+    }
+
+    #[test]
+    fn name_matches_glob_over_whole_name() {
+        assert!(name_matches("shared-*-bastion", "shared-eu-bastion"));
+        assert!(name_matches("web-?", "web-1"));
+        assert!(!name_matches("web-?", "web-12"));
+        assert!(!name_matches("*-bastion", "eu-bastion-2"));
+        assert!(name_matches("*", ""));
+    }
+
+    #[test]
+    fn is_bastion_by_name_substring_or_glob() {
+        let mut s = Settings::default();
+        let tags = BTreeMap::new();
+        assert!(s.is_bastion("prod-Bastion-1", &tags));
+        assert!(!s.is_bastion("jumpbox-1", &tags));
+        s.bastion_name_patterns = vec!["JUMPBOX-*".into()];
+        assert!(s.is_bastion("jumpbox-1", &tags));
+        assert!(!s.is_bastion("old-jumpbox-1", &tags));
+    }
+
+    #[test]
+    fn is_bastion_by_tag() {
+        let s = Settings { bastion_name_patterns: vec![], bastion_tag: Some("Role = bastion".into()), ..Settings::default() };
+        let mut tags = BTreeMap::new();
+        assert!(!s.is_bastion("anything", &tags));
+        tags.insert("Role".to_string(), "web".to_string());
+        assert!(!s.is_bastion("anything", &tags));
+        tags.insert("Role".to_string(), "bastion".to_string());
+        assert!(s.is_bastion("anything", &tags));
+
+        let key_only = Settings { bastion_tag: Some("Jump".into()), ..s };
+        assert!(!key_only.is_bastion("anything", &tags));
+        tags.insert("Jump".to_string(), String::new());
+        assert!(key_only.is_bastion("anything", &tags));
+    }
+}
@@ -4,6 +4,8 @@ use thiserror::Error;
 pub enum AppError {
     #[error("AWS CLI error: {0}")]
     AwsCli(String),
+    #[error("AWS credentials have expired — run `awsx2 login` to refresh them")]
+    CredentialsExpired,
     #[error("JSON parse error: {0}")]
     Json(#[from] serde_json::Error),
     #[error("IO error: {0}")]
@@ -24,9 +26,124 @@ pub enum AppError {
     SamlAuth(String),
     #[error("Browser automation error: {0}")]
     Browser(String),
+    /// An external tool awsx2 shells out to is not on PATH.
+    #[error("`{0}` was not found on PATH. {hint}", hint = install_hint(.0))]
+    MissingDependency(String),
+    /// `--dry-run` reached a step (e.g. an SSM session) that cannot be faked.
+    #[error("dry run: stopping before the first long-running command")]
+    DryRun,
     #[allow(dead_code)]
     #[error("{0}")]
     Other(String),
 }
 
 pub type Result<T> = std::result::Result<T, AppError>;
+
+fn install_hint(name: &str) -> &'static str {
+    match name {
+        "aws" => "Install the AWS CLI v2 (macOS: `brew install awscli`, others: \
+            https://docs.aws.amazon.com/cli/latest/userguide/getting-started-install.html)",
+        "session-manager-plugin" => "Install the Session Manager plugin (macOS: \
+            `brew install --cask session-manager-plugin`, others: \
+            https://docs.aws.amazon.com/systems-manager/latest/userguide/session-manager-working-with-install-plugin.html)",
+        _ => "Install it, or add its directory to PATH",
+    }
+}
+
+// ── Redaction ────────────────────────────────────────────────────────────────
+
+/// Base64 or hex runs at least this long are treated as opaque blobs (SAML
+/// responses are tens of kilobytes) and cut to `BLOB_PREFIX` chars.
+const BLOB_MIN_LEN: usize = 100;
+const BLOB_PREFIX: usize = 8;
+
+/// Mask secrets in text that is about to be shown or logged: `CRV1:` session
+/// IDs and SAML payloads, `SAMLResponse=` values, `password=`/`SID:` style
+/// pairs, and any long base64 or hex blob (kept as a short prefix plus its
+/// length).
+pub fn redact(text: &str) -> String {
+    static PATTERNS: std::sync::OnceLock<[(regex::Regex, &'static str); 3]> = std::sync::OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| [
+        (regex::Regex::new(r"CRV1:([A-Z,]*):[^\s'\x22]+").unwrap(), "CRV1:$1:<redacted>"),
+        (regex::Regex::new(r"(SAMLResponse=)[^\s&'\x22]+").unwrap(), "$1<redacted>"),
+        (
+            regex::Regex::new(r"(?i)\b(password|passwd|secret|sid)(\s*[=:]\s*)[^\s,;&'\x22]+").unwrap(),
+            "$1$2<redacted>",
+        ),
+    ]);
+    static BLOB: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let blob = BLOB.get_or_init(|| {
+        regex::Regex::new(&format!(r"[A-Za-z0-9+/_-]{{{},}}={{0,2}}", BLOB_MIN_LEN)).unwrap()
+    });
+
+    let mut out = text.to_string();
+    for (re, with) in patterns {
+        out = re.replace_all(&out, *with).into_owned();
+    }
+    blob.replace_all(&out, |c: &regex::Captures| {
+        let s = &c[0];
+        if looks_encoded(s) {
+            format!("{}…<redacted {} chars>", &s[..BLOB_PREFIX], s.len())
+        } else {
+            s.to_string()
+        }
+    })
+    .into_owned()
+}
+
+/// Whether a long run is hex, or base64 / base64url: one alphabet, mixing
+/// upper case, lower case and digits. Paths and the tails of ARNs are runs
+/// of the same characters, but mix `/` with `-` / `_` or start with `/`.
+fn looks_encoded(s: &str) -> bool {
+    let body = s.trim_end_matches('=');
+    if body.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return true;
+    }
+    if body.starts_with('/') || (body.contains(['/', '+']) && body.contains(['-', '_'])) {
+        return false;
+    }
+    body.bytes().any(|b| b.is_ascii_uppercase())
+        && body.bytes().any(|b| b.is_ascii_lowercase())
+        && body.bytes().any(|b| b.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redact_masks_credentials() {
+        assert_eq!(redact("password=hunter2 user=bob"), "password=<redacted> user=bob");
+        assert_eq!(redact("SID: abc123, next"), "SID: <redacted>, next");
+        assert_eq!(redact("auth CRV1:R,E:c2Vzc2lvbg==:dXNlcg== done"), "auth CRV1:R,E:<redacted> done");
+        assert_eq!(redact("POST SAMLResponse=PHNhbWw+&RelayState=x"), "POST SAMLResponse=<redacted>&RelayState=x");
+    }
+
+    #[test]
+    fn redact_shortens_long_blobs() {
+        let blob = "A".repeat(150);
+        assert_eq!(redact(&format!("token {} end", blob)), "token AAAAAAAA…<redacted 150 chars> end");
+        let short = "A".repeat(BLOB_MIN_LEN - 1);
+        assert_eq!(redact(&short), short);
+        let saml = "PHNhbWxwOlJlc3BvbnNlIHhtbG5zOnNhbWxwPSJ1cm46b2FzaXM6bmFtZXM6dGM6U0FNTDoyLjA6cHJvdG9jb2wiIElEPSJfMTIzNDU2Nzg5MCIgVmVyc2lvbj0iMi4wIj4=";
+        assert_eq!(redact(saml), "PHNhbWxw…<redacted 132 chars>");
+    }
+
+    #[test]
+    fn redact_keeps_long_paths_and_arns() {
+        let s3 = "s3://my-bucket/AWSLogs/123456789012/elasticloadbalancing/ap-northeast-1/2024/01/15/\
+            123456789012_elasticloadbalancing_app-internal-alb_20240115T1030Z.log.gz";
+        assert_eq!(redact(s3), s3);
+        let arn = "{\"RoleArn\":\"arn:aws:iam::123456789012:role/application/risk-model/production/\
+            ap-northeast-1/AWSReservedSSO_AdministratorAccess_0123456789abcdef\"}";
+        assert_eq!(redact(arn), arn);
+        let path = "/home/ec2-user/.cache/awsx2/ssh-keys/".to_string() + &"Dir1".repeat(30);
+        assert_eq!(redact(&path), path);
+    }
+
+    #[test]
+    fn redact_leaves_plain_text_alone() {
+        let text = "Tunnel error: port 8080 never opened";
+        assert_eq!(redact(text), text);
+    }
+}
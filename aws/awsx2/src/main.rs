@@ -3,20 +3,28 @@
 //! No args  → TUI mode (ratatui full-screen)
 //! With args → non-interactive CLI (same functionality as the bash awsx)
 
+mod audit;
 mod aws;
+mod config;
 mod error;
+mod metrics;
 mod models;
 mod proxy;
 mod tunnel;
 mod tui;
+mod util;
 mod vpn;
 
 use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
 
-use clap::{Parser, Subcommand, Args};
+use clap::{Parser, Subcommand, Args, ValueEnum};
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -31,6 +39,10 @@ fn gray(s: impl std::fmt::Display) -> String {
     format!("\x1b[90m{}\x1b[0m", s)
 }
 
+fn red(s: impl std::fmt::Display) -> String {
+    format!("\x1b[31m{}\x1b[0m", s)
+}
+
 fn find_pid_on_port(port: u16) -> Option<u32> {
     // Try LISTEN state first (clean tunnel), then any TCP state (socat with active connections)
     for args in [
@@ -50,14 +62,33 @@ fn find_pid_on_port(port: u16) -> Option<u32> {
     None
 }
 
+// ── Confirmation prompts ─────────────────────────────────────────────────────
+
+/// Set by the global `--yes` flag.
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+/// Ask a yes/no question on stderr; anything but `y` (or a read error) is no.
+fn ask(prompt: &str) -> bool {
+    eprint!("{} [y/N] ", prompt);
+    let _ = std::io::Write::flush(&mut std::io::stderr());
+    let mut s = String::new();
+    std::io::stdin().read_line(&mut s).is_ok() && s.trim().eq_ignore_ascii_case("y")
+}
+
+/// Guard for destructive commands, mirroring the TUI's confirm dialogs. Skipped
+/// with `--yes`, and when stdout is not a terminal so scripts never block.
+fn confirm(prompt: &str) -> bool {
+    use std::io::IsTerminal;
+    ASSUME_YES.load(Ordering::Relaxed) || !io::stdout().is_terminal() || ask(prompt)
+}
+
 fn confirm_and_kill_port(port: u16) -> bool {
     match find_pid_on_port(port) {
         Some(pid) => {
-            eprint!("Port {} in use by PID {}. Kill it and proceed? [y/N] ", port, pid);
-            let _ = std::io::Write::flush(&mut std::io::stderr());
-            let mut s = String::new();
-            if std::io::stdin().read_line(&mut s).is_err() { return false; }
-            if s.trim().eq_ignore_ascii_case("y") {
+            // Only --yes skips this one: killing an unknown process is not
+            // something a script should get by accident.
+            let prompt = format!("Port {} in use by PID {}. Kill it and proceed?", port, pid);
+            if ASSUME_YES.load(Ordering::Relaxed) || ask(&prompt) {
                 #[cfg(unix)]
                 unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM); }
                 std::thread::sleep(std::time::Duration::from_millis(500));
@@ -75,6 +106,85 @@ fn confirm_and_kill_port(port: u16) -> bool {
 
 // ── CLI spinner ───────────────────────────────────────────────────────────────
 
+static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn request_stop(_sig: libc::c_int) {
+    STOP_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// `--health-check`: GET `path` through the tunnel and print the status code.
+/// Anything but 2xx/3xx (or no HTTP answer at all) is a warning only; the
+/// tunnel stays up either way.
+fn report_health(local_port: u16, path: &str, host: &str) {
+    match tunnel::health_check(local_port, path, host) {
+        Ok(code) if (200..400).contains(&code) => println!("Health check: GET {} -> {}", path, code),
+        Ok(code) => eprintln!("Warning: health check GET {} returned {}; tunnel left open", path, code),
+        Err(e) => eprintln!("Warning: health check GET {} failed: {}; tunnel left open", path, e),
+    }
+}
+
+/// Block until Ctrl-C / SIGTERM or until the tunnel process dies, then stop the
+/// tunnel and its socat forwarder, drop its registry entry and, with
+/// `proxy_port`, remove the reverse proxies pointing at that port.
+fn run_foreground(tp: &models::TunnelProcess, forwarder_pid: Option<u32>, proxy_port: Option<u16>) {
+    let tunnel_pid = tp.pid;
+    #[cfg(unix)]
+    unsafe {
+        libc::signal(libc::SIGINT, request_stop as *const () as libc::sighandler_t);
+        libc::signal(libc::SIGTERM, request_stop as *const () as libc::sighandler_t);
+    }
+    println!("{}", gray("Running in foreground — press Ctrl-C to stop."));
+
+    loop {
+        if STOP_REQUESTED.load(Ordering::SeqCst) {
+            println!();
+            break;
+        }
+        if session_exited(tunnel_pid) {
+            eprintln!("Tunnel process exited.");
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+
+    println!("{}", gray("Stopping tunnel..."));
+    if let Some(pid) = forwarder_pid {
+        tunnel::stop_tunnel(pid);
+    }
+    // session-manager-plugin runs as a child of the aws CLI process.
+    let _ = std::process::Command::new("pkill")
+        .args(["-TERM", "-P", &tunnel_pid.to_string()])
+        .status();
+    tunnel::stop_tunnel(tunnel_pid);
+    tunnel::forget_tunnel(tp.local_port);
+    if let Some(port) = proxy_port {
+        proxy::teardown_proxies_for_port(port);
+    }
+    println!("Tunnel stopped.");
+}
+
+/// Whether the tunnel's session process has exited. It is our own
+/// (forgotten) child, so on unix it is reaped here.
+fn session_exited(pid: u32) -> bool {
+    #[cfg(unix)]
+    {
+        let reaped = unsafe { libc::waitpid(pid as libc::pid_t, std::ptr::null_mut(), libc::WNOHANG) };
+        match reaped {
+            0 => false,
+            -1 => match io::Error::last_os_error().raw_os_error() {
+                // Interrupted by Ctrl-C; the flag is checked on the next turn.
+                Some(libc::EINTR) => false,
+                // Not (or no longer) our child: fall back to asking the kernel.
+                _ => !tunnel::pid_alive(pid),
+            },
+            _ => true,
+        }
+    }
+    #[cfg(not(unix))]
+    { !tunnel::pid_alive(pid) }
+}
+
 struct Spinner {
     stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
     msg:  std::sync::Arc<std::sync::Mutex<String>>,
@@ -85,6 +195,10 @@ impl Spinner {
     fn new(initial: &str) -> Self {
         let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
         let msg  = std::sync::Arc::new(std::sync::Mutex::new(initial.to_string()));
+        // Dry-run output goes to stderr; an animated line would garble it.
+        if aws::dry_run() {
+            return Self { stop, msg, handle: None };
+        }
         let stop_c = stop.clone();
         let msg_c  = msg.clone();
         let handle = std::thread::spawn(move || {
@@ -114,6 +228,230 @@ impl Spinner {
     }
 }
 
+/// JSON row of `list --all-profiles`: the instance plus the profile it came from.
+#[derive(serde::Serialize)]
+struct ProfiledInstance<'a> {
+    profile: &'a str,
+    #[serde(flatten)]
+    instance: &'a models::Instance,
+}
+
+fn list_all_profiles(all: bool, output: OutputFormat, filters: &[String]) -> error::Result<()> {
+    // Keep stdout clean for JSON consumers.
+    let spinner = (output == OutputFormat::Table).then(|| Spinner::new("Listing instances in every profile..."));
+    let results = aws::list_instances_all_profiles(filters);
+    if let Some(spinner) = spinner {
+        spinner.stop();
+    }
+
+    let mut rows: Vec<(String, models::Instance)> = Vec::new();
+    let mut hidden = 0;
+    for (profile, result) in results {
+        match result {
+            Ok(instances) => {
+                for i in instances {
+                    if all || config::settings().includes_instance(&i.name) {
+                        rows.push((profile.clone(), i));
+                    } else {
+                        hidden += 1;
+                    }
+                }
+            }
+            Err(e) => eprintln!("{}", gray(format!("Skipping profile {}: {}", profile, e))),
+        }
+    }
+
+    if output == OutputFormat::Json {
+        let json: Vec<_> = rows
+            .iter()
+            .map(|(profile, instance)| ProfiledInstance { profile, instance })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&json)?);
+        return Ok(());
+    }
+    println!(
+        "{:<16} {:<22} {:<30} {:<14} {:<12} {:<10} {:<18}",
+        "PROFILE", "INSTANCE ID", "NAME", "TYPE", "STATE", "SSM", "PRIVATE IP"
+    );
+    println!("{}", "-".repeat(127));
+    for (profile, i) in &rows {
+        println!(
+            "{:<16} {:<22} {:<30} {:<14} {:<12} {:<10} {:<18}",
+            profile, i.id, i.name, i.instance_type,
+            i.state.as_str(), i.ssm_status.as_str(),
+            i.private_ip.as_deref().unwrap_or("-"),
+        );
+    }
+    if hidden > 0 {
+        println!("{}", gray(format!(
+            "(filtered: {} hidden by instance_name_include, use --all to show)", hidden
+        )));
+    }
+    Ok(())
+}
+
+/// `idle`: running instances up for more than `hours` with neither a local
+/// tunnel nor an active SSM session anywhere in the account.
+fn run_idle(hours: u64, stop: bool) -> error::Result<()> {
+    let mut instances = aws::list_instances(None)?;
+    instances.retain(|i| config::settings().includes_instance(&i.name));
+    tunnel::attach_tunnels(&mut instances, &tunnel::detect_tunnels());
+    let sessions = aws::active_session_targets(None).unwrap_or_else(|e| {
+        eprintln!("{}", gray(format!("Could not list SSM sessions ({}); checking local tunnels only", e)));
+        Default::default()
+    });
+    let idle: Vec<_> = instances
+        .iter()
+        .filter(|i| aws::uptime_secs(i).is_some_and(|up| up > hours * 3_600))
+        .filter(|i| i.tunnel.is_none() && !sessions.contains(&i.id))
+        .collect();
+    if idle.is_empty() {
+        println!("No running instances idle for more than {}h.", hours);
+        return Ok(());
+    }
+
+    let region = aws::get_region(None);
+    println!("{:<22} {:<30} {:<14} {:<8} {:>10}", "INSTANCE ID", "NAME", "TYPE", "UPTIME", "COST/H");
+    println!("{}", "-".repeat(88));
+    let mut total = 0.0;
+    for i in &idle {
+        let cost = models::instance_hourly_cost(&i.instance_type, &region);
+        total += cost.unwrap_or(0.0);
+        println!(
+            "{:<22} {:<30} {:<14} {:<8} {:>10}",
+            i.id, i.name, i.instance_type,
+            aws::uptime_secs(i).map(aws::short_age).unwrap_or_default(),
+            cost.map(|c| format!("${:.3}", c)).unwrap_or_else(|| "?".into()),
+        );
+    }
+    println!("{}", gray(format!(
+        "{} idle instance{}, ~${:.2}/h on-demand (~${:.0}/month)",
+        idle.len(), if idle.len() == 1 { "" } else { "s" }, total, total * 730.0
+    )));
+
+    if !stop {
+        return Ok(());
+    }
+    if !confirm(&format!("Stop these {} instances?", idle.len())) {
+        println!("Nothing stopped.");
+        return Ok(());
+    }
+    for i in &idle {
+        match aws::stop_instance(&i.id, false, None) {
+            Ok(()) => println!("Stopping {} ({})", i.name, i.id),
+            Err(e) => eprintln!("Failed to stop {} ({}): {}", i.name, i.id, e),
+        }
+    }
+    Ok(())
+}
+
+/// `tunnel-multi`: one direct tunnel per running instance matching `pattern`.
+/// Busy local ports are skipped rather than killed, since the caller did not
+/// name them one by one.
+fn run_tunnel_multi(pattern: &str, base_local_port: u16, remote_port: u16) -> error::Result<()> {
+    let (running, other): (Vec<_>, Vec<_>) = aws::find_instances_by_name(pattern, None)?
+        .into_iter()
+        .partition(|i| i.state == models::InstanceState::Running);
+    for inst in &other {
+        println!("{}", gray(format!("Skipping {} ({}): {}", inst.name, inst.id, inst.state.as_str())));
+    }
+    if running.is_empty() {
+        return Err(error::AppError::NoInstance(format!("{} (none running)", pattern)));
+    }
+
+    let mut targets = Vec::with_capacity(running.len());
+    let mut port = base_local_port;
+    for inst in running {
+        while tunnel::test_port(port) {
+            println!("{}", gray(format!("Port {} is in use, skipping it", port)));
+            port = port.checked_add(1).ok_or_else(|| error::AppError::Tunnel("ran out of local ports".into()))?;
+        }
+        targets.push((inst, port));
+        port = port.checked_add(1).ok_or_else(|| error::AppError::Tunnel("ran out of local ports".into()))?;
+    }
+
+    println!("{}", gray(format!("Starting {} tunnels to *{}*:{}...", targets.len(), pattern, remote_port)));
+    let results = tunnel::start_tunnels_to_instances(&targets, remote_port, None);
+
+    let name_w = targets.iter().map(|(i, _)| i.name.len()).max().unwrap_or(0).max(4);
+    println!();
+    println!("{:<7} {:<name_w$} {:<21} STATUS", "LOCAL", "NAME", "INSTANCE ID");
+    let mut failed = 0;
+    for ((inst, port), result) in targets.iter().zip(&results) {
+        let status = match result {
+            Ok(tp) => format!("OK {}ms", tp.latency_ms.unwrap_or(0)),
+            Err(e) => {
+                failed += 1;
+                red(format!("FAILED: {}", e))
+            }
+        };
+        println!("{:<7} {:<name_w$} {:<21} {}", port, inst.name, inst.id, status);
+    }
+    println!();
+    println!("{}", gray("Stop them with `awsx2 tunnel-stop --port <port>`, or all tunnels with `awsx2 tunnel-stop`."));
+
+    if failed > 0 {
+        return Err(error::AppError::Tunnel(format!("{} of {} tunnels failed to start", failed, targets.len())));
+    }
+    Ok(())
+}
+
+/// Snapshot every volume attached to `inst`, printing one line per snapshot.
+fn snapshot_volumes(inst: &models::Instance, description: Option<&str>) -> error::Result<Vec<String>> {
+    let volumes = aws::get_instance_volumes(&inst.id, None)?;
+    if volumes.is_empty() {
+        println!("{} ({}) has no EBS volumes attached.", inst.name, inst.id);
+    }
+    let mut ids = Vec::new();
+    for v in &volumes {
+        let desc = description
+            .map(String::from)
+            .unwrap_or_else(|| format!("awsx2 snapshot of {} {}", inst.name, v.device));
+        let snap = aws::create_snapshot(&v.volume_id, &desc, None)?;
+        println!("Snapshot {} of {} ({}, {} GiB) started.", snap, v.volume_id, v.device, v.size_gib);
+        ids.push(snap);
+    }
+    Ok(ids)
+}
+
+const SNAPSHOT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Poll until every snapshot is `completed`, showing their progress.
+fn wait_for_snapshots(ids: &[String], timeout: Duration) -> error::Result<()> {
+    let spinner = Spinner::new("Waiting for snapshots...");
+    let deadline = std::time::Instant::now() + timeout;
+    let result = loop {
+        let progress = match aws::snapshot_progress(ids, None) {
+            Ok(p) => p,
+            Err(e) => break Err(e),
+        };
+        if let Some((id, ..)) = progress.iter().find(|(_, state, _)| state == "error") {
+            break Err(error::AppError::Other(format!("Snapshot {} failed", id)));
+        }
+        if progress.iter().all(|(_, state, _)| state == "completed") {
+            break Ok(());
+        }
+        if std::time::Instant::now() >= deadline {
+            break Err(error::AppError::Other(format!(
+                "Timed out after {}s waiting for snapshots", timeout.as_secs(),
+            )));
+        }
+        let summary: Vec<String> = progress.iter().map(|(id, _, pct)| format!("{} {}", id, pct)).collect();
+        spinner.set(&format!("Snapshots: {}", summary.join(", ")));
+        std::thread::sleep(SNAPSHOT_POLL_INTERVAL);
+    };
+    spinner.stop();
+    result
+}
+
+/// `aws::wait_for_state` behind a spinner.
+fn wait_with_spinner(id: &str, target: models::InstanceState, timeout: Duration) -> error::Result<()> {
+    let spinner = Spinner::new(&format!("Waiting for {} to be {}...", id, target.as_str()));
+    let waited = aws::wait_for_state(id, target, timeout, None);
+    spinner.stop();
+    waited
+}
+
 // ── CLI definition ────────────────────────────────────────────────────────────
 
 #[derive(Parser)]
@@ -123,6 +461,26 @@ impl Spinner {
     version = concat!(env!("CARGO_PKG_VERSION"), "-", env!("BUILD_DATE"))
 )]
 struct Cli {
+    /// Named config environment (uses ~/.config/awsx2/envs/<name>/ instead of the base config)
+    #[arg(long, global = true, env = "AWSX2_ENV")]
+    env: Option<String>,
+    /// AWS region for every call (overrides AWS_DEFAULT_REGION / AWS_REGION / profile config)
+    #[arg(long, short = 'r', global = true)]
+    region: Option<String>,
+    /// Print mutating aws commands instead of running them (read-only queries still run)
+    #[arg(long, global = true)]
+    dry_run: bool,
+    /// Log every aws / session / openvpn command before running it (to stderr;
+    /// the TUI writes verbose.log in the config directory)
+    #[arg(long, short = 'v', global = true, env = "AWSX2_VERBOSE", value_parser = clap::builder::BoolishValueParser::new())]
+    verbose: bool,
+    /// Answer yes to confirmation prompts (force-stop, idle --stop, killing
+    /// whatever holds a tunnel's local port)
+    #[arg(long, short = 'y', global = true)]
+    yes: bool,
+    /// TUI palette; "mono" uses bold/underline/reverse only (implied by NO_COLOR)
+    #[arg(long, value_enum, default_value = "color")]
+    theme: tui::theme::ThemeName,
     #[command(subcommand)]
     command: Option<Cmd>,
 }
@@ -130,34 +488,119 @@ struct Cli {
 #[derive(Subcommand)]
 enum Cmd {
     /// List all EC2 instances with state and SSM status
-    List,
+    List {
+        /// Ignore instance_name_include from config.toml and list every instance
+        #[arg(long)]
+        all: bool,
+        /// Output format
+        #[arg(long, value_enum, default_value = "table")]
+        output: OutputFormat,
+        /// Server-side filter, repeatable (e.g. tag:Environment=prod, instance-state-name=running)
+        #[arg(long = "filter", value_name = "KEY=VALUE")]
+        filters: Vec<String>,
+        /// Query every profile in ~/.aws/config and ~/.aws/credentials (adds a PROFILE column)
+        #[arg(long)]
+        all_profiles: bool,
+    },
     /// Start an EC2 instance (uses INSTANCE_NAME env or --name)
     Start {
         #[arg(long, env = "INSTANCE_NAME")]
         name: String,
+        /// Block until the instance is running
+        #[arg(long)]
+        wait: bool,
+        /// Seconds to wait with --wait before failing
+        #[arg(long, default_value_t = 600, requires = "wait")]
+        timeout: u64,
+        /// With --wait, also wait for the SSM agent to report Online
+        #[arg(long, requires = "wait")]
+        ssm: bool,
     },
     /// Stop an EC2 instance gracefully
     Stop {
         #[arg(long, env = "INSTANCE_NAME")]
         name: String,
+        /// Block until the instance is stopped
+        #[arg(long)]
+        wait: bool,
+        /// Seconds to wait with --wait before failing
+        #[arg(long, default_value_t = 600, requires = "wait")]
+        timeout: u64,
     },
     /// Force-stop an EC2 instance (like pulling the power cord)
     ForceStop {
         #[arg(long, env = "INSTANCE_NAME")]
         name: String,
+        /// Block until the instance is stopped
+        #[arg(long)]
+        wait: bool,
+        /// Seconds to wait with --wait before failing
+        #[arg(long, default_value_t = 600, requires = "wait")]
+        timeout: u64,
     },
-    /// Switch instance type to gpu (g4dn.4xlarge) or cpu (m6i.2xlarge)
-    Switch {
-        /// Target type: "gpu" or "cpu"
-        target: String,
+    /// Reboot an EC2 instance (faster than stop + start)
+    Reboot {
         #[arg(long, env = "INSTANCE_NAME")]
         name: String,
     },
+    /// Launch a new instance from a launch template and tag it with a Name
+    Launch {
+        /// Name tag for the new instance
+        #[arg(long)]
+        name: String,
+        /// Launch template ID (lt-…) or name (default: launch_template from config.toml)
+        #[arg(long)]
+        template: Option<String>,
+        /// Override the template's instance type
+        #[arg(long)]
+        instance_type: Option<String>,
+    },
+    /// Change the instance type (stops the instance first if needed); without a
+    /// target, list recently used types
+    Switch {
+        /// Instance type (e.g. c6i.xlarge), or the aliases "gpu" / "cpu" (g4dn.4xlarge / m6i.2xlarge
+        /// unless gpu_instance_type / cpu_instance_type are set in config.toml)
+        #[arg(requires = "name")]
+        target: Option<String>,
+        #[arg(long, env = "INSTANCE_NAME")]
+        name: Option<String>,
+        /// Start the instance again once the type has been changed
+        #[arg(long)]
+        start: bool,
+        /// Seconds to wait for the instance to stop
+        #[arg(long, default_value_t = 600)]
+        timeout: u64,
+        /// Snapshot every attached EBS volume once stopped, before the change
+        #[arg(long)]
+        snapshot: bool,
+    },
     /// Show instance status
     Status {
         #[arg(long, env = "INSTANCE_NAME")]
         name: String,
     },
+    /// List running instances with no tunnel or SSM session, up longer than
+    /// --hours: candidates to stop
+    Idle {
+        /// Minimum uptime in hours
+        #[arg(long, default_value_t = 8)]
+        hours: u64,
+        /// Stop them all (asks first unless --yes)
+        #[arg(long)]
+        stop: bool,
+    },
+    /// Internal: keep one tunnel's SSM session warm until the tunnel goes away
+    #[command(hide = true)]
+    Keepalive {
+        #[arg(long)]
+        port: u16,
+        /// Tunnel session pid to watch
+        #[arg(long)]
+        pid: u32,
+        /// Seconds between requests
+        #[arg(long, default_value_t = 300)]
+        interval: u64,
+    },
     /// Run aws sso login
     Login {
         /// AWS profile (defaults to $AWS_PROFILE)
@@ -166,19 +609,89 @@ enum Cmd {
     /// Resolve a URL/hostname to its EC2/ALB/Fargate resource
     Resolve {
         url: String,
+        /// Print the report as JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Open an interactive SSM shell on an EC2 instance by name pattern
+    Shell {
+        /// Instance id, exact Name tag, or a substring of the Name tag
+        pattern: String,
+    },
+    /// Add or overwrite tags on an instance
+    Tag {
+        /// Instance id, exact Name tag, or a substring of the Name tag
+        pattern: String,
+        /// Tags to set, e.g. `Name=my-server Env=dev`
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// Snapshot every EBS volume attached to an instance
+    Snapshot {
+        /// Instance id, exact Name tag, or a substring of the Name tag
+        pattern: String,
+        /// Snapshot description (default: "awsx2 snapshot of <name> <device>")
+        #[arg(long)]
+        description: Option<String>,
+        /// Block until every snapshot has completed
+        #[arg(long)]
+        wait: bool,
+        /// Seconds to wait with --wait before failing
+        #[arg(long, default_value_t = 3600, requires = "wait")]
+        timeout: u64,
+    },
+    /// Print an instance's serial console log (boot messages, cloud-init)
+    Console {
+        /// Instance id, exact Name tag, or a substring of the Name tag
+        pattern: String,
+    },
+    /// Run a one-off shell command on an EC2 instance via SSM send-command
+    SsmRun {
+        /// Instance id, exact Name tag, or a substring of the Name tag
+        pattern: String,
+        /// Command to run (after `--`), e.g. `-- df -h`
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+        /// Seconds to wait for the command to finish
+        #[arg(long, default_value_t = 60)]
+        timeout: u64,
     },
     /// Open an SSM port-forwarding tunnel to an EC2 instance by name pattern
     Tunnel {
-        /// Substring to match against EC2 Name tags
+        /// Instance id, exact Name tag, or a substring of the Name tag
         pattern: String,
         /// Local port to listen on
         local_port: u16,
-        /// Remote port on the instance (default: 8000)
-        #[arg(default_value = "8000")]
-        remote_port: u16,
+        /// Remote port on the instance (default: default_remote_port from config.toml, 8000),
+        /// or on --remote-host (default: default_remote_host_port, 8501)
+        remote_port: Option<u16>,
+        /// Forward to this private IP or hostname through the matched instance
+        /// (AWS-StartPortForwardingSessionToRemoteHost) instead of to the instance itself
+        #[arg(long, value_name = "HOST")]
+        remote_host: Option<String>,
         /// Bind address (default: 0.0.0.0 for Docker/external access)
         #[arg(long, default_value = "0.0.0.0")]
         bind: String,
+        #[command(flatten)]
+        run: TunnelRunArgs,
+        #[command(flatten)]
+        doc: DocumentArgs,
+    },
+    /// Open the same tunnel to every instance whose name matches, on
+    /// sequential local ports
+    TunnelMulti {
+        /// Substring to match against EC2 Name tags; every running match gets a tunnel
+        pattern: String,
+        /// First local port; the next matches get the following free ports
+        base_local_port: u16,
+        /// Remote port on each instance
+        remote_port: u16,
+        /// Poke each tunnel every few minutes so its SSM session does not
+        /// time out while idle (tunnel_keepalive in config.toml)
+        #[arg(long)]
+        keepalive: bool,
+        #[command(flatten)]
+        doc: DocumentArgs,
     },
     /// Tunnel to any internal URL (smart ALB resolution + bastion fallback)
     TunnelUrl {
@@ -189,16 +702,30 @@ enum Cmd {
         /// Set up nginx reverse proxy so the URL works directly in the browser
         #[arg(long)]
         proxy: bool,
+        /// With --proxy: also serve HTTPS on 443 with a generated self-signed cert
+        #[arg(long, requires = "proxy")]
+        tls: bool,
+        /// Open the tunnel (or, with --proxy, the proxied URL) in the default browser once it is up
+        #[arg(long)]
+        open: bool,
         /// Bind address (default: 0.0.0.0 for Docker/external access)
         #[arg(long, default_value = "0.0.0.0")]
         bind: String,
+        #[command(flatten)]
+        run: TunnelRunArgs,
+        #[command(flatten)]
+        doc: DocumentArgs,
     },
     /// Tunnel to EC2 or Fargate by resolving a URL's DNS
     TunnelDns {
         url: String,
         local_port: u16,
-        #[arg(default_value = "8501")]
-        remote_port: u16,
+        /// Remote port (default: default_remote_host_port from config.toml, 8501)
+        remote_port: Option<u16>,
+        #[command(flatten)]
+        run: TunnelRunArgs,
+        #[command(flatten)]
+        doc: DocumentArgs,
     },
     /// Tunnel to a remote host via a specific bastion
     TunnelRemote {
@@ -207,37 +734,75 @@ enum Cmd {
         /// Private IP or hostname of the target
         host: String,
         local_port: u16,
-        #[arg(default_value = "8501")]
-        remote_port: u16,
+        /// Remote port (default: default_remote_host_port from config.toml, 8501)
+        remote_port: Option<u16>,
+        #[command(flatten)]
+        run: TunnelRunArgs,
+        #[command(flatten)]
+        doc: DocumentArgs,
+    },
+    /// Tunnel to a remote host via any SSM-online instance that can reach it
+    /// (no bastion needed)
+    TunnelViaAny {
+        /// Private IP or hostname of the target
+        host: String,
+        local_port: u16,
+        /// Remote port (default: default_remote_host_port from config.toml, 8501)
+        remote_port: Option<u16>,
+        #[command(flatten)]
+        run: TunnelRunArgs,
+        #[command(flatten)]
+        doc: DocumentArgs,
+    },
+    /// Re-establish a dropped tunnel from its recorded parameters
+    TunnelRestart {
+        local_port: u16,
+    },
+    /// List active SSM tunnels (what the TUI Tunnels tab shows)
+    TunnelList {
+        /// Print a JSON array instead of a table (same as --format json)
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+        /// table, json, or prometheus (text exposition for node_exporter)
+        #[arg(long, value_enum, default_value_t = TunnelListFormat::Table)]
+        format: TunnelListFormat,
+    },
+    /// Kill all running SSM tunnel processes, or just one with --port / --pid
+    TunnelStop {
+        /// Stop only the tunnel on this local port (a --bind forwarder port works too)
+        #[arg(long, conflicts_with = "pid")]
+        port: Option<u16>,
+        /// Stop only the tunnel with this PID (see tunnel-list)
+        #[arg(long)]
+        pid: Option<u32>,
     },
-    /// Kill all running SSM tunnel processes
-    TunnelStop,
     /// Test if a local tunnel port is open
     TunnelTest {
         local_port: u16,
     },
+    /// Measure latency and throughput of an active tunnel with repeated HTTP requests
+    TunnelBench {
+        local_port: u16,
+        /// How long to run, in seconds
+        #[arg(long, default_value_t = 10)]
+        seconds: u64,
+    },
     /// List ECR images (like `docker images`). Omit repository to scan all repos.
     EcrImages {
         /// ECR repository name (omit to list all repositories)
         repository: Option<String>,
-        /// AWS region (overrides profile/env default)
-        #[arg(long, short = 'r')]
-        region: Option<String>,
         /// Show only the newest image per tag prefix (filter out older builds)
         #[arg(long)]
         latest: bool,
     },
     /// Act as SSH ProxyCommand: resolve EC2 Name tag to instance ID and exec SSM session
     SsmProxy {
-        /// EC2 Name tag to resolve
+        /// EC2 Name tag (or instance ID) to resolve
         #[arg(long)]
         name: String,
         /// SSH port (passed by SSH as %p)
         #[arg(long, default_value = "22")]
         port: String,
-        /// AWS region (defaults to configured region)
-        #[arg(long)]
-        region: Option<String>,
     },
     /// Generate ~/.ssh/config entries for all running EC2 instances (SSM-online)
     SshConfig {
@@ -247,6 +812,12 @@ enum Cmd {
         /// SSH user (default: ec2-user)
         #[arg(long, default_value = "ec2-user")]
         user: String,
+        /// Write a standalone file (e.g. ~/.ssh/config.d/awsx2) instead of merging into ~/.ssh/config
+        #[arg(long, value_name = "PATH")]
+        write: Option<String>,
+        /// Proxy through `awsx2 ssm-proxy`, which pushes your SSH public key first
+        #[arg(long)]
+        push_key: bool,
     },
     /// Print the raw AWS CLI commands used behind the scenes
     Cheatcodes,
@@ -263,17 +834,102 @@ enum VpnAction {
     Connect {
         /// MFA/TOTP code from your authenticator app
         mfa: Option<String>,
+        /// Named VPN config (~/.config/awsx2/vpn/<name>.json); default is vpn.json
+        #[arg(long)]
+        config: Option<String>,
+    },
+    /// List saved VPN configs
+    List,
+    /// Check the SAML port, openvpn, the .ovpn file and helper tools
+    Doctor {
+        /// Named VPN config to check (default: vpn.json)
+        #[arg(long)]
+        config: Option<String>,
+    },
+    /// Print the most recent openvpn log
+    Logs {
+        /// Keep printing new output as it is written (like tail -f)
+        #[arg(long, short = 'f')]
+        follow: bool,
     },
     /// Disconnect active VPN
     Disconnect,
-    /// Show VPN connection status
-    Status,
+    /// Show VPN connection status, routes and split/full tunnel mode
+    Status {
+        /// Named VPN config whose DNS settings to report (default: vpn.json)
+        #[arg(long)]
+        config: Option<String>,
+        /// Print the status as JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
     /// Configure VPN credentials and .ovpn file path
     Setup(VpnSetupArgs),
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum TunnelListFormat {
+    Table,
+    Json,
+    Prometheus,
+}
+
+/// Options shared by the commands that open a single tunnel.
+#[derive(Args)]
+struct TunnelRunArgs {
+    /// Stay attached and tear the tunnel down on Ctrl-C / SIGTERM
+    #[arg(long)]
+    foreground: bool,
+    /// Once the tunnel is up, GET this path (e.g. /health) and report the
+    /// status code; a non-2xx/3xx answer warns but keeps the tunnel open
+    #[arg(long, value_name = "PATH")]
+    health_check: Option<String>,
+    /// Poke the tunnel every few minutes so the SSM session does not
+    /// time out while idle (tunnel_keepalive in config.toml)
+    #[arg(long)]
+    keepalive: bool,
+}
+
+/// Custom SSM document options shared by the tunnel commands.
+#[derive(Args)]
+struct DocumentArgs {
+    /// SSM document to start the session with instead of the AWS-managed one
+    /// (ssm_document / ssm_remote_document in config.toml)
+    #[arg(long, value_name = "NAME")]
+    document: Option<String>,
+    /// Extra parameter for a custom --document, repeatable
+    #[arg(long = "document-param", value_name = "KEY=VALUE")]
+    document_params: Vec<String>,
+}
+
+impl DocumentArgs {
+    fn apply(self) -> error::Result<()> {
+        let extra = self
+            .document_params
+            .iter()
+            .map(|spec| match spec.split_once('=') {
+                Some((k, v)) if !k.trim().is_empty() => Ok((k.trim().to_string(), v.to_string())),
+                _ => Err(error::AppError::Other(format!(
+                    "Invalid --document-param '{}' — expected KEY=VALUE", spec
+                ))),
+            })
+            .collect::<error::Result<Vec<_>>>()?;
+        tunnel::set_document(self.document, extra);
+        Ok(())
+    }
+}
+
 #[derive(Args)]
 struct VpnSetupArgs {
+    /// Named VPN config to create/update (default: vpn.json)
+    #[arg(long)]
+    config: Option<String>,
     /// SSO username/email
     #[arg(long)]
     username: Option<String>,
@@ -289,90 +945,268 @@ struct VpnSetupArgs {
     /// DNS routing domain for VPN (e.g. ~internal.example.com)
     #[arg(long)]
     dns_domain: Option<String>,
+    /// Base32 TOTP secret — MFA codes are then generated automatically ("" to remove)
+    #[arg(long)]
+    totp_secret: Option<String>,
+    /// Local port for the SAML callback (default: saml_listen_port from config.toml, 35001;
+    /// next ports tried if busy)
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    saml_port: Option<u16>,
 }
 
-const GPU_TYPE: &str = "g4dn.4xlarge";
-const CPU_TYPE: &str = "m6i.2xlarge";
-
 // ── Entry point ───────────────────────────────────────────────────────────────
 
 fn main() {
     let cli = Cli::parse();
+    if let Some(ref env) = cli.env {
+        if let Err(e) = config::set_env(env) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+    aws::set_region_override(cli.region.clone());
+    aws::set_dry_run(cli.dry_run);
+    ASSUME_YES.store(cli.yes, Ordering::Relaxed);
+    if cli.verbose {
+        aws::set_verbose(Some(match cli.command {
+            None => aws::VerboseLog::File(config::config_dir().join("verbose.log")),
+            Some(_) => aws::VerboseLog::Stderr,
+        }));
+    }
+    if cli.dry_run && cli.command.is_none() {
+        eprintln!("Error: --dry-run only applies to CLI subcommands, not the TUI");
+        std::process::exit(2);
+    }
+    if let Err(e) = config::load_settings() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    let deps: Vec<&str> = match &cli.command {
+        None => vec!["aws"],
+        // `--dry-run` stops before any session, so it never needs the plugin.
+        Some(cmd) => cli_dependencies(cmd).iter().copied()
+            .filter(|d| !(aws::dry_run() && *d == aws::SSM_PLUGIN))
+            .collect(),
+    };
+    if let Err(e) = aws::check_dependencies(&deps) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
     match cli.command {
         None => {
-            if let Err(e) = run_tui() {
+            if let Err(e) = run_tui(tui::theme::Theme::from_env(cli.theme)) {
                 eprintln!("TUI error: {}", e);
                 std::process::exit(1);
             }
         }
-        Some(cmd) => {
-            if let Err(e) = run_cli(cmd) {
+        Some(cmd) => match run_cli(cmd) {
+            Ok(()) => {}
+            Err(error::AppError::DryRun) => {
+                eprintln!("[dry-run] stopped before starting a session");
+            }
+            Err(e) => {
                 eprintln!("Error: {}", e);
                 std::process::exit(1);
             }
-        }
+        },
     }
 }
 
 // ── Non-interactive CLI ───────────────────────────────────────────────────────
 
+/// External tools a command needs, checked before it runs.
+fn cli_dependencies(cmd: &Cmd) -> &'static [&'static str] {
+    match cmd {
+        Cmd::Cheatcodes
+        | Cmd::Keepalive { .. }
+        | Cmd::Vpn { .. }
+        | Cmd::TunnelList { .. }
+        | Cmd::TunnelStop { .. }
+        | Cmd::TunnelTest { .. }
+        | Cmd::TunnelBench { .. } => &[],
+        Cmd::Shell { .. }
+        | Cmd::Tunnel { .. }
+        | Cmd::TunnelMulti { .. }
+        | Cmd::TunnelUrl { .. }
+        | Cmd::TunnelDns { .. }
+        | Cmd::TunnelRemote { .. }
+        | Cmd::TunnelViaAny { .. }
+        | Cmd::TunnelRestart { .. }
+        | Cmd::SsmProxy { .. } => &["aws", aws::SSM_PLUGIN],
+        _ => &["aws"],
+    }
+}
+
 fn run_cli(cmd: Cmd) -> error::Result<()> {
     match cmd {
-        Cmd::List => {
-            let instances = aws::list_instances(None)?;
+        Cmd::List { all, output, filters, all_profiles } => {
+            if all_profiles {
+                return list_all_profiles(all, output, &filters);
+            }
+            let mut instances = aws::list_instances_filtered(&filters, None)?;
+            let total = instances.len();
+            if !all {
+                instances.retain(|i| config::settings().includes_instance(&i.name));
+            }
+            if output == OutputFormat::Json {
+                println!("{}", serde_json::to_string_pretty(&instances)?);
+                return Ok(());
+            }
+            let tag_column = config::settings().tag_column.as_deref();
+            let tag_header = tag_column.map(|k| format!("{:<16} ", k.to_uppercase())).unwrap_or_default();
             println!(
-                "{:<22} {:<30} {:<14} {:<12} {:<10} {:<18}",
-                "INSTANCE ID", "NAME", "TYPE", "STATE", "SSM", "PRIVATE IP"
+                "{:<22} {:<30} {}{:<14} {:<12} {:<10} {:<18} UPTIME",
+                "INSTANCE ID", "NAME", tag_header, "TYPE", "STATE", "SSM", "PRIVATE IP"
             );
-            println!("{}", "-".repeat(110));
+            println!("{}", "-".repeat(117 + tag_header.len()));
             for i in &instances {
+                let tag = tag_column
+                    .map(|k| format!("{:<16} ", i.tags.get(k).map_or("-", String::as_str)))
+                    .unwrap_or_default();
+                // Last column, so colouring it can't break the alignment.
+                let uptime = match aws::uptime_secs(i) {
+                    Some(up) if aws::long_running(i) => red(format!("{} (long-running)", aws::short_age(up))),
+                    Some(up) => aws::short_age(up),
+                    None => "-".into(),
+                };
                 println!(
-                    "{:<22} {:<30} {:<14} {:<12} {:<10} {:<18}",
-                    i.id, i.name, i.instance_type,
+                    "{:<22} {:<30} {}{:<14} {:<12} {:<10} {:<18} {}",
+                    i.id, i.name, tag, i.instance_type,
                     i.state.as_str(), i.ssm_status.as_str(),
                     i.private_ip.as_deref().unwrap_or("-"),
+                    uptime,
                 );
             }
+            if instances.len() < total {
+                println!("{}", gray(format!(
+                    "(filtered: {} hidden by instance_name_include, use --all to show)",
+                    total - instances.len()
+                )));
+            }
         }
 
-        Cmd::Start { name } => {
+        Cmd::Start { name, wait, timeout, ssm } => {
             let inst = aws::find_instance_by_name(&name, None)?;
             println!("{}", gray(format!("Starting {} ({})...", inst.name, inst.id)));
             aws::start_instance(&inst.id, None)?;
             println!("Start command sent.");
+            if wait {
+                let started = Instant::now();
+                let timeout = Duration::from_secs(timeout);
+                wait_with_spinner(&inst.id, models::InstanceState::Running, timeout)?;
+                println!("{} is running.", inst.id);
+                if ssm {
+                    let spinner = Spinner::new(&format!("Waiting for SSM agent on {}...", inst.id));
+                    let waited = aws::wait_for_ssm_online(&inst.id, timeout.saturating_sub(started.elapsed()), None);
+                    spinner.stop();
+                    waited?;
+                    println!("SSM agent is Online.");
+                }
+            }
         }
 
-        Cmd::Stop { name } => {
+        Cmd::Stop { name, wait, timeout } => {
             let inst = aws::find_instance_by_name(&name, None)?;
             println!("{}", gray(format!("Stopping {} ({})...", inst.name, inst.id)));
             aws::stop_instance(&inst.id, false, None)?;
             println!("Stop command sent.");
+            if wait {
+                wait_with_spinner(&inst.id, models::InstanceState::Stopped, Duration::from_secs(timeout))?;
+                println!("{} is stopped.", inst.id);
+            }
         }
 
-        Cmd::ForceStop { name } => {
+        Cmd::ForceStop { name, wait, timeout } => {
             let inst = aws::find_instance_by_name(&name, None)?;
+            if !confirm(&format!("Force-stop '{}' ({})? This may cause data loss!", inst.name, inst.id)) {
+                println!("Aborted.");
+                return Ok(());
+            }
             println!("{}", gray(format!("Force-stopping {} ({})...", inst.name, inst.id)));
             aws::stop_instance(&inst.id, true, None)?;
             println!("Force-stop command sent.");
+            if wait {
+                wait_with_spinner(&inst.id, models::InstanceState::Stopped, Duration::from_secs(timeout))?;
+                println!("{} is stopped.", inst.id);
+            }
         }
 
-        Cmd::Switch { target, name } => {
-            let new_type = match target.to_lowercase().as_str() {
-                "gpu" => GPU_TYPE,
-                "cpu" => CPU_TYPE,
-                other => {
-                    eprintln!("Unknown target '{}'. Use 'gpu' or 'cpu'.", other);
-                    std::process::exit(1);
-                }
-            };
+        Cmd::Reboot { name } => {
             let inst = aws::find_instance_by_name(&name, None)?;
-            println!("{}", gray(format!("Switching {} ({}) to {}...", inst.name, inst.id, new_type)));
-            if inst.state == models::InstanceState::Running {
-                println!("{}", gray("Stopping instance first..."));
-                aws::stop_instance(&inst.id, false, None)?;
+            println!("{}", gray(format!("Rebooting {} ({})...", inst.name, inst.id)));
+            aws::reboot_instance(&inst.id, None)?;
+            println!("Reboot command sent.");
+        }
+
+        Cmd::Launch { name, template, instance_type } => {
+            let template = template
+                .or_else(|| config::settings().launch_template.clone())
+                .ok_or_else(|| error::AppError::Other(
+                    "No launch template given (use --template or set launch_template in config.toml)".into()
+                ))?;
+            println!("{}", gray(format!("Launching '{}' from template {}...", name, template)));
+            let id = aws::launch_from_template(&template, &name, instance_type.as_deref(), None)?;
+            println!("Launched {} ({}).", name, id);
+        }
+
+        Cmd::Switch { target: None, .. } => {
+            let settings = config::settings();
+            println!("Recently used instance types:");
+            let recent = config::recent_instance_types();
+            if recent.is_empty() {
+                println!("  {}", gray("(none yet)"));
             }
-            aws::modify_instance_type(&inst.id, new_type, None)?;
+            for t in &recent {
+                println!("  {}", t);
+            }
+            println!("Aliases:");
+            println!("  gpu  {}", settings.gpu_instance_type);
+            println!("  cpu  {}", settings.cpu_instance_type);
+        }
+
+        Cmd::Switch { target: Some(target), name, start, timeout, snapshot } => {
+            let new_type = aws::resolve_instance_type(&target)?;
+            // `target` requires `--name`, so clap has already checked it.
+            let name = name.unwrap_or_default();
+            let inst = aws::find_instance_by_name(&name, None)?;
+            if inst.instance_type == new_type {
+                println!("{} ({}) is already {}.", inst.name, inst.id, new_type);
+                return Ok(());
+            }
+            println!("{}", gray(format!("Switching {} ({}) from {} to {}...",
+                inst.name, inst.id, inst.instance_type, new_type)));
+            match inst.state {
+                models::InstanceState::Stopped | models::InstanceState::Stopping => {}
+                models::InstanceState::Running | models::InstanceState::Pending => {
+                    // EC2 rejects StopInstances while the instance is still pending.
+                    if inst.state == models::InstanceState::Pending {
+                        println!("{}", gray("Instance is still starting; waiting for it to run..."));
+                        wait_with_spinner(&inst.id, models::InstanceState::Running, Duration::from_secs(timeout))?;
+                    }
+                    println!("{}", gray("Stopping instance first..."));
+                    aws::stop_instance(&inst.id, false, None)?;
+                }
+                models::InstanceState::Other(ref s) => {
+                    return Err(error::AppError::Other(format!(
+                        "{} ({}) is {}; the instance type can only be changed while stopped.",
+                        inst.name, inst.id, s,
+                    )));
+                }
+            }
+            if inst.state != models::InstanceState::Stopped {
+                wait_with_spinner(&inst.id, models::InstanceState::Stopped, Duration::from_secs(timeout))?;
+            }
+            if snapshot {
+                let description = format!("awsx2 pre-switch snapshot of {} ({} -> {})",
+                    inst.name, inst.instance_type, new_type);
+                snapshot_volumes(&inst, Some(&description))?;
+            }
+            aws::modify_instance_type(&inst.id, &new_type, None)?;
             println!("Instance type changed to {}.", new_type);
+            if start {
+                aws::start_instance(&inst.id, None)?;
+                println!("Starting {} ({}).", inst.name, inst.id);
+            }
         }
 
         Cmd::Status { name } => {
@@ -382,12 +1216,23 @@ fn run_cli(cmd: Cmd) -> error::Result<()> {
             println!("  ID:         {}", inst.id);
             println!("  Type:       {}", inst.instance_type);
             println!("  State:      {}", inst.state.as_str());
-            println!("  SSM:        {}", inst.ssm_status.as_str());
+            println!("  SSM:        {}", aws::ssm_health(&inst, true));
+            if let Some(up) = aws::uptime_secs(&inst) {
+                let flag = if aws::long_running(&inst) { " (long-running)" } else { "" };
+                println!("  Uptime:     {}{}", aws::short_age(up), flag);
+            }
             println!("  Private IP: {}", inst.private_ip.as_deref().unwrap_or("N/A"));
             println!("  Public IP:  {}", inst.public_ip.as_deref().unwrap_or("N/A"));
+            if !inst.ipv6_addresses.is_empty() {
+                println!("  IPv6:       {}", inst.ipv6_addresses.join(", "));
+            }
             println!("-------------------------------------");
         }
 
+        Cmd::Idle { hours, stop } => {
+            run_idle(hours, stop)?;
+        }
+
         Cmd::Login { profile } => {
             let profile_str = profile
                 .or_else(|| std::env::var("AWS_PROFILE").ok())
@@ -400,15 +1245,24 @@ fn run_cli(cmd: Cmd) -> error::Result<()> {
             println!("{}", aws::get_caller_identity(profile_opt)?);
         }
 
-        Cmd::Resolve { url } => {
-            println!("{}", aws::resolve_dns_report(&url, None)?);
+        Cmd::Resolve { url, json } => {
+            if json {
+                let report = aws::resolve_dns_detail(&url, None)?;
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!("{}", aws::resolve_dns_report(&url, None)?);
+            }
         }
 
-        Cmd::Tunnel { pattern, local_port, remote_port, bind } => {
-            if tunnel::test_port(local_port) {
-                if !confirm_and_kill_port(local_port) {
-                    return Ok(());
-                }
+        Cmd::Tunnel { pattern, local_port, remote_port, remote_host, bind, run, doc } => {
+            tunnel::set_keepalive(run.keepalive);
+            doc.apply()?;
+            let remote_port = remote_port.unwrap_or(match remote_host {
+                Some(_) => config::settings().default_remote_host_port,
+                None => config::settings().default_remote_port,
+            });
+            if tunnel::test_port(local_port) && !confirm_and_kill_port(local_port) {
+                return Ok(());
             }
 
             let needs_forwarder = bind != "127.0.0.1";
@@ -418,23 +1272,56 @@ fn run_cli(cmd: Cmd) -> error::Result<()> {
                 local_port
             };
 
-            println!("{}", gray(format!("Starting tunnel: *{}*:{} -> {}:{}", pattern, remote_port, bind, local_port)));
-            let tp = tunnel::start_tunnel_by_pattern(&pattern, ssm_port, remote_port, None)?;
+            let tp = match &remote_host {
+                Some(host) => {
+                    println!("{}", gray(format!(
+                        "Starting tunnel: {}:{} via *{}* -> {}:{}", host, remote_port, pattern, bind, local_port
+                    )));
+                    tunnel::start_remote_tunnel_via_pattern(&pattern, host, ssm_port, remote_port, None)?
+                }
+                None => {
+                    println!("{}", gray(format!("Starting tunnel: *{}*:{} -> {}:{}", pattern, remote_port, bind, local_port)));
+                    tunnel::start_tunnel_by_pattern(&pattern, ssm_port, remote_port, None)?
+                }
+            };
+            let target = match &tp.remote_host {
+                Some(host) => format!("{}:{} via {}", host, tp.remote_port, tp.instance_name),
+                None => format!("{}:{}", tp.instance_name, tp.remote_port),
+            };
 
-            if needs_forwarder {
+            let fwd_pid = if needs_forwarder {
                 let fwd_pid = tunnel::start_bind_forwarder(&bind, local_port, ssm_port)?;
-                println!("Tunnel active: {}:{} -> {}:{} (forwarder pid {})",
-                    bind, local_port, tp.instance_name, tp.remote_port, fwd_pid);
+                println!("Tunnel active: {}:{} -> {} (forwarder pid {})", bind, local_port, target, fwd_pid);
+                Some(fwd_pid)
             } else {
-                println!("Tunnel active: localhost:{} -> {}:{}", tp.local_port, tp.instance_name, tp.remote_port);
+                println!("Tunnel active: localhost:{} -> {}", tp.local_port, target);
+                None
+            };
+
+            if let Some(path) = &run.health_check {
+                report_health(local_port, path, remote_host.as_deref().unwrap_or("localhost"));
+            }
+
+            if run.foreground {
+                run_foreground(&tp, fwd_pid, None);
             }
         }
 
-        Cmd::TunnelUrl { url, local_port, remote_port, proxy, bind } => {
-            if tunnel::test_port(local_port) {
-                if !confirm_and_kill_port(local_port) {
-                    return Ok(());
-                }
+        Cmd::Keepalive { port, pid, interval } => {
+            tunnel::keepalive_loop(port, pid, Duration::from_secs(interval));
+        }
+
+        Cmd::TunnelMulti { pattern, base_local_port, remote_port, keepalive, doc } => {
+            tunnel::set_keepalive(keepalive);
+            doc.apply()?;
+            run_tunnel_multi(&pattern, base_local_port, remote_port)?;
+        }
+
+        Cmd::TunnelUrl { url, local_port, remote_port, proxy, tls, open, bind, run, doc } => {
+            tunnel::set_keepalive(run.keepalive);
+            doc.apply()?;
+            if tunnel::test_port(local_port) && !confirm_and_kill_port(local_port) {
+                return Ok(());
             }
             let host = aws::strip_url_to_host(&url);
             println!("{}", gray(format!("Resolving {}...", host)));
@@ -447,9 +1334,9 @@ fn run_cli(cmd: Cmd) -> error::Result<()> {
             };
 
             // Smart path: URL → ALB → target group → healthy backend → SG → hop instance
-            let tunneled = match try_alb_tunnel(&host, ssm_port, remote_port) {
+            let (tp, fwd_pid) = match try_alb_tunnel(&host, ssm_port, remote_port) {
                 Ok(Some(tp)) => {
-                    if needs_forwarder {
+                    let fwd_pid = if needs_forwarder {
                         let fwd_pid = tunnel::start_bind_forwarder(&bind, local_port, ssm_port)?;
                         println!(
                             "Tunnel active: {}:{} -> {}:{} via {} (forwarder pid {})",
@@ -459,6 +1346,7 @@ fn run_cli(cmd: Cmd) -> error::Result<()> {
                             tp.instance_name,
                             fwd_pid,
                         );
+                        Some(fwd_pid)
                     } else {
                         println!(
                             "Tunnel active: localhost:{} -> {}:{} via {}",
@@ -467,59 +1355,195 @@ fn run_cli(cmd: Cmd) -> error::Result<()> {
                             tp.remote_port,
                             tp.instance_name,
                         );
-                    }
-                    true
+                        None
+                    };
+                    (tp, fwd_pid)
                 }
                 _ => {
                     // Fallback: try all SSM-online bastions directly
                     println!("{}", gray("  Trying bastions..."));
-                    let tp = tunnel::start_url_tunnel_via_any_bastion(&url, ssm_port, remote_port, None)?;
-                    if needs_forwarder {
+                    let tp = tunnel::start_url_tunnel_via_any_bastion(
+                        &url, ssm_port, remote_port, None,
+                        |msg| println!("{}", gray(format!("  {}", msg))),
+                    )?;
+                    let fwd_pid = if needs_forwarder {
                         let fwd_pid = tunnel::start_bind_forwarder(&bind, local_port, ssm_port)?;
                         println!(
                             "Tunnel active: {}:{} -> {} via {} (forwarder pid {})",
                             bind, local_port, tp.remote_host.as_deref().unwrap_or("?"), tp.instance_name, fwd_pid
                         );
+                        Some(fwd_pid)
                     } else {
                         println!(
                             "Tunnel active: localhost:{} -> {} via {}",
                             tp.local_port, tp.remote_host.as_deref().unwrap_or("?"), tp.instance_name
                         );
-                    }
-                    true
+                        None
+                    };
+                    (tp, fwd_pid)
                 }
             };
 
-            if tunneled && proxy {
+            if let Some(path) = &run.health_check {
+                report_health(local_port, path, &host);
+            }
+
+            if proxy {
                 println!("{}", gray("Setting up reverse proxy..."));
-                proxy::setup_proxy(&host, local_port)?;
-                println!("Access: http://{}", host);
+                proxy::setup_proxy(&host, local_port, tls)?;
+                tunnel::record_proxy(tp.local_port, &host, tls);
+                println!("Access: {}://{}", if tls { "https" } else { "http" }, host);
+            }
+
+            if open {
+                let target = if proxy {
+                    format!("{}://{}", if tls { "https" } else { "http" }, host)
+                } else {
+                    format!("http://localhost:{}", local_port)
+                };
+                println!("{}", gray(format!("Opening {}", target)));
+                util::open_url_in_browser(&target);
+            }
+
+            if run.foreground {
+                run_foreground(&tp, fwd_pid, proxy.then_some(local_port));
             }
         }
 
-        Cmd::TunnelDns { url, local_port, remote_port } => {
-            if tunnel::test_port(local_port) {
-                if !confirm_and_kill_port(local_port) {
-                    return Ok(());
-                }
+        Cmd::TunnelDns { url, local_port, remote_port, run, doc } => {
+            tunnel::set_keepalive(run.keepalive);
+            doc.apply()?;
+            let remote_port = remote_port.unwrap_or(config::settings().default_remote_host_port);
+            if tunnel::test_port(local_port) && !confirm_and_kill_port(local_port) {
+                return Ok(());
             }
             println!("{}", gray(format!("Resolving {} for tunnel...", url)));
-            let tp = tunnel::start_dns_tunnel(&url, local_port, remote_port, None)?;
+            let target = aws::resolve_dns_to_target(&url, None)?;
+            if let models::TunnelTarget::RemoteViaBastion { ecs_task: Some(ref task), ref bastion_name, .. } = target {
+                println!("Target: {} (via bastion {})", task.describe(), bastion_name);
+            }
+            let tp = tunnel::start_target_tunnel(target, local_port, remote_port, None)?;
             println!("Tunnel active: localhost:{} -> {}:{}", tp.local_port, tp.instance_name, tp.remote_port);
+            if let Some(path) = &run.health_check {
+                report_health(local_port, path, &aws::strip_url_to_host(&url));
+            }
+            if run.foreground {
+                run_foreground(&tp, None, None);
+            }
         }
 
-        Cmd::TunnelRemote { bastion, host, local_port, remote_port } => {
-            if tunnel::test_port(local_port) {
-                if !confirm_and_kill_port(local_port) {
-                    return Ok(());
-                }
+        Cmd::TunnelRemote { bastion, host, local_port, remote_port, run, doc } => {
+            tunnel::set_keepalive(run.keepalive);
+            doc.apply()?;
+            let remote_port = remote_port.unwrap_or(config::settings().default_remote_host_port);
+            if tunnel::test_port(local_port) && !confirm_and_kill_port(local_port) {
+                return Ok(());
             }
             println!("{}", gray(format!("Starting remote tunnel via *{}* -> {}:{}", bastion, host, remote_port)));
             let tp = tunnel::start_remote_tunnel_via_pattern(&bastion, &host, local_port, remote_port, None)?;
             println!("Tunnel active: localhost:{} -> {}:{} via {}", tp.local_port, host, remote_port, tp.instance_name);
+            if let Some(path) = &run.health_check {
+                report_health(local_port, path, &host);
+            }
+            if run.foreground {
+                run_foreground(&tp, None, None);
+            }
+        }
+
+        Cmd::TunnelViaAny { host, local_port, remote_port, run, doc } => {
+            tunnel::set_keepalive(run.keepalive);
+            doc.apply()?;
+            let remote_port = remote_port.unwrap_or(config::settings().default_remote_host_port);
+            if tunnel::test_port(local_port) && !confirm_and_kill_port(local_port) {
+                return Ok(());
+            }
+            let (hop, by_sg) = aws::find_ssm_hop_for_host(&host, remote_port, None)?
+                .ok_or_else(|| error::AppError::Tunnel("no running, SSM-online instance to forward through".into()))?;
+            if by_sg {
+                println!("{}", gray(format!("Hop: {} ({}), allowed to reach port {} by security group rules", hop.name, hop.id, remote_port)));
+            } else {
+                println!("{}", gray(format!("Hop: {} ({}), no security group match; trying any SSM-online instance", hop.name, hop.id)));
+            }
+            let tp = tunnel::start_remote_tunnel_via_instance(&hop.id, &hop.name, &host, local_port, remote_port, None)?;
+            println!("Tunnel active: localhost:{} -> {}:{} via {}", tp.local_port, host, remote_port, tp.instance_name);
+            if let Some(path) = &run.health_check {
+                report_health(local_port, path, &host);
+            }
+            if run.foreground {
+                run_foreground(&tp, None, None);
+            }
+        }
+
+        Cmd::TunnelRestart { local_port } => {
+            println!("{}", gray(format!("Restarting tunnel on port {}...", local_port)));
+            let tp = tunnel::restart_tunnel(local_port, None, |msg| println!("{}", gray(msg)))?;
+            match tp.remote_host {
+                Some(ref host) => println!("Tunnel active: localhost:{} -> {}:{} via {}",
+                    tp.local_port, host, tp.remote_port, tp.instance_name),
+                None => println!("Tunnel active: localhost:{} -> {}:{}",
+                    tp.local_port, tp.instance_name, tp.remote_port),
+            }
         }
 
-        Cmd::TunnelStop => {
+        Cmd::TunnelList { json, format } => {
+            // Same rows as the TUI: live sessions, then registered ones that died.
+            let mut tunnels = tunnel::detect_tunnels();
+            let dead = tunnel::dead_tunnels(&tunnels);
+            tunnels.extend(dead);
+            let format = if json { TunnelListFormat::Json } else { format };
+            if format == TunnelListFormat::Json {
+                println!("{}", serde_json::to_string_pretty(&tunnels)?);
+                return Ok(());
+            }
+            if format == TunnelListFormat::Prometheus {
+                print!("{}", metrics::prometheus(&tunnels));
+                return Ok(());
+            }
+            if tunnels.is_empty() {
+                println!("{}", gray("No active tunnels."));
+                return Ok(());
+            }
+            println!(
+                "{:<8} {:<7} {:<30} {:<30} {:<6} {:>8}",
+                "PID", "LOCAL", "REMOTE", "INSTANCE", "STATUS", "LATENCY"
+            );
+            println!("{}", "-".repeat(94));
+            for t in &tunnels {
+                let remote = format!("{}:{}", t.remote_host.as_deref().unwrap_or(&t.instance_id), t.remote_port);
+                let status = match (t.port_open, t.latency_ms) {
+                    (true, Some(_)) => "OK",
+                    (true, None) => "OPEN",
+                    (false, _) => "DOWN",
+                };
+                println!(
+                    "{:<8} {:<7} {:<30} {:<30} {:<6} {:>8}",
+                    if t.pid == 0 { "-".to_string() } else { t.pid.to_string() },
+                    t.local_port, remote, t.instance_name, status,
+                    t.latency_ms.map(|ms| format!("{}ms", ms)).unwrap_or_else(|| "-".into()),
+                );
+            }
+        }
+
+        Cmd::TunnelStop { port, pid } if port.is_some() || pid.is_some() => {
+            let (tp, freed) = tunnel::stop_one_tunnel(port, pid)?;
+            for p in &freed[1..] {
+                println!("{}", gray(format!("Stopped forwarder on port {}", p)));
+            }
+            for p in &freed {
+                for host in proxy::teardown_proxies_for_port(*p) {
+                    println!("{}", gray(format!("Removed reverse proxy for {}", host)));
+                }
+            }
+            println!(
+                "Stopped tunnel localhost:{} -> {}:{}{}",
+                tp.local_port,
+                tp.remote_host.as_deref().unwrap_or(&tp.instance_name),
+                tp.remote_port,
+                if tp.pid == 0 { " (was already down, forgotten)".to_string() } else { format!(" (PID {})", tp.pid) },
+            );
+        }
+
+        Cmd::TunnelStop { .. } => {
             tunnel::stop_all_tunnels();
             if proxy::has_active_proxies() {
                 println!("{}", gray("Cleaning up reverse proxies..."));
@@ -528,6 +1552,12 @@ fn run_cli(cmd: Cmd) -> error::Result<()> {
             println!("All SSM tunnels stopped.");
         }
 
+        Cmd::TunnelBench { local_port, seconds } => {
+            println!("{}", gray(format!("Benchmarking localhost:{} for {}s...", local_port, seconds)));
+            let result = tunnel::benchmark_port(local_port, Duration::from_secs(seconds))?;
+            println!("{}", result.summary());
+        }
+
         Cmd::TunnelTest { local_port } => {
             if tunnel::test_port(local_port) {
                 println!("Port {} is OPEN (tunnel active).", local_port);
@@ -537,7 +1567,8 @@ fn run_cli(cmd: Cmd) -> error::Result<()> {
             }
         }
 
-        Cmd::EcrImages { repository, region, latest } => {
+        Cmd::EcrImages { repository, latest } => {
+            let region = aws::region_override();
             // If a full ECR URI was given, extract repo name + region from it
             let (repository, region) = match repository {
                 Some(r) => {
@@ -588,7 +1619,7 @@ fn run_cli(cmd: Cmd) -> error::Result<()> {
             } else {
                 let repo_w = images.iter().map(|i| i.repository.len()).max().unwrap_or(10).max(10) + 2;
                 let tag_w  = images.iter().map(|i| i.tag.len()).max().unwrap_or(3).max(3) + 2;
-                println!("{:<repo_w$} {:<tag_w$} {:<14} {:<20} {}", "REPOSITORY", "TAG", "IMAGE ID", "CREATED", "SIZE");
+                println!("{:<repo_w$} {:<tag_w$} {:<14} {:<20} SIZE", "REPOSITORY", "TAG", "IMAGE ID", "CREATED");
                 println!("{}", "-".repeat(repo_w + tag_w + 14 + 20 + 10));
                 for img in &images {
                     println!(
@@ -600,12 +1631,61 @@ fn run_cli(cmd: Cmd) -> error::Result<()> {
             }
         }
 
-        Cmd::SsmProxy { name, port, region } => {
-            run_ssm_proxy(&name, &port, region.as_deref())?;
+        Cmd::Shell { pattern } => {
+            run_ssm_shell(&pattern)?;
         }
 
-        Cmd::SshConfig { dry_run, user } => {
-            run_ssh_config(dry_run, &user)?;
+        Cmd::Tag { pattern, tags } => {
+            let tags = tags.iter().map(|t| aws::parse_tag(t)).collect::<error::Result<Vec<_>>>()?;
+            let inst = aws::find_instance_by_name(&pattern, None)?;
+            aws::create_tags(&inst.id, &tags, None)?;
+            for (key, value) in &tags {
+                println!("Tagged {} ({}): {}={}", inst.name, inst.id, key, value);
+            }
+        }
+
+        Cmd::Snapshot { pattern, description, wait, timeout } => {
+            let inst = aws::find_instance_by_name(&pattern, None)?;
+            let ids = snapshot_volumes(&inst, description.as_deref())?;
+            if wait && !ids.is_empty() && !aws::dry_run() {
+                wait_for_snapshots(&ids, Duration::from_secs(timeout))?;
+                println!("All {} snapshot(s) completed.", ids.len());
+            } else if !ids.is_empty() {
+                println!("{}", gray("Snapshots complete in the background (add --wait to block)."));
+            }
+        }
+
+        Cmd::Console { pattern } => {
+            let inst = aws::find_instance_by_name(&pattern, None)?;
+            match aws::get_console_output(&inst.id, None)? {
+                Some(output) => print!("{}", output),
+                None => println!("{} ({}): {}", inst.name, inst.id, aws::NO_CONSOLE_OUTPUT),
+            }
+        }
+
+        Cmd::SsmRun { pattern, command, timeout } => {
+            let inst = aws::find_instance_by_name(&pattern, None)?;
+            let command = command.join(" ");
+            let spinner = Spinner::new(&format!("Running `{}` on {} ({})...", command, inst.name, inst.id));
+            let result = aws::run_ssm_command_output(&inst.id, &command, Duration::from_secs(timeout), None);
+            spinner.stop();
+            let out = result?;
+            print!("{}", out.stdout);
+            eprint!("{}", out.stderr);
+            let code = out.exit_code.unwrap_or(1);
+            println!("{}", gray(format!("[{}] exit status {}", out.status,
+                out.exit_code.map_or("n/a".to_string(), |c| c.to_string()))));
+            if out.status != "Success" || code != 0 {
+                std::process::exit(if code == 0 { 1 } else { code });
+            }
+        }
+
+        Cmd::SsmProxy { name, port } => {
+            run_ssm_proxy(&name, &port)?;
+        }
+
+        Cmd::SshConfig { dry_run, user, write, push_key } => {
+            run_ssh_config(dry_run, &user, write.as_deref(), push_key)?;
         }
 
         Cmd::Cheatcodes => {
@@ -615,12 +1695,21 @@ fn run_cli(cmd: Cmd) -> error::Result<()> {
         Cmd::Vpn { action } => {
             match action {
                 VpnAction::Setup(args) => {
-                    let mut config = vpn::load_config()?;
+                    let profile = args.config.as_deref();
+                    let mut config = vpn::load_config(profile)?;
                     if let Some(u) = args.username { config.sso_username = u; }
                     if let Some(p) = args.password { config.sso_password = p; }
                     if let Some(o) = args.ovpn { config.ovpn_path = o; }
                     if let Some(d) = args.dns_server { config.dns_server = d; }
                     if let Some(d) = args.dns_domain { config.dns_domain = d; }
+                    if let Some(p) = args.saml_port {
+                        config.saml_port = Some(p).filter(|&p| p != config::settings().saml_listen_port);
+                    }
+                    if let Some(t) = args.totp_secret {
+                        config.totp_secret = Some(t).filter(|t| !t.trim().is_empty());
+                        // Fail at setup time rather than on the next connect.
+                        vpn::totp_code(&config)?;
+                    }
                     // Interactive prompts for missing fields
                     if config.sso_username.is_empty() {
                         eprint!("SSO Username/Email: ");
@@ -628,7 +1717,7 @@ fn run_cli(cmd: Cmd) -> error::Result<()> {
                         std::io::stdin().read_line(&mut s)?;
                         config.sso_username = s.trim().to_string();
                     }
-                    if config.sso_password.is_empty() {
+                    if config.sso_password.is_empty() && !config.password_in_keychain {
                         eprint!("SSO Password: ");
                         let mut s = String::new();
                         std::io::stdin().read_line(&mut s)?;
@@ -640,20 +1729,57 @@ fn run_cli(cmd: Cmd) -> error::Result<()> {
                         std::io::stdin().read_line(&mut s)?;
                         config.ovpn_path = s.trim().to_string();
                     }
-                    vpn::save_config(&config)?;
-                    let path = dirs::config_dir()
-                        .unwrap_or_default()
-                        .join("awsx2")
-                        .join("vpn.json");
-                    println!("VPN config saved to {}", path.display());
+                    vpn::save_config(&mut config, profile)?;
+                    println!("VPN config saved to {}", vpn::config_path(profile).display());
                     println!("  Username: {}", config.sso_username);
+                    println!("  Password: {}", if config.password_in_keychain { "stored in the OS keychain" } else { "stored in the config file (keychain unavailable)" });
                     println!("  OVPN:     {}", config.ovpn_path);
                     println!("  DNS:      {} ({})", config.dns_server, config.dns_domain);
+                    println!("  TOTP:     {}", if config.totp_secret.is_some() { "stored (auto MFA)" } else { "not set (prompt for MFA)" });
                 }
-                VpnAction::Connect { mfa } => {
-                    let config = vpn::load_config()?;
+                VpnAction::List => {
+                    let default = if vpn::config_path(None).exists() { "" } else { "  (not set up)" };
+                    println!("(default)  {}{}", vpn::config_path(None).display(), default);
+                    for name in vpn::list_configs() {
+                        println!("{:<10} {}", name, vpn::config_path(Some(&name)).display());
+                    }
+                }
+                VpnAction::Doctor { config } => {
+                    let config = vpn::load_config(config.as_deref())?;
+                    let checks = vpn::doctor(&config);
+                    for c in &checks {
+                        let mark = if c.ok { "\x1b[32m✓\x1b[0m" } else if c.required { "\x1b[31m✗\x1b[0m" } else { "\x1b[33m✗\x1b[0m" };
+                        println!("{} {:<24} {}", mark, c.label, gray(&c.detail));
+                    }
+                    if checks.iter().any(|c| c.required && !c.ok) {
+                        std::process::exit(1);
+                    }
+                }
+                VpnAction::Logs { follow } => {
+                    let path = vpn::latest_log().ok_or_else(|| error::AppError::Vpn(
+                        "No VPN logs yet — they are written on 'awsx2 vpn connect'".into()
+                    ))?;
+                    eprintln!("{}", gray(format!("==> {} <==", path.display())));
+                    let mut file = std::fs::File::open(&path)?;
+                    std::io::copy(&mut file, &mut std::io::stdout())?;
+                    if follow {
+                        use std::io::Write;
+                        loop {
+                            std::thread::sleep(Duration::from_millis(500));
+                            if std::io::copy(&mut file, &mut std::io::stdout())? > 0 {
+                                std::io::stdout().flush()?;
+                            }
+                        }
+                    }
+                }
+                VpnAction::Connect { mfa, config: profile } => {
+                    let config = vpn::load_config(profile.as_deref())?;
                     let mfa_code = match mfa {
                         Some(code) => code,
+                        None if config.totp_secret.is_some() => {
+                            println!("{}", gray("Using MFA code generated from stored TOTP secret"));
+                            vpn::totp_code(&config)?.unwrap_or_default()
+                        }
                         None => {
                             eprint!("MFA Code: ");
                             let mut s = String::new();
@@ -665,7 +1791,17 @@ fn run_cli(cmd: Cmd) -> error::Result<()> {
                         eprintln!("MFA code is required.");
                         std::process::exit(1);
                     }
-                    let pid = vpn::connect(&config, &mfa_code, |msg| println!("{}", msg))?;
+                    let mut last_phase = None;
+                    let pid = vpn::connect(&config, profile.as_deref(), &mfa_code, |phase, msg| {
+                        if phase == models::VpnPhase::Done {
+                            println!("{}", msg);
+                        } else if last_phase == Some(phase) {
+                            println!("  {}", msg);
+                        } else {
+                            println!("[{}/{}] {}", phase.step(), models::VpnPhase::COUNT, msg);
+                        }
+                        last_phase = Some(phase);
+                    }, || false)?;
                     let ip = vpn::get_vpn_ip().unwrap_or_else(|| "?".into());
                     println!("\nVPN connected and running in background.");
                     println!("  IP:  {}", ip);
@@ -673,16 +1809,27 @@ fn run_cli(cmd: Cmd) -> error::Result<()> {
                     println!("\nUse 'awsx2 vpn disconnect' to stop.");
                 }
                 VpnAction::Disconnect => {
-                    vpn::disconnect();
+                    vpn::disconnect()?;
                     println!("VPN disconnected.");
                 }
-                VpnAction::Status => {
-                    if vpn::is_connected() {
-                        let ip = vpn::get_vpn_ip().unwrap_or_else(|| "unknown".into());
-                        let pid = vpn::find_vpn_pid().map(|p| p.to_string()).unwrap_or_else(|| "?".into());
+                VpnAction::Status { config, json } => {
+                    let status = vpn::status(&vpn::load_config(config.as_deref())?);
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&status)?);
+                    } else if status.connected {
                         println!("VPN: CONNECTED");
-                        println!("  IP:  {}", ip);
-                        println!("  PID: {}", pid);
+                        println!("  IP:     {}", status.ip.as_deref().unwrap_or("unknown"));
+                        println!("  PID:    {}", status.pid.map(|p| p.to_string()).unwrap_or_else(|| "?".into()));
+                        println!("  Iface:  {}", status.interface.as_deref().unwrap_or("?"));
+                        if !status.dns_server.is_empty() {
+                            println!("  DNS:    {} ({})", status.dns_server, status.dns_domain);
+                        }
+                        let mode = match status.tunnel {
+                            Some(models::TunnelMode::Full) => "full — all traffic goes through the VPN",
+                            _ => "split — only the routes below go through the VPN",
+                        };
+                        println!("  Tunnel: {}", mode);
+                        println!("  Routes: {}", if status.routes.is_empty() { "(none)".into() } else { status.routes.join(", ") });
                     } else {
                         println!("VPN: DISCONNECTED");
                     }
@@ -702,44 +1849,42 @@ fn try_alb_tunnel(
     local_port: u16,
     remote_port: Option<u16>,
 ) -> error::Result<Option<models::TunnelProcess>> {
-    let alb_arn = match aws::find_alb_for_hostname(host, None).unwrap_or(None) {
-        Some(arn) => arn,
-        None => return Ok(None),
+    let Some(aws::AlbHop { hop, target, port, target_az }) =
+        aws::resolve_alb_hop(host, remote_port, None, |_| {}, || false).unwrap_or(None)
+    else {
+        return Ok(None);
     };
-    let targets = aws::get_alb_healthy_targets(&alb_arn, remote_port, None).unwrap_or_default();
-    if targets.is_empty() { return Ok(None); }
-
-    // Try each healthy target — pick the first one for which we can find a valid hop.
-    for (target_ip, target_port) in &targets {
-        let target_sgs = match aws::get_target_sg_ids(target_ip, None) {
-            Ok(sgs) if !sgs.is_empty() => sgs,
-            _ => continue,
-        };
-        let allowed_sgs = match aws::get_allowed_source_sgs(&target_sgs, *target_port, None) {
-            Ok(sgs) if !sgs.is_empty() => sgs,
-            _ => continue,
-        };
-        let hop = match aws::find_ssm_hop_by_sgs(&allowed_sgs, None).unwrap_or(None) {
-            Some(inst) => inst,
-            None => continue,
-        };
-        println!("{}", gray(format!("  ALB target: {}:{}", target_ip, target_port)));
-        println!("{}", gray(format!("  Via: {}", hop.name)));
+    println!("{}", gray(format!("  ALB target: {}:{}", target, port)));
+    match (&hop.availability_zone, &target_az) {
+        (Some(az), Some(t)) if az == t => println!("{}", gray(format!("  Via: {} (same AZ, {})", hop.name, az))),
+        _ => println!("{}", gray(format!("  Via: {}", hop.name))),
+    }
+
+    let tp = tunnel::start_remote_tunnel_via_instance(&hop.id, &hop.name, &target, local_port, port, None)?;
+    Ok(Some(tp))
+}
+
+// ── SSM Shell ─────────────────────────────────────────────────────────────
+
+fn run_ssm_shell(pattern: &str) -> error::Result<()> {
+    let inst = aws::find_instance_by_name(pattern, None)?;
+    if inst.ssm_status != models::SsmStatus::Online {
+        eprintln!("{}", gray(format!("Warning: SSM agent on {} is {}", inst.id, inst.ssm_status.as_str())));
+    }
+    println!("{}", gray(format!("Starting session on {} ({})...", inst.name, inst.id)));
 
-        let tp = tunnel::start_remote_tunnel_via_instance(
-            &hop.id, &hop.name, target_ip, local_port, *target_port, None,
-        )?;
-        return Ok(Some(tp));
+    // Same launch as the TUI's `x`; the session's exit code becomes ours.
+    let status = aws::ssm_shell(&inst.id, None)?;
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
     }
-    Ok(None)
+    Ok(())
 }
 
 // ── SSM Proxy (SSH ProxyCommand) ──────────────────────────────────────────
 
-fn run_ssm_proxy(name: &str, port: &str, region: Option<&str>) -> error::Result<()> {
-    let region = region
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| aws::get_region(None));
+fn run_ssm_proxy(name: &str, port: &str) -> error::Result<()> {
+    let region = aws::get_region(None);
 
     // Resolve Name tag → instance ID
     let inst = aws::find_instance_by_name(name, None)?;
@@ -748,16 +1893,21 @@ fn run_ssm_proxy(name: &str, port: &str, region: Option<&str>) -> error::Result<
     ensure_ssh_key_pushed(&inst.id, &region);
 
     // exec aws ssm start-session (replaces current process)
-    let err = exec::execvp(
-        "aws",
-        &[
-            "aws", "ssm", "start-session",
-            "--target", &inst.id,
-            "--document-name", "AWS-StartSSHSession",
-            "--parameters", &format!("portNumber={}", port),
-            "--region", &region,
-        ],
-    );
+    let parameters = format!("portNumber={}", port);
+    let args = [
+        "aws", "ssm", "start-session",
+        "--target", &inst.id,
+        "--document-name", "AWS-StartSSHSession",
+        "--parameters", &parameters,
+        "--region", &region,
+    ];
+    let mut cmd = std::process::Command::new("aws");
+    cmd.args(&args[1..]);
+    if aws::dry_run_skip(&cmd) {
+        return Ok(());
+    }
+    aws::log_command(&cmd);
+    let err = exec::execvp("aws", &args);
     Err(error::AppError::AwsCli(format!("exec failed: {}", err)))
 }
 
@@ -800,16 +1950,20 @@ fn ensure_ssh_key_pushed(instance_id: &str, region: &str) {
         pub_key_content, pub_key_content
     );
 
-    let output = std::process::Command::new("aws")
-        .args([
-            "ssm", "send-command",
-            "--instance-ids", instance_id,
-            "--document-name", "AWS-RunShellScript",
-            "--parameters", &format!("commands=[\"{}\"]", script.replace('"', "\\\"")),
-            "--region", region,
-            "--output", "json",
-        ])
-        .output();
+    let mut send = std::process::Command::new("aws");
+    send.args([
+        "ssm", "send-command",
+        "--instance-ids", instance_id,
+        "--document-name", "AWS-RunShellScript",
+        "--parameters", &format!("commands=[\"{}\"]", script.replace('"', "\\\"")),
+        "--region", region,
+        "--output", "json",
+    ]);
+    if aws::dry_run_skip(&send) {
+        return;
+    }
+    aws::log_command(&send);
+    let output = send.output();
 
     let command_id = match output {
         Ok(o) if o.status.success() => {
@@ -824,15 +1978,16 @@ fn ensure_ssh_key_pushed(instance_id: &str, region: &str) {
     // Wait for completion (up to 10s)
     for _ in 0..5 {
         std::thread::sleep(std::time::Duration::from_secs(2));
-        let poll = std::process::Command::new("aws")
-            .args([
-                "ssm", "get-command-invocation",
-                "--command-id", &command_id,
-                "--instance-id", instance_id,
-                "--region", region,
-                "--output", "json",
-            ])
-            .output();
+        let mut poll_cmd = std::process::Command::new("aws");
+        poll_cmd.args([
+            "ssm", "get-command-invocation",
+            "--command-id", &command_id,
+            "--instance-id", instance_id,
+            "--region", region,
+            "--output", "json",
+        ]);
+        aws::log_command(&poll_cmd);
+        let poll = poll_cmd.output();
         if let Ok(o) = poll {
             if o.status.success() {
                 let json: serde_json::Value = serde_json::from_slice(&o.stdout).unwrap_or_default();
@@ -855,7 +2010,7 @@ fn ensure_ssh_key_pushed(instance_id: &str, region: &str) {
 const SSH_CONFIG_BEGIN: &str = "# BEGIN awsx2-managed";
 const SSH_CONFIG_END: &str = "# END awsx2-managed";
 
-fn run_ssh_config(dry_run: bool, user: &str) -> error::Result<()> {
+fn run_ssh_config(dry_run: bool, user: &str, write: Option<&str>, push_key: bool) -> error::Result<()> {
     let instances = aws::list_instances(None)?;
     let running_ssm: Vec<_> = instances
         .into_iter()
@@ -877,22 +2032,40 @@ fn run_ssh_config(dry_run: bool, user: &str) -> error::Result<()> {
         .unwrap_or_else(|_| "awsx2".to_string());
 
     let region = aws::get_region(None);
+    let profile = std::env::var("AWS_PROFILE").ok().filter(|s| !s.is_empty());
 
-    // Collect instance names we'll manage
-    let managed_names: std::collections::HashSet<String> = running_ssm
+    // Host aliases: slugified Name tag, disambiguated with the instance ID when
+    // several instances share a name (e.g. ASG members).
+    let mut seen = std::collections::HashSet::new();
+    let hosts: Vec<(String, &models::Instance)> = running_ssm
         .iter()
-        .map(|i| i.name.clone())
+        .map(|i| (ssh_host_alias(&i.name, &i.id, &mut seen), i))
         .collect();
 
+    // Collect host aliases we'll manage
+    let managed_names: std::collections::HashSet<String> =
+        hosts.iter().map(|(alias, _)| alias.clone()).collect();
+
     let mut block = String::new();
     block.push_str(&format!("{}\n", SSH_CONFIG_BEGIN));
-    for inst in &running_ssm {
+    for (alias, inst) in &hosts {
+        // Both forms pin the instance ID: aliases of instances sharing a Name
+        // must not all land on whichever one the name resolves to.
+        let proxy_cmd = if push_key {
+            format!("{} ssm-proxy --name {} --port %p --region {}", shell_quote(&awsx2_bin), inst.id, region)
+        } else {
+            format!(
+                "aws{} ssm start-session --target {} --document-name AWS-StartSSHSession --parameters portNumber=%p --region {}",
+                profile.as_deref().map(|p| format!(" --profile {}", p)).unwrap_or_default(),
+                inst.id,
+                region,
+            )
+        };
         block.push_str(&format!(
-            "\nHost {name}\n    User {user}\n    ProxyCommand {bin} ssm-proxy --name {name} --port %p --region {region}\n",
-            name = inst.name,
+            "\nHost {alias}\n    User {user}\n    ProxyCommand {proxy_cmd}\n",
+            alias = alias,
             user = user,
-            bin = awsx2_bin,
-            region = region,
+            proxy_cmd = proxy_cmd,
         ));
     }
     block.push_str(&format!("{}\n", SSH_CONFIG_END));
@@ -903,6 +2076,21 @@ fn run_ssh_config(dry_run: bool, user: &str) -> error::Result<()> {
         return Ok(());
     }
 
+    // Standalone file: owned entirely by awsx2, so just overwrite it.
+    // Include it from ~/.ssh/config with `Include config.d/*`.
+    if let Some(path) = write {
+        let path = expand_tilde(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, &block)?;
+        println!("Wrote {} with {} instances.", path.display(), hosts.len());
+        for (alias, _) in &hosts {
+            println!("  ssh {}", alias);
+        }
+        return Ok(());
+    }
+
     // Read existing config
     let home = std::env::var("HOME").unwrap_or_default();
     let ssh_dir = std::path::PathBuf::from(&home).join(".ssh");
@@ -934,12 +2122,56 @@ fn run_ssh_config(dry_run: bool, user: &str) -> error::Result<()> {
 
     std::fs::write(&config_path, &new_config)?;
     println!("Updated {} with {} instances.", config_path.display(), running_ssm.len());
-    for inst in &running_ssm {
-        println!("  ssh {}", inst.name);
+    for (alias, _) in &hosts {
+        println!("  ssh {}", alias);
     }
     Ok(())
 }
 
+/// Turn an EC2 Name tag into a safe SSH Host alias: lowercase, with runs of
+/// anything outside `[a-z0-9._]` collapsed to a single '-'.
+fn slugify_host(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Pick the SSH Host alias for an instance. Falls back to the instance ID when
+/// the Name tag slugifies to nothing, since a bare `Host ` line or an alias
+/// starting with '-' would break ssh.
+fn ssh_host_alias(name: &str, id: &str, seen: &mut std::collections::HashSet<String>) -> String {
+    let slug = slugify_host(name);
+    if slug.is_empty() {
+        id.to_string()
+    } else if seen.insert(slug.clone()) {
+        slug
+    } else {
+        format!("{}-{}", slug, id)
+    }
+}
+
+/// Single-quote a value for the shell that runs ProxyCommand, if needed.
+fn shell_quote(s: &str) -> String {
+    if s.chars().all(|c| c.is_ascii_alphanumeric() || "-_.:/@".contains(c)) {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+fn expand_tilde(path: &str) -> std::path::PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => std::path::PathBuf::from(std::env::var("HOME").unwrap_or_default()).join(rest),
+        None => std::path::PathBuf::from(path),
+    }
+}
+
 /// Remove SSH config Host blocks whose Host name matches any of the given names.
 /// Parses the config line-by-line: a Host block starts at `Host <name>` and ends
 /// at the next `Host ` line or EOF.
@@ -973,7 +2205,7 @@ fn remove_stale_host_blocks(config: &str, names: &std::collections::HashSet<Stri
     }
 
     // Trim trailing blank lines that may be left behind
-    while result.last().map_or(false, |l| l.trim().is_empty()) {
+    while result.last().is_some_and(|l| l.trim().is_empty()) {
         result.pop();
     }
     if result.is_empty() {
@@ -1056,18 +2288,23 @@ fn print_cheatcodes() {
 
 // ── TUI ───────────────────────────────────────────────────────────────────────
 
-fn run_tui() -> io::Result<()> {
+fn run_tui(theme: tui::theme::Theme) -> io::Result<()> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     let mut app = App::new();
+    app.theme = theme;
     app.refresh_instances();
     app.refresh_tunnels();
+    // Listing and start/stop still work without the plugin; only sessions fail.
+    if let Err(e) = aws::check_dependencies(&[aws::SSM_PLUGIN]) {
+        app.show_error("Missing dependency", e);
+    }
 
-    let tick_rate = Duration::from_millis(200);
+    let tick_rate = tui::app::TICK_RATE;
     let mut last_tick = Instant::now();
 
     loop {
@@ -1075,11 +2312,17 @@ fn run_tui() -> io::Result<()> {
 
         let timeout = tick_rate.saturating_sub(last_tick.elapsed());
         if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                handle_global_key(&mut app, key);
+            match event::read()? {
+                Event::Key(key) => handle_global_key(&mut app, key),
+                Event::Mouse(mouse) => handle_mouse(&mut app, mouse),
+                _ => {}
             }
         }
 
+        if let Some((id, name)) = app.pending_shell.take() {
+            run_tui_shell(&mut terminal, &mut app, &id, &name)?;
+        }
+
         if last_tick.elapsed() >= tick_rate {
             app.tick_spinner();
             app.poll_bg();
@@ -1089,19 +2332,83 @@ fn run_tui() -> io::Result<()> {
         if app.quit { break; }
     }
 
+    app.save_state();
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+    Ok(())
+}
+
+/// Hand the terminal to an interactive SSM session, then restore the TUI.
+fn run_tui_shell(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+    id: &str,
+    name: &str,
+) -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+    println!("{}", gray(format!("Starting session on {} ({}) — type `exit` to return to awsx2.", name, id)));
+
+    let result = aws::ssm_shell(id, None);
+
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen, EnableMouseCapture)?;
+    terminal.clear()?;
+    match result {
+        Ok(status) if status.success() => app.notify(format!("Session on {} closed", name)),
+        Ok(status) => app.notify(format!("Session on {} ended with {}", name, status)),
+        Err(e) => {
+            app.log_event(format!("Shell on {} failed: {}", name, e), true);
+            app.status_msg = Some(format!("Shell failed: {}", e));
+        }
+    }
     Ok(())
 }
 
+/// Wheel scrolls like Up/Down (rows or popup text); left click picks a tab
+/// or a row of the active tab. Clicks are ignored while a popup is open.
+fn handle_mouse(app: &mut App, mouse: MouseEvent) {
+    let arrow = |code| KeyEvent::new(code, KeyModifiers::NONE);
+    match mouse.kind {
+        MouseEventKind::ScrollUp => handle_global_key(app, arrow(KeyCode::Up)),
+        MouseEventKind::ScrollDown => handle_global_key(app, arrow(KeyCode::Down)),
+        MouseEventKind::Down(MouseButton::Left) if matches!(app.popup, Popup::None) => {
+            let hit = app.hit_areas.get();
+            if mouse.row == hit.tabs.y {
+                if let Some(tab) = tui::ui::tab_at(hit.tabs.x, mouse.column) {
+                    app.tab = tab;
+                }
+            } else if let Some(idx) = hit.row_at(mouse.column, mouse.row) {
+                match app.tab {
+                    Tab::Instances => pages::instances::select_row(app, idx),
+                    Tab::Tunnels   => pages::tunnels::select_row(app, idx),
+                    Tab::Tools     => pages::tools::select_row(app, idx),
+                    Tab::Vpn       => pages::vpn::select_row(app, idx),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
 fn handle_global_key(app: &mut App, key: KeyEvent) {
     // Handle open popup first
     match app.popup.clone() {
         Popup::None => {}
 
         Popup::Help => {
-            if matches!(key.code, KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?')) {
-                app.popup = Popup::None;
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?') => {
+                    app.popup = Popup::None;
+                    app.result_scroll = 0;
+                }
+                KeyCode::Up | KeyCode::Char('k') => app.scroll_result(-1),
+                KeyCode::Down | KeyCode::Char('j') => app.scroll_result(1),
+                KeyCode::PageUp => app.scroll_result(-10),
+                KeyCode::PageDown => app.scroll_result(10),
+                KeyCode::Char('g') => { app.result_scroll = 0; }
+                KeyCode::Char('G') => { app.result_scroll = app.hit_areas.get().scroll_max; }
+                _ => {}
             }
             return;
         }
@@ -1170,22 +2477,89 @@ fn handle_global_key(app: &mut App, key: KeyEvent) {
             return;
         }
 
-        Popup::Result { .. } | Popup::Loading { .. } => {
-            if matches!(key.code, KeyCode::Esc | KeyCode::Enter) {
-                app.popup = Popup::None;
+        Popup::Result { title, body, .. } => {
+            match key.code {
+                KeyCode::Esc | KeyCode::Enter => {
+                    app.popup = Popup::None;
+                    app.result_scroll = 0;
+                }
+                KeyCode::Up | KeyCode::Char('k') => app.scroll_result(-1),
+                KeyCode::Down | KeyCode::Char('j') => app.scroll_result(1),
+                KeyCode::PageUp => app.scroll_result(-10),
+                KeyCode::PageDown => app.scroll_result(10),
+                KeyCode::Char('g') => { app.result_scroll = 0; }
+                KeyCode::Char('G') => { app.result_scroll = app.hit_areas.get().scroll_max; }
+                KeyCode::Char('c') => {
+                    app.copy_to_clipboard(&format!("\"{}\"", title.trim()), &body);
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        Popup::InstanceDetail(inst) => {
+            match key.code {
+                KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+                    app.popup = Popup::None;
+                    app.result_scroll = 0;
+                }
+                KeyCode::Up | KeyCode::Char('k') => app.scroll_result(-1),
+                KeyCode::Down | KeyCode::Char('j') => app.scroll_result(1),
+                KeyCode::Char('c') => {
+                    let text = pages::instances::detail_text(&inst, &app.region, app.volumes_for(&inst.id));
+                    app.copy_to_clipboard(&format!("details of {}", inst.id), &text);
+                }
+                KeyCode::Char('t') => {
+                    app.popup = Popup::None;
+                    app.result_scroll = 0;
+                    app.show_instance_tunnels(&inst.id, &inst.name);
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        Popup::Loading { .. } => {
+            match key.code {
+                KeyCode::Esc => app.cancel_loading(),
+                // Hide the spinner but keep the task; its result still shows.
+                KeyCode::Enter => app.popup = Popup::None,
+                _ => {}
             }
             return;
         }
     }
 
+    // The filter bar takes every key, so typing `q` or `P` doesn't trigger globals.
+    if !key.modifiers.contains(KeyModifiers::CONTROL) {
+        match app.tab {
+            Tab::Instances if app.instance_filter_active => {
+                pages::instances::handle_key(app, key);
+                return;
+            }
+            Tab::Tunnels if app.tunnel_filter_active => {
+                pages::tunnels::handle_key(app, key);
+                return;
+            }
+            _ => {}
+        }
+    }
+
     // Global keys (no popup open)
     match (key.code, key.modifiers) {
         (KeyCode::Char('c'), KeyModifiers::CONTROL) | (KeyCode::Char('q'), _) => {
             app.quit = true;
         }
         (KeyCode::Char('?'), _) => {
+            app.result_scroll = 0;
             app.popup = Popup::Help;
         }
+        (KeyCode::Char('P'), _) => {
+            app.open_profile_switcher();
+        }
+        (KeyCode::Char('L'), _) => {
+            app.show_event_log = !app.show_event_log;
+        }
         (KeyCode::Tab, KeyModifiers::NONE) => {
             app.tab = app.tab.next();
         }
@@ -1203,7 +2577,7 @@ fn handle_global_key(app: &mut App, key: KeyEvent) {
 
 fn dispatch_input(app: &mut App, tag: InputTag, value: String) {
     match tag {
-        InputTag::LoginProfile | InputTag::ResolveUrl | InputTag::TestPort
+        InputTag::LoginProfile | InputTag::ResolveUrl | InputTag::ExplainUrlPath | InputTag::TestPort
         | InputTag::SwitchProfile | InputTag::SwitchRegion => {
             pages::tools::handle_input(app, tag, value);
         }
@@ -1219,7 +2593,17 @@ fn dispatch_input(app: &mut App, tag: InputTag, value: String) {
         | InputTag::NewTunnelBastionRemotePort => {
             pages::tunnels::handle_input(app, tag, value);
         }
+        InputTag::LaunchTemplate
+        | InputTag::LaunchName
+        | InputTag::TerminateInstanceName(..)
+        | InputTag::SwitchInstanceType(..)
+        | InputTag::TagInstance(..)
+        | InputTag::InstanceAwsFilters => {
+            pages::instances::handle_input(app, tag, value);
+        }
         InputTag::VpnMfaCode
+        | InputTag::VpnSelectConfig
+        | InputTag::VpnNewConfigName
         | InputTag::VpnSetupUsername
         | InputTag::VpnSetupPassword
         | InputTag::VpnSetupOvpnPath => {
@@ -1230,11 +2614,43 @@ fn dispatch_input(app: &mut App, tag: InputTag, value: String) {
 
 fn dispatch_confirm(app: &mut App, tag: ConfirmTag, confirmed: bool) {
     match tag {
-        ConfirmTag::StopTunnel(_) | ConfirmTag::StopAllTunnels => {
+        ConfirmTag::StopTunnel(_) | ConfirmTag::StopAllTunnels | ConfirmTag::ReusePort(_) => {
             pages::tunnels::handle_confirm(app, tag, confirmed);
         }
-        ConfirmTag::StopInstance | ConfirmTag::ForceStopInstance => {
+        ConfirmTag::StopInstance(..)
+        | ConfirmTag::ForceStopInstance(..)
+        | ConfirmTag::RebootInstance(..)
+        | ConfirmTag::TerminateInstance(..) => {
             pages::instances::handle_confirm(app, tag, confirmed);
         }
+        ConfirmTag::SsoLogin => {
+            if confirmed {
+                let profile = app.profile.clone();
+                app.sso_login(Some(profile));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify_host_collapses_separators() {
+        assert_eq!(slugify_host("My Server (GPU)"), "my-server-gpu");
+        assert_eq!(slugify_host("  web__1.prod  "), "web__1.prod");
+        assert_eq!(slugify_host("--a//b--"), "a-b");
+    }
+
+    #[test]
+    fn ssh_host_alias_falls_back_to_instance_id() {
+        let mut seen = std::collections::HashSet::new();
+        assert_eq!(ssh_host_alias("日本", "i-0abc", &mut seen), "i-0abc");
+        assert_eq!(ssh_host_alias("日本", "i-0def", &mut seen), "i-0def");
+        assert_eq!(ssh_host_alias("", "i-1", &mut seen), "i-1");
+        assert_eq!(ssh_host_alias("!!!", "i-2", &mut seen), "i-2");
+        assert_eq!(ssh_host_alias("Web", "i-3", &mut seen), "web");
+        assert_eq!(ssh_host_alias("web", "i-4", &mut seen), "web-i-4");
     }
 }
@@ -0,0 +1,87 @@
+//! Prometheus text exposition for `awsx2 tunnel-list --format prometheus`,
+//! e.g. for a node_exporter textfile collector fed by cron.
+
+use std::fmt::Write as _;
+
+use crate::models::TunnelProcess;
+
+/// `awsx2_tunnel_up` (1 when the session is alive and its port answers) and
+/// `awsx2_tunnel_latency_ms` (only for tunnels with a measured latency).
+pub fn prometheus(tunnels: &[TunnelProcess]) -> String {
+    let mut out = String::new();
+    out.push_str("# HELP awsx2_tunnel_up Whether the SSM tunnel is running and its local port is open.\n");
+    out.push_str("# TYPE awsx2_tunnel_up gauge\n");
+    for t in tunnels {
+        let up = t.pid != 0 && t.port_open;
+        let _ = writeln!(out, "awsx2_tunnel_up{{{}}} {}", labels(t), u8::from(up));
+    }
+    out.push_str("# HELP awsx2_tunnel_latency_ms Round-trip latency through the tunnel in milliseconds.\n");
+    out.push_str("# TYPE awsx2_tunnel_latency_ms gauge\n");
+    for t in tunnels {
+        if let Some(ms) = t.latency_ms {
+            let _ = writeln!(out, "awsx2_tunnel_latency_ms{{{}}} {}", labels(t), ms);
+        }
+    }
+    out
+}
+
+fn labels(t: &TunnelProcess) -> String {
+    format!(
+        "local_port=\"{}\",remote=\"{}:{}\",instance=\"{}\",instance_id=\"{}\"",
+        t.local_port,
+        escape(t.remote_host.as_deref().unwrap_or(&t.instance_id)),
+        t.remote_port,
+        escape(&t.instance_name),
+        escape(&t.instance_id),
+    )
+}
+
+/// Label values escape `\`, `"` and newlines.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tunnel(pid: u32, port_open: bool, latency_ms: Option<u64>) -> TunnelProcess {
+        TunnelProcess {
+            pid,
+            local_port: 8080,
+            remote_port: 80,
+            remote_host: None,
+            instance_id: "i-1".into(),
+            instance_name: "web \"a\"".into(),
+            port_open,
+            latency_ms,
+            document: None,
+            parameters: None,
+        }
+    }
+
+    #[test]
+    fn escape_label_values() {
+        assert_eq!(escape(r#"a\b"c"#), r#"a\\b\"c"#);
+        assert_eq!(escape("two\nlines"), "two\\nlines");
+    }
+
+    #[test]
+    fn prometheus_up_and_latency() {
+        let out = prometheus(&[tunnel(42, true, Some(12)), tunnel(0, false, None)]);
+        let labels = r#"local_port="8080",remote="i-1:80",instance="web \"a\"",instance_id="i-1""#;
+        assert!(out.contains(&format!("awsx2_tunnel_up{{{}}} 1\n", labels)));
+        assert!(out.contains(&format!("awsx2_tunnel_up{{{}}} 0\n", labels)));
+        assert!(out.contains(&format!("awsx2_tunnel_latency_ms{{{}}} 12\n", labels)));
+        assert_eq!(out.matches("awsx2_tunnel_latency_ms{").count(), 1);
+        assert_eq!(out.matches("# TYPE").count(), 2);
+    }
+
+    #[test]
+    fn prometheus_remote_host_label() {
+        let mut t = tunnel(42, true, None);
+        t.remote_host = Some("db.internal".into());
+        t.remote_port = 5432;
+        assert!(prometheus(&[t]).contains(r#"remote="db.internal:5432""#));
+    }
+}
@@ -2,7 +2,9 @@
 
 #![allow(dead_code)]
 
-use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize, Serializer};
 
 // ── Domain models ─────────────────────────────────────────────────────────────
 
@@ -36,6 +38,12 @@ impl InstanceState {
     }
 }
 
+impl Serialize for InstanceState {
+    fn serialize<S: Serializer>(&self, s: S) -> std::result::Result<S::Ok, S::Error> {
+        s.serialize_str(self.as_str())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum SsmStatus { Online, Offline, Unknown }
 
@@ -49,10 +57,21 @@ impl SsmStatus {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Same strings as `as_str()`, except `Unknown` is spelled out rather than "-".
+impl Serialize for SsmStatus {
+    fn serialize<S: Serializer>(&self, s: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Self::Unknown => s.serialize_str("Unknown"),
+            other         => s.serialize_str(other.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TunnelStatus { Active, Down }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TunnelInfo {
     pub local_port: u16,
     pub remote_port: u16,
@@ -60,7 +79,8 @@ pub struct TunnelInfo {
     pub status: TunnelStatus,
 }
 
-#[derive(Debug, Clone)]
+/// Serialized field names are part of `awsx2 list --output json` — keep them stable.
+#[derive(Debug, Clone, Serialize)]
 pub struct Instance {
     pub id: String,
     pub name: String,
@@ -68,21 +88,80 @@ pub struct Instance {
     pub state: InstanceState,
     pub private_ip: Option<String>,
     pub public_ip: Option<String>,
+    /// IPv6 addresses across all network interfaces.
+    pub ipv6_addresses: Vec<String>,
     pub ssm_status: SsmStatus,
+    /// ISO 8601 time of the SSM agent's last ping, if registered.
+    pub ssm_last_ping: Option<String>,
+    pub ssm_agent_version: Option<String>,
     pub tunnel: Option<TunnelInfo>,
     pub security_groups: Vec<String>,
     pub security_group_ids: Vec<String>,
+    pub availability_zone: Option<String>,
+    /// ISO 8601 timestamp as returned by `describe-instances`.
+    pub launch_time: Option<String>,
+    /// All tags, including `Name`.
+    pub tags: BTreeMap<String, String>,
 }
 
-#[derive(Debug, Clone)]
+impl Instance {
+    /// Whether `ip` (v4 or v6) is one of this instance's addresses.
+    pub fn has_ip(&self, ip: &str) -> bool {
+        self.private_ip.iter()
+            .chain(self.public_ip.iter())
+            .chain(self.ipv6_addresses.iter())
+            .any(|own| same_ip(own, ip))
+    }
+}
+
+/// Compare two addresses as parsed IPs, so differently written IPv6 forms
+/// (`2001:db8::1` vs `2001:0db8:0:0:0:0:0:1`) still match.
+pub fn same_ip(a: &str, b: &str) -> bool {
+    match (a.parse::<std::net::IpAddr>(), b.parse::<std::net::IpAddr>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct BastionInfo {
     pub id: String,
     pub name: String,
     pub ssm_online: bool,
+    pub availability_zone: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+/// Result of `aws::resolve_dns_detail`; serialized by `awsx2 resolve --json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolveReport {
+    pub host: String,
+    /// Addresses from `aws::resolve_host`: the local resolver, or external DNS
+    /// when that only gives loopback. Empty for internal-only names.
+    pub local_ips: Vec<String>,
+    /// Instances with a private, public or IPv6 address matching a local one.
+    pub ec2_matches: Vec<Instance>,
+    pub alb_arn: Option<String>,
+    /// ECS/Fargate tasks owning a local or bastion-resolved IP.
+    pub ecs_tasks: Vec<EcsTask>,
+    /// Only attempted when nothing matched locally and a bastion is online.
+    pub via_bastion: Option<BastionResolution>,
+    /// SSM-online bastions.
+    pub bastions: Vec<BastionInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BastionResolution {
+    pub bastion_id: String,
+    pub bastion_name: String,
+    pub ips: Vec<String>,
+    pub ec2_matches: Vec<Instance>,
+    pub error: Option<String>,
+}
+
+/// Serialized field names are part of `awsx2 tunnel-list --json` — keep them stable.
+#[derive(Debug, Clone, Serialize)]
 pub struct TunnelProcess {
+    /// 0 for a registered tunnel whose process is gone.
     pub pid: u32,
     pub local_port: u16,
     pub remote_port: u16,
@@ -93,6 +172,61 @@ pub struct TunnelProcess {
     pub port_open: bool,
     /// Round-trip latency in ms for the first successful TCP connect (None if unknown).
     pub latency_ms: Option<u64>,
+    /// SSM document the session runs, e.g. `AWS-StartPortForwardingSession`.
+    /// Read from the plugin's command line, or recorded when the tunnel was
+    /// started; None for a `ps`-detected tunnel awsx2 doesn't know.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document: Option<String>,
+    /// The session's `Parameters` object, as compact JSON.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<String>,
+}
+
+/// Outcome of `tunnel::benchmark_port`.
+#[derive(Debug, Clone)]
+pub struct BenchResult {
+    pub requests: u32,
+    pub failures: u32,
+    /// Time to first response byte, over successful requests.
+    pub min_ms: u64,
+    pub max_ms: u64,
+    pub avg_ms: u64,
+    /// Response bytes read through the tunnel.
+    pub bytes: u64,
+    pub elapsed: std::time::Duration,
+}
+
+impl BenchResult {
+    pub fn requests_per_sec(&self) -> f64 {
+        self.requests as f64 / self.elapsed.as_secs_f64().max(0.001)
+    }
+
+    pub fn mb_per_sec(&self) -> f64 {
+        self.bytes as f64 / 1_000_000.0 / self.elapsed.as_secs_f64().max(0.001)
+    }
+
+    /// Multi-line report shared by the CLI and the TUI popup.
+    pub fn summary(&self) -> String {
+        format!(
+            "Requests:   {} ok, {} failed in {:.1}s ({:.1} req/s)\n\
+             Latency:    min {}ms / avg {}ms / max {}ms\n\
+             Throughput: {:.2} MB/s ({} bytes)",
+            self.requests, self.failures, self.elapsed.as_secs_f64(), self.requests_per_sec(),
+            self.min_ms, self.avg_ms, self.max_ms,
+            self.mb_per_sec(), self.bytes,
+        )
+    }
+}
+
+/// Result of `aws::run_ssm_command_output`.
+#[derive(Debug, Clone)]
+pub struct SsmCommandOutput {
+    /// Final SSM status: Success, Failed, Cancelled or TimedOut.
+    pub status: String,
+    /// Exit code of the script; None if it never ran.
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
 }
 
 #[derive(Debug, Clone)]
@@ -103,21 +237,134 @@ pub enum TunnelTarget {
         bastion_name: String,
         target_host: String,
         target_port: u16,
+        /// Set when the target IP belongs to a running ECS/Fargate task.
+        ecs_task: Option<EcsTask>,
     },
 }
 
+/// An EBS volume attached to an instance (`aws::get_instance_volumes`).
+#[derive(Debug, Clone, Serialize)]
+pub struct VolumeInfo {
+    /// Attachment device name, e.g. `/dev/xvda`.
+    pub device: String,
+    pub volume_id: String,
+    pub size_gib: u64,
+    /// `gp3`, `io2`, ...
+    pub volume_type: String,
+    /// Provisioned or baseline IOPS; None for types without (e.g. `standard`).
+    pub iops: Option<u64>,
+    /// Volume state, e.g. `in-use`.
+    pub state: String,
+}
+
+/// A running ECS task, matched by the private IP of its ENI.
+#[derive(Debug, Clone, Serialize)]
+pub struct EcsTask {
+    pub cluster: String,
+    pub task_id: String,
+    pub task_arn: String,
+    /// From the task group `service:<name>`; None for standalone tasks.
+    pub service: Option<String>,
+    pub launch_type: String,
+    pub private_ip: String,
+}
+
+impl EcsTask {
+    /// One-line description used by the resolve report and CLI.
+    pub fn describe(&self) -> String {
+        format!(
+            "{} task {} ({}, cluster {}, {})",
+            self.launch_type,
+            self.task_id,
+            self.service.as_deref().map_or("standalone".to_string(), |s| format!("service {}", s)),
+            self.cluster,
+            self.private_ip,
+        )
+    }
+}
+
+// ── Pricing ───────────────────────────────────────────────────────────────────
+
+/// Region used as the fallback when a region has no price table of its own.
+pub const PRICING_REGION: &str = "us-east-1";
+
+/// On-demand Linux USD/hour in us-east-1. Rough figures for the detail view,
+/// not a billing source.
+const ON_DEMAND_US_EAST_1: &[(&str, f64)] = &[
+    ("t2.nano", 0.0058), ("t2.micro", 0.0116), ("t2.small", 0.023), ("t2.medium", 0.0464),
+    ("t2.large", 0.0928), ("t2.xlarge", 0.1856),
+    ("t3.nano", 0.0052), ("t3.micro", 0.0104), ("t3.small", 0.0208), ("t3.medium", 0.0416),
+    ("t3.large", 0.0832), ("t3.xlarge", 0.1664), ("t3.2xlarge", 0.3328),
+    ("t3a.micro", 0.0094), ("t3a.small", 0.0188), ("t3a.medium", 0.0376),
+    ("t3a.large", 0.0752), ("t3a.xlarge", 0.1504),
+    ("t4g.micro", 0.0084), ("t4g.small", 0.0168), ("t4g.medium", 0.0336), ("t4g.large", 0.0672),
+    ("m5.large", 0.096), ("m5.xlarge", 0.192), ("m5.2xlarge", 0.384), ("m5.4xlarge", 0.768),
+    ("m6i.large", 0.096), ("m6i.xlarge", 0.192), ("m6i.2xlarge", 0.384), ("m6i.4xlarge", 0.768),
+    ("m7i.large", 0.1008), ("m7i.xlarge", 0.2016), ("m7i.2xlarge", 0.4032),
+    ("c5.large", 0.085), ("c5.xlarge", 0.17), ("c5.2xlarge", 0.34), ("c5.4xlarge", 0.68),
+    ("c6i.large", 0.085), ("c6i.xlarge", 0.17), ("c6i.2xlarge", 0.34), ("c6i.4xlarge", 0.68),
+    ("r5.large", 0.126), ("r5.xlarge", 0.252), ("r5.2xlarge", 0.504),
+    ("r6i.large", 0.126), ("r6i.xlarge", 0.252), ("r6i.2xlarge", 0.504),
+    ("g4dn.xlarge", 0.526), ("g4dn.2xlarge", 0.752), ("g4dn.4xlarge", 1.204),
+    ("g4dn.8xlarge", 2.176), ("g4dn.12xlarge", 3.912),
+    ("g5.xlarge", 1.006), ("g5.2xlarge", 1.212), ("g5.4xlarge", 1.624),
+    ("p3.2xlarge", 3.06),
+];
+
+/// Per-region price tables. Add a region here to stop it being approximate.
+const ON_DEMAND_PRICES: &[(&str, &[(&str, f64)])] = &[(PRICING_REGION, ON_DEMAND_US_EAST_1)];
+
+/// Whether `instance_hourly_cost` has real figures for `region` rather than
+/// falling back to us-east-1.
+pub fn has_regional_pricing(region: &str) -> bool {
+    ON_DEMAND_PRICES.iter().any(|(r, _)| *r == region)
+}
+
+/// Rough on-demand hourly cost in USD, or None for types not in the table.
+/// Regions without a table get the us-east-1 figure (see `has_regional_pricing`).
+pub fn instance_hourly_cost(instance_type: &str, region: &str) -> Option<f64> {
+    let table = ON_DEMAND_PRICES
+        .iter()
+        .find(|(r, _)| *r == region)
+        .map_or(ON_DEMAND_US_EAST_1, |(_, t)| *t);
+    table
+        .iter()
+        .find(|(t, _)| *t == instance_type)
+        .map(|(_, price)| *price)
+}
+
 // ── VPN configuration ────────────────────────────────────────────────────────
 
 /// Persisted VPN configuration (~/.config/awsx2/vpn.json)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VpnConfig {
     pub sso_username: String,
+    /// Empty when `password_in_keychain` is set; `vpn::connect` reads it from
+    /// the OS keychain.
+    #[serde(default)]
     pub sso_password: String,
+    /// The password lives in the OS keychain rather than in this file.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub password_in_keychain: bool,
     pub ovpn_path: String,
     #[serde(default = "default_dns_server")]
     pub dns_server: String,
     #[serde(default = "default_dns_domain")]
     pub dns_domain: String,
+    /// Base32 TOTP secret; when set, MFA codes are generated instead of prompted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub totp_secret: Option<String>,
+    /// CSS selectors for the SSO login fields. When set, each list replaces the
+    /// built-in defaults (see `vpn::DEFAULT_*_SELECTORS`) for that step.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username_selectors: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_selectors: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mfa_selectors: Option<Vec<String>>,
+    /// Preferred local port for the SAML callback listener (default 35001).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saml_port: Option<u16>,
 }
 
 fn default_dns_server() -> String { String::new() }
@@ -128,9 +375,74 @@ impl Default for VpnConfig {
         Self {
             sso_username: String::new(),
             sso_password: String::new(),
+            password_in_keychain: false,
             ovpn_path: String::new(),
             dns_server: default_dns_server(),
             dns_domain: default_dns_domain(),
+            totp_secret: None,
+            username_selectors: None,
+            password_selectors: None,
+            mfa_selectors: None,
+            saml_port: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TunnelMode {
+    /// Only the VPN's own routes go through the TUN interface.
+    Split,
+    /// The default route (or OpenVPN's `0/1` + `128/1` pair) points at it.
+    Full,
+}
+
+/// Result of `vpn::status`; serialized by `awsx2 vpn status --json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct VpnStatus {
+    pub connected: bool,
+    pub interface: Option<String>,
+    pub ip: Option<String>,
+    pub pid: Option<u32>,
+    /// From the VPN config; these are what `connect` applied.
+    pub dns_server: String,
+    pub dns_domain: String,
+    pub tunnel: Option<TunnelMode>,
+    /// Destinations routed through `interface`, as the routing table prints them.
+    pub routes: Vec<String>,
+}
+
+/// Step of the `vpn::connect` flow, reported to the progress callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VpnPhase {
+    PreparingConfig,
+    FetchingSaml,
+    Authenticating,
+    Connecting,
+    ConfiguringDns,
+    Done,
+}
+
+impl VpnPhase {
+    /// Number of working phases (`Done` is not counted).
+    pub const COUNT: usize = 5;
+
+    /// 1-based step number; `Done` reports `COUNT`.
+    pub fn step(self) -> usize {
+        match self {
+            VpnPhase::PreparingConfig => 1,
+            VpnPhase::FetchingSaml => 2,
+            VpnPhase::Authenticating => 3,
+            VpnPhase::Connecting => 4,
+            VpnPhase::ConfiguringDns | VpnPhase::Done => 5,
+        }
+    }
+
+    /// Fraction of the flow completed when this phase starts.
+    pub fn ratio(self) -> f64 {
+        match self {
+            VpnPhase::Done => 1.0,
+            p => (p.step() - 1) as f64 / Self::COUNT as f64,
         }
     }
 }
@@ -153,6 +465,30 @@ pub struct RawInstance {
     pub tags: Option<Vec<Tag>>,
     #[serde(rename = "SecurityGroups")]
     pub security_groups: Option<Vec<SecurityGroup>>,
+    #[serde(rename = "Placement")]
+    pub placement: Option<RawPlacement>,
+    #[serde(rename = "LaunchTime")]
+    pub launch_time: Option<String>,
+    #[serde(rename = "NetworkInterfaces")]
+    pub network_interfaces: Option<Vec<RawNetworkInterface>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RawNetworkInterface {
+    #[serde(rename = "Ipv6Addresses")]
+    pub ipv6_addresses: Option<Vec<RawIpv6Address>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RawIpv6Address {
+    #[serde(rename = "Ipv6Address")]
+    pub address: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RawPlacement {
+    #[serde(rename = "AvailabilityZone")]
+    pub availability_zone: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -183,6 +519,10 @@ pub struct SsmInstanceInfo {
     pub instance_id: String,
     #[serde(rename = "PingStatus")]
     pub ping_status: String,
+    #[serde(rename = "LastPingDateTime")]
+    pub last_ping: Option<String>,
+    #[serde(rename = "AgentVersion")]
+    pub agent_version: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -6,6 +6,10 @@ use std::process::{Command, Stdio};
 
 use crate::error::{AppError, Result};
 
+const HOSTS_PATH: &str = "/etc/hosts";
+const HOSTS_BEGIN: &str = "# awsx2 BEGIN";
+const HOSTS_END: &str = "# awsx2 END";
+/// Per-line marker used before the BEGIN/END block; still cleaned up on teardown.
 const HOSTS_TAG: &str = "# awsx2-proxy";
 
 fn is_macos() -> bool {
@@ -92,6 +96,15 @@ impl NginxDir {
         }
     }
 
+    /// Contents of an active config file.
+    fn read_config(&self, filename: &str) -> Option<String> {
+        let dir = match self {
+            NginxDir::Direct(d) => d,
+            NginxDir::SitesAvailable { enabled, .. } => enabled,
+        };
+        std::fs::read_to_string(dir.join(filename)).ok()
+    }
+
     /// Iterate over active config filenames matching a predicate.
     fn active_configs(&self) -> Vec<String> {
         let dir = match self {
@@ -114,11 +127,55 @@ fn config_filename(hostname: &str) -> String {
     format!("awsx2-{}.conf", hostname)
 }
 
+// ── Self-signed certificates (--tls) ─────────────────────────────────────────
+
+/// Kept outside the nginx include dirs, which load every file they contain.
+fn certs_dir() -> PathBuf {
+    crate::config::config_dir().join("certs")
+}
+
+/// Generate (or reuse) a self-signed cert for `hostname`.
+/// Returns the (certificate, private key) PEM paths.
+fn ensure_self_signed_cert(hostname: &str) -> Result<(PathBuf, PathBuf)> {
+    let dir = certs_dir();
+    let cert_path = dir.join(format!("awsx2-{}.crt", hostname));
+    let key_path = dir.join(format!("awsx2-{}.key", hostname));
+    if cert_path.exists() && key_path.exists() {
+        println!("  TLS cert: reusing {}", cert_path.display());
+        return Ok((cert_path, key_path));
+    }
+
+    let certified = rcgen::generate_simple_self_signed(vec![hostname.to_string()])
+        .map_err(|e| AppError::Tunnel(format!("Failed to generate certificate: {}", e)))?;
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(&cert_path, certified.cert.pem())?;
+    std::fs::write(&key_path, certified.key_pair.serialize_pem())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&key_path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    println!("  TLS cert: {} (self-signed — trust it to avoid browser warnings)", cert_path.display());
+    Ok((cert_path, key_path))
+}
+
+/// Delete every generated cert/key pair.
+fn remove_certs() {
+    let Ok(entries) = std::fs::read_dir(certs_dir()) else { return };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with("awsx2-") && (name.ends_with(".crt") || name.ends_with(".key")) {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
 // ── Public API ───────────────────────────────────────────────────────────────
 
 /// Set up a local nginx reverse proxy so the hostname resolves to localhost and
-/// nginx forwards traffic to the tunnel's local port.
-pub fn setup_proxy(hostname: &str, local_port: u16) -> Result<()> {
+/// nginx forwards traffic to the tunnel's local port. With `tls`, nginx also
+/// serves 443 using a generated self-signed certificate.
+pub fn setup_proxy(hostname: &str, local_port: u16, tls: bool) -> Result<()> {
     let dir = nginx_servers_dir().ok_or_else(|| {
         let hint = if is_macos() {
             "Install nginx: brew install nginx"
@@ -129,11 +186,21 @@ pub fn setup_proxy(hostname: &str, local_port: u16) -> Result<()> {
     })?;
 
     // 1. Write nginx config
+    let tls_directives = if tls {
+        let (cert, key) = ensure_self_signed_cert(hostname)?;
+        format!(
+            "    listen 443 ssl;\n    ssl_certificate {};\n    ssl_certificate_key {};\n",
+            cert.display(),
+            key.display()
+        )
+    } else {
+        String::new()
+    };
     let config = format!(
         r#"# Auto-generated by awsx2 — do not edit
 server {{
     listen 80;
-    server_name {hostname};
+{tls_directives}    server_name {hostname};
 
     location / {{
         proxy_pass http://127.0.0.1:{local_port};
@@ -176,87 +243,202 @@ pub fn teardown_all_proxies() {
         }
     }
 
-    // Remove all awsx2-proxy tagged lines from /etc/hosts.
-    // Read, filter, write back — portable across macOS and Linux.
+    // Independent of nginx: the hosts block and certs must go even if nginx is gone.
     remove_hosts_entries();
+    remove_certs();
 
     let _ = reload_nginx();
     flush_dns_cache();
 }
 
-/// Check if any awsx2 proxy configs exist.
+/// Remove the proxies (config, hosts entry, cert) that forward to `local_port`.
+/// Returns the hostnames that were removed.
+pub fn teardown_proxies_for_port(local_port: u16) -> Vec<String> {
+    let Some(dir) = nginx_servers_dir() else { return vec![] };
+    let target = format!("proxy_pass http://127.0.0.1:{};", local_port);
+    let mut removed = vec![];
+    for name in dir.active_configs() {
+        let Some(host) = name.strip_prefix("awsx2-").and_then(|n| n.strip_suffix(".conf")) else {
+            continue;
+        };
+        if dir.read_config(&name).is_some_and(|c| c.contains(&target)) {
+            dir.remove_config(&name);
+            let certs = certs_dir();
+            let _ = std::fs::remove_file(certs.join(format!("awsx2-{}.crt", host)));
+            let _ = std::fs::remove_file(certs.join(format!("awsx2-{}.key", host)));
+            removed.push(host.to_string());
+        }
+    }
+    if !removed.is_empty() {
+        remove_hosts_entries_for(&removed);
+        let _ = reload_nginx();
+        flush_dns_cache();
+    }
+    removed
+}
+
+/// The hostname of a proxy forwarding to `local_port`, and whether it also
+/// serves HTTPS.
+pub fn proxy_for_port(local_port: u16) -> Option<(String, bool)> {
+    let dir = nginx_servers_dir()?;
+    let target = format!("proxy_pass http://127.0.0.1:{};", local_port);
+    dir.active_configs().into_iter().find_map(|name| {
+        let host = name.strip_prefix("awsx2-")?.strip_suffix(".conf")?.to_string();
+        let config = dir.read_config(&name).filter(|c| c.contains(&target))?;
+        Some((host, config.contains("listen 443 ssl")))
+    })
+}
+
+/// Check if any awsx2 proxy configs or /etc/hosts entries exist.
 pub fn has_active_proxies() -> bool {
     if let Some(dir) = nginx_servers_dir() {
-        return dir
+        if dir
             .active_configs()
             .iter()
-            .any(|n| n.starts_with("awsx2-") && n.ends_with(".conf"));
+            .any(|n| n.starts_with("awsx2-") && n.ends_with(".conf"))
+        {
+            return true;
+        }
     }
-    false
+    !hosts_entries().is_empty()
 }
 
 // ── /etc/hosts management ────────────────────────────────────────────────────
-
-fn add_hosts_entry(hostname: &str) -> Result<()> {
-    // Check if already present
-    if let Ok(hosts) = std::fs::read_to_string("/etc/hosts") {
-        for line in hosts.lines() {
-            if line.contains(hostname) && line.contains(HOSTS_TAG) {
-                println!("  /etc/hosts: already has {}", hostname);
-                return Ok(());
+//
+// Entries live in one marked block so teardown removes exactly what we added:
+//
+//   # awsx2 BEGIN
+//   127.0.0.1 app.internal
+//   # awsx2 END
+//
+// The file is always rewritten whole (staged next to it, then renamed), so an
+// interrupted write can't leave /etc/hosts truncated.
+
+/// Split /etc/hosts into (lines outside our block, hostnames inside it).
+/// Lines carrying the legacy per-line `HOSTS_TAG` count as ours too. A BEGIN
+/// with no END after it (a crash, a hand edit) is dropped, and the lines after
+/// it are kept as the user's.
+fn parse_hosts(hosts: &str) -> (Vec<&str>, Vec<String>) {
+    let lines: Vec<&str> = hosts.lines().collect();
+    let mut outside = Vec::new();
+    let mut ours = Vec::new();
+    let mut in_block = false;
+    for (i, &line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if trimmed == HOSTS_BEGIN {
+            in_block = lines[i + 1..].iter().any(|l| l.trim() == HOSTS_END);
+        } else if trimmed == HOSTS_END {
+            in_block = false;
+        } else if in_block || line.contains(HOSTS_TAG) {
+            if let Some(host) = line.split('#').next().and_then(|l| l.split_whitespace().nth(1)) {
+                ours.push(host.to_string());
             }
+        } else {
+            outside.push(line);
         }
     }
+    (outside, ours)
+}
 
-    let entry = format!("127.0.0.1 {} {}\n", hostname, HOSTS_TAG);
-    let mut child = Command::new("sudo")
-        .args(["tee", "-a", "/etc/hosts"])
-        .stdin(Stdio::piped())
-        .stdout(Stdio::null())
-        .spawn()?;
-    if let Some(mut stdin) = child.stdin.take() {
-        use std::io::Write;
-        stdin.write_all(entry.as_bytes())?;
+fn render_hosts(outside: &[&str], hostnames: &[String]) -> String {
+    let mut out: String = outside.iter().map(|l| format!("{}\n", l)).collect();
+    if !hostnames.is_empty() {
+        out.push_str(HOSTS_BEGIN);
+        out.push('\n');
+        for host in hostnames {
+            out.push_str(&format!("127.0.0.1 {}\n", host));
+        }
+        out.push_str(HOSTS_END);
+        out.push('\n');
     }
-    let status = child.wait()?;
-    if !status.success() {
-        return Err(AppError::Tunnel(
-            "Failed to add /etc/hosts entry (sudo required)".into(),
-        ));
+    out
+}
+
+/// Replace /etc/hosts with `content`: stage a copy beside it, then rename over.
+/// Falls back to an in-place write where /etc/hosts is a mount point (containers).
+fn write_hosts(content: &str) -> Result<()> {
+    use std::io::Write;
+    let staged = format!("{}.awsx2.tmp", HOSTS_PATH);
+
+    let mut tmp = tempfile::NamedTempFile::new()?;
+    tmp.write_all(content.as_bytes())?;
+    tmp.flush()?;
+    let tmp_path = tmp.path().to_string_lossy().to_string();
+
+    let sudo = |args: &[&str]| -> bool {
+        let is_root = unsafe { libc::geteuid() } == 0;
+        let mut cmd = if is_root { Command::new(args[0]) } else { Command::new("sudo") };
+        if !is_root { cmd.arg(args[0]); }
+        cmd.args(&args[1..])
+            .stdout(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success())
+    };
+
+    if !sudo(&["install", "-m", "644", &tmp_path, &staged]) {
+        return Err(AppError::Tunnel("Failed to update /etc/hosts (sudo required)".into()));
+    }
+    if sudo(&["mv", "-f", &staged, HOSTS_PATH]) {
+        return Ok(());
+    }
+    let copied = sudo(&["cp", &staged, HOSTS_PATH]);
+    sudo(&["rm", "-f", &staged]);
+    if copied {
+        Ok(())
+    } else {
+        Err(AppError::Tunnel("Failed to update /etc/hosts (sudo required)".into()))
+    }
+}
+
+fn add_hosts_entry(hostname: &str) -> Result<()> {
+    let hosts = std::fs::read_to_string(HOSTS_PATH)?;
+    let (outside, mut ours) = parse_hosts(&hosts);
+    if ours.iter().any(|h| h == hostname) {
+        println!("  /etc/hosts: already has {}", hostname);
+        return Ok(());
     }
+    ours.push(hostname.to_string());
+    write_hosts(&render_hosts(&outside, &ours))?;
     println!("  /etc/hosts: added 127.0.0.1 {}", hostname);
     Ok(())
 }
 
-/// Remove all awsx2-proxy tagged lines from /etc/hosts.
-/// Reads the file, filters out tagged lines, writes back via sudo tee.
+/// Hostnames currently in the awsx2 block of /etc/hosts.
+fn hosts_entries() -> Vec<String> {
+    std::fs::read_to_string(HOSTS_PATH)
+        .map(|h| parse_hosts(&h).1)
+        .unwrap_or_default()
+}
+
+/// Remove the awsx2 block (and any legacy tagged lines) from /etc/hosts,
+/// leaving every other line untouched.
 fn remove_hosts_entries() {
-    let hosts = match std::fs::read_to_string("/etc/hosts") {
+    let hosts = match std::fs::read_to_string(HOSTS_PATH) {
         Ok(h) => h,
         Err(_) => return,
     };
-    let filtered: String = hosts
-        .lines()
-        .filter(|line| !line.contains(HOSTS_TAG))
-        .map(|line| format!("{}\n", line))
-        .collect();
-    if filtered.len() == hosts.len() {
-        return; // nothing to remove
-    }
-    let mut child = match Command::new("sudo")
-        .args(["tee", "/etc/hosts"])
-        .stdin(Stdio::piped())
-        .stdout(Stdio::null())
-        .spawn()
-    {
-        Ok(c) => c,
-        Err(_) => return,
-    };
-    if let Some(mut stdin) = child.stdin.take() {
-        use std::io::Write;
-        let _ = stdin.write_all(filtered.as_bytes());
+    let (outside, _) = parse_hosts(&hosts);
+    let cleaned = render_hosts(&outside, &[]);
+    if cleaned == hosts {
+        return; // nothing of ours in there
+    }
+    if let Err(e) = write_hosts(&cleaned) {
+        eprintln!("  {}", e);
+    }
+}
+
+/// Drop just these hostnames from the awsx2 block of /etc/hosts.
+fn remove_hosts_entries_for(hostnames: &[String]) {
+    let Ok(hosts) = std::fs::read_to_string(HOSTS_PATH) else { return };
+    let (outside, ours) = parse_hosts(&hosts);
+    let kept: Vec<String> = ours.into_iter().filter(|h| !hostnames.contains(h)).collect();
+    let cleaned = render_hosts(&outside, &kept);
+    if cleaned == hosts {
+        return;
+    }
+    if let Err(e) = write_hosts(&cleaned) {
+        eprintln!("  {}", e);
     }
-    let _ = child.wait();
 }
 
 // ── Nginx management ─────────────────────────────────────────────────────────
@@ -286,7 +468,7 @@ fn has_systemctl_nginx() -> bool {
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .status()
-        .map_or(false, |s| s.success())
+        .is_ok_and(|s| s.success())
 }
 
 fn reload_nginx() -> Result<()> {
@@ -321,7 +503,7 @@ fn reload_nginx_systemd() -> Result<()> {
     let is_active = Command::new("systemctl")
         .args(["is-active", "--quiet", "nginx"])
         .status()
-        .map_or(false, |s| s.success());
+        .is_ok_and(|s| s.success());
 
     let action = if is_active { "reload" } else { "start" };
 
@@ -329,7 +511,7 @@ fn reload_nginx_systemd() -> Result<()> {
     let ok = Command::new("systemctl")
         .args([action, "nginx"])
         .status()
-        .map_or(false, |s| s.success());
+        .is_ok_and(|s| s.success());
     if ok {
         println!("  nginx: {} (systemctl)", action);
         return Ok(());
@@ -339,7 +521,7 @@ fn reload_nginx_systemd() -> Result<()> {
     let ok = Command::new("sudo")
         .args(["systemctl", action, "nginx"])
         .status()
-        .map_or(false, |s| s.success());
+        .is_ok_and(|s| s.success());
     if ok {
         println!("  nginx: {} (sudo systemctl)", action);
         return Ok(());
@@ -358,7 +540,7 @@ fn reload_nginx_direct(bin: &str) -> Result<()> {
         .args(["-x", "nginx"])
         .stdout(Stdio::null())
         .status()
-        .map_or(false, |s| s.success());
+        .is_ok_and(|s| s.success());
 
     if is_running {
         // Reload — try without sudo, then with sudo.
@@ -366,7 +548,7 @@ fn reload_nginx_direct(bin: &str) -> Result<()> {
         let ok = Command::new(bin)
             .args(["-s", "reload"])
             .status()
-            .map_or(false, |s| s.success());
+            .is_ok_and(|s| s.success());
         if ok {
             println!("  nginx: reloaded");
             return Ok(());
@@ -374,7 +556,7 @@ fn reload_nginx_direct(bin: &str) -> Result<()> {
         let ok = Command::new("sudo")
             .args([bin, "-s", "reload"])
             .status()
-            .map_or(false, |s| s.success());
+            .is_ok_and(|s| s.success());
         if ok {
             println!("  nginx: reloaded");
             return Ok(());
@@ -386,7 +568,7 @@ fn reload_nginx_direct(bin: &str) -> Result<()> {
     let ok = Command::new("sudo")
         .args([bin])
         .status()
-        .map_or(false, |s| s.success());
+        .is_ok_and(|s| s.success());
     if ok {
         println!("  nginx: started");
         return Ok(());
@@ -430,3 +612,27 @@ fn flush_dns_cache() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hosts_splits_managed_block() {
+        let hosts = format!(
+            "127.0.0.1 localhost\n{}\n127.0.0.1 app.internal\n{}\n10.0.0.1 db\n",
+            HOSTS_BEGIN, HOSTS_END
+        );
+        let (outside, ours) = parse_hosts(&hosts);
+        assert_eq!(outside, vec!["127.0.0.1 localhost", "10.0.0.1 db"]);
+        assert_eq!(ours, vec!["app.internal"]);
+    }
+
+    #[test]
+    fn parse_hosts_keeps_lines_after_unterminated_begin() {
+        let hosts = format!("127.0.0.1 localhost\n{}\n10.0.0.1 db\n10.0.0.2 cache\n", HOSTS_BEGIN);
+        let (outside, ours) = parse_hosts(&hosts);
+        assert_eq!(outside, vec!["127.0.0.1 localhost", "10.0.0.1 db", "10.0.0.2 cache"]);
+        assert!(ours.is_empty());
+    }
+}
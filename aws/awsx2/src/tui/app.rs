@@ -1,8 +1,16 @@
 //! Central application state for the TUI.
 
-use std::sync::mpsc::{self, Receiver, Sender};
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, SendError, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use crate::models::{Instance, TunnelProcess, VpnConfig};
+use ratatui::layout::{Position, Rect};
+
+use crate::models::{BenchResult, Instance, TunnelProcess, VolumeInfo, VpnConfig, VpnPhase};
+use crate::tui::theme::Theme;
 
 // ── Tab ───────────────────────────────────────────────────────────────────────
 
@@ -39,11 +47,13 @@ pub enum Popup {
     Confirm { message: String, tag: ConfirmTag, selected_yes: bool },
     /// Show result text (success or error)
     Result { title: String, body: String, is_error: bool },
+    /// Full detail panel for one instance (snapshot taken when opened).
+    InstanceDetail(Box<Instance>),
     /// Spinner overlay
     Loading { message: String },
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum InputTag {
     NewTunnelPattern,
     NewTunnelLocalPort,
@@ -57,21 +67,44 @@ pub enum InputTag {
     NewTunnelBastionRemotePort,
     LoginProfile,
     ResolveUrl,
+    ExplainUrlPath,
     TestPort,
     SwitchProfile,
     SwitchRegion,
+    LaunchTemplate,
+    LaunchName,
+    /// Type the instance name to confirm termination of `(id, name)`.
+    TerminateInstanceName(String, String),
+    /// New type for `(id, name)`: picked from the recent list, or typed in.
+    SwitchInstanceType(String, String),
+    /// `Key=Value` tag to set on `(id, name)`.
+    TagInstance(String, String),
+    /// Space-separated server-side filters for describe-instances.
+    InstanceAwsFilters,
     VpnMfaCode,
+    VpnSelectConfig,
+    VpnNewConfigName,
     VpnSetupUsername,
     VpnSetupPassword,
     VpnSetupOvpnPath,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Instance actions carry the `(id, name)` shown in the prompt, and tunnel
+/// stops the local port, so a refresh that reorders the list can't redirect
+/// them to another instance or tunnel.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ConfirmTag {
-    StopTunnel(usize),
+    StopTunnel(u16),
     StopAllTunnels,
-    StopInstance,
-    ForceStopInstance,
+    StopInstance(String, String),
+    ForceStopInstance(String, String),
+    RebootInstance(String, String),
+    TerminateInstance(String, String),
+    /// Offered when a call fails with `AppError::CredentialsExpired`.
+    SsoLogin,
+    /// The wizard's local port is already listening; carries the final
+    /// wizard step to resume if the user continues anyway.
+    ReusePort(InputTag),
 }
 
 // ── Background task messages ──────────────────────────────────────────────────
@@ -83,7 +116,179 @@ pub enum BgMessage {
     TunnelStarted(crate::error::Result<TunnelProcess>),
     ActionDone(crate::error::Result<String>),
     VpnConnected(crate::error::Result<String>),
-    VpnProgress(String),
+    VpnProgress(VpnPhase, String),
+    /// Replaces the message of an open `Popup::Loading`.
+    Progress(String),
+    /// `B` benchmark finished for the tunnel on this local port.
+    TunnelBench(u16, crate::error::Result<BenchResult>),
+    /// EBS volumes for the instance with this ID, for the detail popup.
+    VolumesLoaded(String, crate::error::Result<Vec<VolumeInfo>>),
+    /// `c` console log for the named instance; None when EC2 has none yet.
+    ConsoleOutput(String, crate::error::Result<Option<String>>),
+    /// Watchdog reconnect finished for the tunnel on this local port.
+    TunnelReconnected(u16, crate::error::Result<TunnelProcess>),
+    /// Enabled regions for Tools → Switch Region.
+    RegionsLoaded(crate::error::Result<Vec<String>>),
+    /// A line for the event log from a background task, e.g. a forwarder
+    /// or proxy that a tunnel restart could not bring back.
+    Notice(String),
+}
+
+/// Sender for a task shown behind `Popup::Loading`. Once the user cancels
+/// with `Esc` its messages are dropped, so an abandoned thread can't pop up
+/// a result later; a tunnel that still came up is stopped.
+#[derive(Clone)]
+pub struct LoadingSender {
+    tx: Sender<BgMessage>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl LoadingSender {
+    // Like `Sender::send`, with the (large) unsent message boxed.
+    pub fn send(&self, msg: BgMessage) -> Result<(), Box<SendError<BgMessage>>> {
+        if self.is_cancelled() {
+            // Nothing will list this tunnel, so don't leave its session holding the port.
+            if let BgMessage::TunnelStarted(Ok(tp)) = &msg {
+                crate::tunnel::stop_tunnel(tp.pid);
+                crate::tunnel::forget_tunnel(tp.local_port);
+            }
+            return Ok(());
+        }
+        self.tx.send(msg).map_err(Box::new)
+    }
+
+    /// Checked between slow steps so a cancelled task stops early.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Give up auto-reconnecting a tunnel after this many failed attempts.
+const MAX_RECONNECT_ATTEMPTS: u32 = 3;
+/// Delay before the first retry; doubles with each failure.
+const RECONNECT_BACKOFF: Duration = Duration::from_secs(15);
+
+/// Instances table column used for sorting (`1`–`8` / `o`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortColumn {
+    Id,
+    Name,
+    Type,
+    State,
+    Ssm,
+    Tunnel,
+    PrivateIp,
+    Uptime,
+}
+
+impl SortColumn {
+    /// Table order, so `ALL[n - 1]` is the column bound to key `n`.
+    pub const ALL: [SortColumn; 8] = [
+        SortColumn::Id,
+        SortColumn::Name,
+        SortColumn::Type,
+        SortColumn::State,
+        SortColumn::Ssm,
+        SortColumn::Tunnel,
+        SortColumn::PrivateIp,
+        SortColumn::Uptime,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortColumn::Id => "Instance ID",
+            SortColumn::Name => "Name",
+            SortColumn::Type => "Type",
+            SortColumn::State => "State",
+            SortColumn::Ssm => "SSM",
+            SortColumn::Tunnel => "Tunnel",
+            SortColumn::PrivateIp => "Private IP",
+            SortColumn::Uptime => "Uptime",
+        }
+    }
+
+    fn compare(self, a: &Instance, b: &Instance) -> std::cmp::Ordering {
+        match self {
+            SortColumn::Id => a.id.cmp(&b.id),
+            SortColumn::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortColumn::Type => a.instance_type.cmp(&b.instance_type),
+            SortColumn::State => a.state.as_str().cmp(b.state.as_str()),
+            SortColumn::Ssm => a.ssm_status.as_str().cmp(b.ssm_status.as_str()),
+            // Instances with a tunnel first, then by local port.
+            SortColumn::Tunnel => {
+                let port = |i: &Instance| i.tunnel.as_ref().map_or(u16::MAX, |t| t.local_port);
+                port(a).cmp(&port(b))
+            }
+            // Numeric, so 10.0.0.9 sorts before 10.0.0.10; no IP sorts last.
+            SortColumn::PrivateIp => {
+                let ip = |i: &Instance| i.private_ip.as_deref()
+                    .and_then(|s| s.parse::<std::net::Ipv4Addr>().ok())
+                    .map_or(u32::MAX, u32::from);
+                ip(a).cmp(&ip(b))
+            }
+            // Instances that aren't running sort last.
+            SortColumn::Uptime => {
+                let up = |i: &Instance| crate::aws::uptime_secs(i).unwrap_or(u64::MAX);
+                up(a).cmp(&up(b))
+            }
+        }
+    }
+}
+
+/// Screen regions of the last frame, kept for mouse hit-testing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HitAreas {
+    /// Row holding the tab titles.
+    pub tabs: Rect,
+    /// Data rows of the active tab's table or list (inside the border,
+    /// below any header).
+    pub rows: Rect,
+    /// Index of the first visible row (the widget's scroll offset).
+    pub row_offset: usize,
+    /// Largest useful scroll offset of the open result / detail popup.
+    pub scroll_max: u16,
+}
+
+impl HitAreas {
+    /// Index of the row under `(x, y)`, if any.
+    pub fn row_at(&self, x: u16, y: u16) -> Option<usize> {
+        self.rows.contains(Position { x, y })
+            .then(|| self.row_offset + (y - self.rows.y) as usize)
+    }
+}
+
+/// Event-loop tick: spinner frames, background polling and refresh timers.
+pub const TICK_RATE: Duration = Duration::from_millis(200);
+
+/// Latency probes kept per tunnel for the Tunnels tab sparkline.
+pub const LATENCY_HISTORY_LEN: usize = 20;
+
+/// Watchdog bookkeeping for one local port.
+#[derive(Debug, Clone, Copy)]
+pub struct ReconnectState {
+    pub attempts: u32,
+    pub next_try: Instant,
+}
+
+// ── Event log ─────────────────────────────────────────────────────────────────
+
+/// Entries kept for the `L` event log pane; older ones are dropped.
+pub const EVENT_LOG_LEN: usize = 100;
+
+/// One line of the event log: an action started or finished, an error, or a
+/// tunnel going down or coming back.
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    /// UTC time, `HH:MM:SS`.
+    pub time: String,
+    pub text: String,
+    pub is_error: bool,
+}
+
+/// Current UTC time as `HH:MM:SS`, the clock the audit log uses.
+fn clock_time() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    crate::aws::unix_to_iso8601(now)[11..19].to_string()
 }
 
 // ── App state ─────────────────────────────────────────────────────────────────
@@ -97,23 +302,52 @@ pub struct App {
     // Instances tab
     pub instances: Vec<Instance>,
     pub instance_selected: usize,
+    /// First visible row of the Instances table; moved while rendering just
+    /// enough to keep the selection in view.
+    pub instance_offset: Cell<usize>,
     pub instance_filter: String,
     pub instance_filter_active: bool,
+    /// Bypass the configured instance_name_include scoping.
+    pub show_all_instances: bool,
+    /// Server-side describe-instances filters (`key=value`), set with `F`.
+    pub aws_filters: Vec<String>,
+    /// None keeps the API order.
+    pub sort_column: Option<SortColumn>,
+    pub sort_desc: bool,
+    /// `(id, name)` of an instance to open an SSM shell on; the main loop
+    /// suspends the TUI for it.
+    pub pending_shell: Option<(String, String)>,
 
     // Tunnels tab
     pub tunnels: Vec<TunnelProcess>,
     pub tunnel_selected: usize,
+    pub tunnel_filter: String,
+    pub tunnel_filter_active: bool,
+    /// Watchdog: reconnect tunnels that go DOWN (toggled with `w`).
+    pub auto_reconnect: bool,
+    pub reconnects: HashMap<u16, ReconnectState>,
+    reconnecting: HashSet<u16>,
+    /// Recent probe results per tunnel pid, oldest first (None = no answer).
+    pub latency_history: HashMap<u32, VecDeque<Option<u64>>>,
 
     // Tools tab
     pub tool_selected: usize,
 
     // VPN tab
     pub vpn_selected: usize,
+    /// Named VPN profile in use (None = default vpn.json).
+    pub vpn_profile: Option<String>,
+    /// Phase of an in-flight `vpn::connect`, drives the progress gauge.
+    pub vpn_phase: Option<VpnPhase>,
     pub vpn_config: VpnConfig,
     pub vpn_status: String,
 
     // Popup / modal
     pub popup: Popup,
+    /// Scroll offset of an open `Popup::Result` (clamped when rendering).
+    pub result_scroll: u16,
+    /// EBS volumes fetched for the detail popup, keyed by instance ID.
+    pub detail_volumes: Option<(String, crate::error::Result<Vec<VolumeInfo>>)>,
 
     // Loading
     pub loading: bool,
@@ -125,12 +359,27 @@ pub struct App {
     // Background channel
     pub tx: Sender<BgMessage>,
     pub rx: Receiver<BgMessage>,
+    /// Cancel flag of the task behind the open `Popup::Loading`.
+    loading_cancel: Arc<AtomicBool>,
 
     // Wizard state (multi-step input buffer)
     pub wizard_buf: WizardBuf,
 
     pub quit: bool,
     pub status_msg: Option<String>,
+    /// Recent actions, results and errors, oldest first.
+    pub event_log: VecDeque<LogEntry>,
+    /// Event log pane shown under the active tab (toggled with `L`).
+    pub show_event_log: bool,
+
+    // System clipboard — kept alive for the whole session because on X11 the
+    // copied content is served by the owning process until it exits.
+    clipboard: Option<arboard::Clipboard>,
+
+    /// Written while rendering (which only borrows `App`), read by mouse handling.
+    pub hit_areas: Cell<HitAreas>,
+
+    pub theme: Theme,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -145,63 +394,208 @@ pub struct WizardBuf {
 
 impl App {
     pub fn new() -> Self {
+        let vpn_status = if crate::vpn::is_connected() {
+            format!("CONNECTED ({})", crate::vpn::get_vpn_ip().unwrap_or_else(|| "?".into()))
+        } else {
+            "DISCONNECTED".into()
+        };
+        let mut app = Self::with_environment(
+            crate::aws::get_profile(),
+            crate::aws::get_region(None),
+            crate::vpn::load_config(None).unwrap_or_default(),
+            vpn_status,
+        );
+        if crate::config::settings().restore_tui_state {
+            let state = crate::config::load_tui_state();
+            if let Some(i) = Tab::titles().iter().position(|t| *t == state.tab) {
+                app.tab = Tab::from_index(i);
+            }
+            app.instance_filter = state.instance_filter;
+        }
+        app
+    }
+
+    /// App with empty lists and no saved state; runs no commands and reads no
+    /// files, so tests can build one.
+    #[cfg(test)]
+    pub fn empty() -> Self {
+        Self::with_environment(String::new(), String::new(), VpnConfig::default(), "DISCONNECTED".into())
+    }
+
+    fn with_environment(profile: String, region: String, vpn_config: VpnConfig, vpn_status: String) -> Self {
         let (tx, rx) = mpsc::channel();
         Self {
-            profile: crate::aws::get_profile(),
-            region: crate::aws::get_region(None),
+            profile,
+            region,
             tab: Tab::Instances,
             tunnel_refresh_ticks: 0,
             instances: vec![],
             instance_selected: 0,
+            instance_offset: Cell::new(0),
             instance_filter: String::new(),
             instance_filter_active: false,
+            show_all_instances: false,
+            aws_filters: vec![],
+            sort_column: None,
+            sort_desc: false,
+            pending_shell: None,
             tunnels: vec![],
             tunnel_selected: 0,
+            tunnel_filter: String::new(),
+            tunnel_filter_active: false,
+            auto_reconnect: false,
+            reconnects: HashMap::new(),
+            reconnecting: HashSet::new(),
+            latency_history: HashMap::new(),
             tool_selected: 0,
             vpn_selected: 0,
-            vpn_config: crate::vpn::load_config().unwrap_or_default(),
-            vpn_status: if crate::vpn::is_connected() {
-                format!("CONNECTED ({})", crate::vpn::get_vpn_ip().unwrap_or_else(|| "?".into()))
-            } else {
-                "DISCONNECTED".into()
-            },
+            vpn_profile: None,
+            vpn_phase: None,
+            vpn_config,
+            vpn_status,
             popup: Popup::None,
+            result_scroll: 0,
+            detail_volumes: None,
             loading: false,
             loading_message: String::new(),
             spinner_tick: 0,
             tx,
             rx,
+            loading_cancel: Arc::new(AtomicBool::new(false)),
             wizard_buf: WizardBuf::default(),
             quit: false,
             status_msg: None,
+            event_log: VecDeque::with_capacity(EVENT_LOG_LEN),
+            show_event_log: false,
+            clipboard: None,
+            hit_areas: Cell::new(HitAreas::default()),
+            theme: Theme::default(),
+        }
+    }
+
+    /// Remember the tab and instance filter for the next run, when enabled.
+    pub fn save_state(&self) {
+        if crate::config::settings().restore_tui_state {
+            crate::config::save_tui_state(&crate::config::TuiState {
+                tab: Tab::titles()[self.tab.index()].to_string(),
+                instance_filter: self.instance_filter.clone(),
+            });
         }
     }
 
+    /// Sender for a task started behind `Popup::Loading`; `cancel_loading`
+    /// silences it.
+    pub fn loading_sender(&self) -> LoadingSender {
+        LoadingSender { tx: self.tx.clone(), cancelled: self.loading_cancel.clone() }
+    }
+
+    /// `Esc` on `Popup::Loading`: stop waiting for the current task. Its thread
+    /// is abandoned (an SSM call can't be interrupted) and whatever it sends is
+    /// dropped; later tasks get a fresh flag.
+    pub fn cancel_loading(&mut self) {
+        self.loading_cancel.store(true, Ordering::Relaxed);
+        self.loading_cancel = Arc::new(AtomicBool::new(false));
+        self.vpn_phase = None;
+        self.log_event("Stopped waiting for the running task", false);
+        self.popup = Popup::Result {
+            title: "Cancelled".into(),
+            body: "Stopped waiting. A tunnel that still comes up is stopped again and a VPN connect\n\
+                   is aborted; other tasks may still finish in the background.".into(),
+            is_error: false,
+        };
+    }
+
+    /// Volumes for the detail popup of `instance_id`; None while loading.
+    pub fn volumes_for(&self, instance_id: &str) -> Option<&crate::error::Result<Vec<VolumeInfo>>> {
+        self.detail_volumes.as_ref().filter(|(id, _)| id == instance_id).map(|(_, r)| r)
+    }
+
+    /// Reload the instance table (served from the short-lived cache when fresh).
     pub fn refresh_instances(&mut self) {
         self.loading = true;
         self.loading_message = "Loading instances...".to_string();
         let tx = self.tx.clone();
         let profile = std::env::var("AWS_PROFILE").ok().filter(|s| !s.is_empty());
+        let filters = self.aws_filters.clone();
         std::thread::spawn(move || {
-            let _ = tx.send(BgMessage::InstancesLoaded(
-                crate::aws::list_instances(profile.as_deref()),
-            ));
+            let result = if filters.is_empty() {
+                crate::aws::list_instances_cached(profile.as_deref())
+            } else {
+                crate::aws::list_instances_filtered(&filters, profile.as_deref())
+            };
+            let _ = tx.send(BgMessage::InstancesLoaded(result));
+        });
+    }
+
+    pub fn refresh_vpn_status(&mut self) {
+        self.vpn_status = if crate::vpn::is_connected() {
+            format!("CONNECTED ({})", crate::vpn::get_vpn_ip().unwrap_or_else(|| "?".into()))
+        } else {
+            "DISCONNECTED".into()
+        };
+    }
+
+    /// Reload every tab's data, e.g. after switching profile.
+    pub fn refresh_all(&mut self) {
+        self.refresh_instances();
+        self.refresh_tunnels();
+        self.refresh_vpn_status();
+    }
+
+    /// Region picker (Tools → Switch Region): enabled regions in a list,
+    /// falling back to free text when they can't be fetched.
+    pub fn open_region_switcher(&mut self) {
+        let tx = self.loading_sender();
+        self.show_loading("Listing enabled regions...");
+        std::thread::spawn(move || {
+            let _ = tx.send(BgMessage::RegionsLoaded(crate::aws::enabled_regions(None)));
         });
     }
 
+    pub fn open_region_input(&mut self) {
+        self.popup = Popup::Input {
+            title: "Switch AWS Region".into(),
+            placeholder: "e.g. us-east-1, ap-northeast-1".into(),
+            value: self.region.clone(),
+            tag: InputTag::SwitchRegion,
+        };
+    }
+
+    /// Profile picker (`P` anywhere, or Tools → Switch Profile).
+    pub fn open_profile_switcher(&mut self) {
+        let profiles = crate::aws::list_profiles();
+        let selected = profiles.iter().position(|p| *p == self.profile).unwrap_or(0);
+        self.popup = Popup::Select {
+            title: "Switch AWS Profile".into(),
+            items: profiles,
+            selected,
+            tag: InputTag::SwitchProfile,
+        };
+    }
+
     pub fn refresh_tunnels(&mut self) {
         let tx = self.tx.clone();
         std::thread::spawn(move || {
-            let _ = tx.send(BgMessage::TunnelsLoaded(crate::tunnel::detect_tunnels()));
+            let mut tunnels = crate::tunnel::detect_tunnels();
+            let dead = crate::tunnel::dead_tunnels(&tunnels);
+            tunnels.extend(dead);
+            let _ = tx.send(BgMessage::TunnelsLoaded(tunnels));
         });
     }
 
     pub fn poll_bg(&mut self) {
         while let Ok(msg) = self.rx.try_recv() {
             // VpnProgress keeps the loading state active
-            if matches!(msg, BgMessage::VpnProgress(_)) {
-                if let BgMessage::VpnProgress(text) = msg {
-                    self.popup = Popup::Loading { message: text };
+            if let BgMessage::VpnProgress(phase, text) = msg {
+                self.vpn_phase = Some(phase);
+                self.log_event(&text, false);
+                self.popup = Popup::Loading { message: text };
+                continue;
+            }
+            if let BgMessage::Progress(text) = msg {
+                self.log_event(&text, false);
+                if let Popup::Loading { ref mut message } = self.popup {
+                    *message = text;
                 }
                 continue;
             }
@@ -209,83 +603,539 @@ impl App {
             match msg {
                 BgMessage::InstancesLoaded(Ok(instances)) => {
                     self.instances = instances;
-                    self.instance_selected = self.instance_selected
-                        .min(self.instances.len().saturating_sub(1));
-                }
-                BgMessage::InstancesLoaded(Err(e)) => {
-                    self.popup = Popup::Result { title: "Error".into(), body: e.to_string(), is_error: true };
+                    crate::tunnel::attach_tunnels(&mut self.instances, &self.tunnels);
+                    self.clamp_instance_selected();
                 }
+                BgMessage::InstancesLoaded(Err(e)) => self.show_error("Error", e),
                 BgMessage::TunnelsLoaded(tunnels) => {
+                    self.log_tunnel_changes(&tunnels);
+                    if self.auto_reconnect {
+                        self.watchdog(&tunnels);
+                    }
+                    self.latency_history.retain(|pid, _| tunnels.iter().any(|t| t.pid == *pid));
+                    for t in &tunnels {
+                        self.record_latency(t);
+                    }
                     self.tunnels = tunnels;
+                    crate::tunnel::attach_tunnels(&mut self.instances, &self.tunnels);
                     self.tunnel_selected = self.tunnel_selected
-                        .min(self.tunnels.len().saturating_sub(1));
+                        .min(self.filtered_tunnels().len().saturating_sub(1));
                 }
                 BgMessage::TunnelStarted(Ok(tp)) => {
                     let latency_str = tp.latency_ms
                         .map(|ms| format!(" ({}ms)", ms))
                         .unwrap_or_default();
                     let body = format!(
-                        "localhost:{} -> {}:{}{}",
+                        "localhost:{} -> {}:{}{}\n\n{}",
                         tp.local_port,
                         tp.remote_host.as_deref().unwrap_or(&tp.instance_name),
                         tp.remote_port,
                         latency_str,
+                        crate::tunnel::command_strings(&tp),
                     );
+                    let via = tp.remote_host.as_ref().map(|_| format!(" via {}", tp.instance_name));
+                    self.log_event(format!(
+                        "Tunnel started: {}{}", crate::tunnel::tunnel_detail(&tp), via.unwrap_or_default()
+                    ), false);
+                    self.record_latency(&tp);
                     self.tunnels.push(tp);
                     self.popup = Popup::Result { title: "Tunnel Started".into(), body, is_error: false };
                 }
-                BgMessage::TunnelStarted(Err(e)) => {
-                    self.popup = Popup::Result { title: "Tunnel Error".into(), body: e.to_string(), is_error: true };
-                }
+                BgMessage::TunnelStarted(Err(e)) => self.show_error("Tunnel Error", e),
                 BgMessage::ActionDone(Ok(msg)) => {
+                    self.log_event(msg.lines().next().unwrap_or_default(), false);
                     self.popup = Popup::Result { title: "Done".into(), body: msg, is_error: false };
                     self.refresh_instances();
                 }
-                BgMessage::ActionDone(Err(e)) => {
-                    self.popup = Popup::Result { title: "Error".into(), body: e.to_string(), is_error: true };
+                BgMessage::ActionDone(Err(e)) => self.show_error("Error", e),
+                BgMessage::VolumesLoaded(id, result) => {
+                    // A slow lookup for a popup that was since closed or
+                    // replaced must not overwrite the current one's volumes.
+                    if matches!(&self.popup, Popup::InstanceDetail(inst) if inst.id == id) {
+                        self.detail_volumes = Some((id, result));
+                    }
                 }
-                BgMessage::VpnConnected(Ok(msg)) => {
-                    self.vpn_status = if crate::vpn::is_connected() {
-                        format!("CONNECTED ({})", crate::vpn::get_vpn_ip().unwrap_or_else(|| "?".into()))
-                    } else {
-                        "DISCONNECTED".into()
+                BgMessage::ConsoleOutput(name, Ok(output)) => {
+                    self.log_event(format!("Fetched the console output of {}", name), false);
+                    self.result_scroll = 0;
+                    self.popup = Popup::Result {
+                        title: format!("Console — {}", name),
+                        body: output
+                            .map(|o| crate::tui::pages::instances::printable(&o))
+                            .unwrap_or_else(|| crate::aws::NO_CONSOLE_OUTPUT.to_string()),
+                        is_error: false,
                     };
+                }
+                BgMessage::ConsoleOutput(_, Err(e)) => self.show_error("Console Error", e),
+                BgMessage::VpnConnected(Ok(msg)) => {
+                    self.log_event(msg.lines().next().unwrap_or_default(), false);
+                    self.vpn_phase = None;
+                    self.refresh_vpn_status();
                     self.popup = Popup::Result { title: "VPN".into(), body: msg, is_error: false };
                 }
                 BgMessage::VpnConnected(Err(e)) => {
+                    self.vpn_phase = None;
                     self.vpn_status = "DISCONNECTED".into();
-                    self.popup = Popup::Result { title: "VPN Error".into(), body: e.to_string(), is_error: true };
+                    self.show_error("VPN Error", e);
+                }
+                BgMessage::TunnelBench(port, Ok(result)) => {
+                    self.log_event(format!("Benchmarked localhost:{}", port), false);
+                    self.popup = Popup::Result {
+                        title: format!("Benchmark — localhost:{}", port),
+                        body: result.summary(),
+                        is_error: false,
+                    };
+                }
+                BgMessage::TunnelBench(port, Err(e)) => {
+                    self.show_error(&format!("Benchmark — localhost:{}", port), e);
+                }
+                BgMessage::Notice(msg) => self.log_event(msg, false),
+                BgMessage::TunnelReconnected(port, Ok(_)) => {
+                    self.reconnecting.remove(&port);
+                    self.reconnects.remove(&port);
+                    self.notify(format!("Reconnected tunnel on port {}", port));
+                    self.refresh_tunnels();
+                }
+                BgMessage::TunnelReconnected(port, Err(e)) => {
+                    self.reconnecting.remove(&port);
+                    let attempts = self.reconnects.get(&port).map(|r| r.attempts).unwrap_or(0);
+                    let msg = if attempts >= MAX_RECONNECT_ATTEMPTS {
+                        format!("Gave up reconnecting port {} after {} attempts: {}", port, attempts, e)
+                    } else {
+                        format!("Reconnect of port {} failed ({}/{}): {}", port, attempts, MAX_RECONNECT_ATTEMPTS, e)
+                    };
+                    self.log_event(&msg, true);
+                    self.status_msg = Some(msg);
                 }
-                BgMessage::VpnProgress(_) => unreachable!(),
+                BgMessage::RegionsLoaded(Ok(regions)) if !regions.is_empty() => {
+                    let selected = regions.iter().position(|r| *r == self.region).unwrap_or(0);
+                    self.popup = Popup::Select {
+                        title: "Switch AWS Region".into(),
+                        items: regions,
+                        selected,
+                        tag: InputTag::SwitchRegion,
+                    };
+                }
+                BgMessage::RegionsLoaded(result) => {
+                    // e.g. no ec2:DescribeRegions permission: let the user type one.
+                    if let Err(e) = result {
+                        let msg = format!("Could not list regions: {}", e);
+                        self.log_event(&msg, true);
+                        self.status_msg = Some(msg);
+                    }
+                    self.open_region_input();
+                }
+                BgMessage::VpnProgress(..) | BgMessage::Progress(_) => unreachable!(),
             }
         }
     }
 
+    /// Error popup for a failed background task. Expired credentials get a
+    /// login prompt instead of the raw CLI error.
+    pub fn show_error(&mut self, title: &str, e: crate::error::AppError) {
+        self.log_event(format!("{}: {}", title, e), true);
+        self.popup = match e {
+            crate::error::AppError::CredentialsExpired => Popup::Confirm {
+                message: format!("AWS credentials for profile {} have expired. Run aws sso login now?", self.profile),
+                tag: ConfirmTag::SsoLogin,
+                selected_yes: true,
+            },
+            e => Popup::Result { title: title.into(), body: e.to_string(), is_error: true },
+        };
+    }
+
+    /// `aws sso login` in the background, then show the caller identity
+    /// (the `ActionDone` handler also reloads instances).
+    pub fn sso_login(&mut self, profile: Option<String>) {
+        let tx = self.loading_sender();
+        self.show_loading(match profile {
+            Some(ref p) => format!("aws sso login --profile {}...", p),
+            None => "aws sso login...".into(),
+        });
+        std::thread::spawn(move || {
+            let result = crate::aws::sso_login(profile.as_deref())
+                .and_then(|_| crate::aws::get_caller_identity(profile.as_deref()));
+            let _ = tx.send(BgMessage::ActionDone(result));
+        });
+    }
+
     pub fn filtered_instances(&self) -> Vec<&Instance> {
         let filter = self.instance_filter.to_lowercase();
-        self.instances.iter().filter(|i| {
+        let mut list: Vec<&Instance> = self.scoped_instances().filter(|i| {
             filter.is_empty()
                 || i.name.to_lowercase().contains(&filter)
                 || i.id.to_lowercase().contains(&filter)
                 || i.instance_type.to_lowercase().contains(&filter)
-        }).collect()
+                || i.tags.values().any(|v| v.to_lowercase().contains(&filter))
+        }).collect();
+        if let Some(col) = self.sort_column {
+            list.sort_by(|a, b| {
+                let ord = col.compare(a, b);
+                if self.sort_desc { ord.reverse() } else { ord }
+            });
+        }
+        list
+    }
+
+    /// Re-sort the table (`None` restores API order), keeping the cursor on
+    /// the same instance.
+    pub fn set_sort(&mut self, col: Option<SortColumn>, desc: bool) {
+        let selected_id = self.selected_instance().map(|i| i.id.clone());
+        self.sort_column = col;
+        self.sort_desc = desc;
+        let list = self.filtered_instances();
+        self.instance_selected = selected_id
+            .and_then(|id| list.iter().position(|i| i.id == id))
+            .unwrap_or(0)
+            .min(list.len().saturating_sub(1));
+    }
+
+    /// Instances inside the configured name scope (all of them when toggled off).
+    fn scoped_instances(&self) -> impl Iterator<Item = &Instance> {
+        let settings = crate::config::settings();
+        let show_all = self.show_all_instances;
+        self.instances.iter().filter(move |i| show_all || settings.includes_instance(&i.name))
+    }
+
+    /// Number of instances hidden by the configured name scope.
+    pub fn hidden_instance_count(&self) -> usize {
+        self.instances.len() - self.scoped_instances().count()
+    }
+
+    /// Append the tunnel's latest probe to its sparkline history.
+    fn record_latency(&mut self, t: &TunnelProcess) {
+        if t.pid == 0 { return; }
+        let history = self.latency_history.entry(t.pid).or_default();
+        if history.len() == LATENCY_HISTORY_LEN {
+            history.pop_front();
+        }
+        history.push_back(if t.port_open { t.latency_ms } else { None });
+    }
+
+    /// Start background reconnects for tunnels that dropped since the last
+    /// refresh, or that are due for another retry.
+    fn watchdog(&mut self, fresh: &[TunnelProcess]) {
+        let now = Instant::now();
+        for t in fresh.iter().filter(|t| !t.port_open) {
+            let port = t.local_port;
+            if self.reconnecting.contains(&port) { continue; }
+            let was_up = self.tunnels.iter().any(|old| old.local_port == port && old.port_open);
+            let state = match self.reconnects.get(&port) {
+                Some(s) => *s,
+                None if was_up => ReconnectState { attempts: 0, next_try: now },
+                None => continue,
+            };
+            if state.attempts >= MAX_RECONNECT_ATTEMPTS || now < state.next_try { continue; }
+
+            let attempts = state.attempts + 1;
+            self.reconnects.insert(port, ReconnectState {
+                attempts,
+                next_try: now + RECONNECT_BACKOFF * 2u32.pow(attempts - 1),
+            });
+            self.reconnecting.insert(port);
+            self.notify(format!(
+                "Tunnel on port {} dropped — reconnecting ({}/{})...", port, attempts, MAX_RECONNECT_ATTEMPTS
+            ));
+
+            let pid = t.pid;
+            let tx = self.tx.clone();
+            std::thread::spawn(move || {
+                if pid != 0 {
+                    crate::tunnel::stop_tunnel(pid);
+                    std::thread::sleep(Duration::from_millis(500));
+                }
+                let notices = tx.clone();
+                let result = crate::tunnel::restart_tunnel(port, None, |msg| {
+                    let _ = notices.send(BgMessage::Notice(msg.to_string()));
+                });
+                let _ = tx.send(BgMessage::TunnelReconnected(port, result));
+            });
+        }
+    }
+
+    /// Append a line to the event log, dropping the oldest past `EVENT_LOG_LEN`.
+    /// Multi-line text (e.g. an SSM error tail) is folded onto one line.
+    pub fn log_event(&mut self, text: impl AsRef<str>, is_error: bool) {
+        let text = text.as_ref().lines().map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>().join(" | ");
+        if text.is_empty() { return; }
+        if self.event_log.len() == EVENT_LOG_LEN {
+            self.event_log.pop_front();
+        }
+        self.event_log.push_back(LogEntry { time: clock_time(), text, is_error });
+    }
+
+    /// Status-bar message that is worth keeping in the event log too.
+    pub fn notify(&mut self, msg: impl Into<String>) {
+        let msg = msg.into();
+        self.log_event(&msg, false);
+        self.status_msg = Some(msg);
+    }
+
+    /// Status-bar spinner for a background action, logged as it starts.
+    pub fn start_task(&mut self, message: impl Into<String>) {
+        self.loading = true;
+        self.loading_message = message.into();
+        self.log_event(self.loading_message.clone(), false);
+    }
+
+    /// `Popup::Loading` for a background action, logged as it starts.
+    pub fn show_loading(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        self.log_event(&message, false);
+        self.popup = Popup::Loading { message };
+    }
+
+    /// Log tunnels that went down or came back since the last refresh.
+    fn log_tunnel_changes(&mut self, fresh: &[TunnelProcess]) {
+        for t in fresh {
+            let Some(old) = self.tunnels.iter().find(|old| old.local_port == t.local_port) else { continue };
+            match (old.port_open, t.port_open) {
+                (true, false) => self.log_event(format!("Tunnel on port {} is down", t.local_port), true),
+                (false, true) => self.log_event(format!("Tunnel on port {} is back up", t.local_port), false),
+                _ => {}
+            }
+        }
+    }
+
+    /// Keep the cursor inside the filtered list; call whenever it may shrink.
+    pub fn clamp_instance_selected(&mut self) {
+        let len = self.filtered_instances().len();
+        self.instance_selected = self.instance_selected.min(len.saturating_sub(1));
     }
 
     pub fn selected_instance(&self) -> Option<&Instance> {
-        self.filtered_instances().get(self.instance_selected).copied()
+        let list = self.filtered_instances();
+        // Clamped here too, so a stale cursor can't act on nothing.
+        let idx = self.instance_selected.min(list.len().saturating_sub(1));
+        list.get(idx).copied()
+    }
+
+    /// Tunnels matching the `/` filter on instance name or ID, remote host or
+    /// either port.
+    pub fn filtered_tunnels(&self) -> Vec<&TunnelProcess> {
+        let filter = self.tunnel_filter.to_lowercase();
+        self.tunnels.iter().filter(|t| {
+            filter.is_empty()
+                || t.instance_name.to_lowercase().contains(&filter)
+                || t.instance_id.to_lowercase().contains(&filter)
+                || t.remote_host.as_deref().is_some_and(|h| h.to_lowercase().contains(&filter))
+                || t.local_port.to_string().contains(&filter)
+                || t.remote_port.to_string().contains(&filter)
+        }).collect()
+    }
+
+    /// `T` / `t` in the detail popup: open the Tunnels tab filtered to the
+    /// tunnels through this instance (`Esc` there clears the filter).
+    pub fn show_instance_tunnels(&mut self, id: &str, name: &str) {
+        self.tab = Tab::Tunnels;
+        self.tunnel_filter = id.to_string();
+        self.tunnel_filter_active = false;
+        self.tunnel_selected = 0;
+        let label = if name.is_empty() { id } else { name };
+        self.status_msg = Some(match self.filtered_tunnels().len() {
+            0 => format!("No tunnels through {} — [n] opens one, [Esc] clears the filter", label),
+            n => format!("{} tunnel(s) through {} — [Esc] clears the filter", n, label),
+        });
+    }
+
+    /// Index into `tunnels` of the row selected in the filtered table.
+    pub fn selected_tunnel_index(&self) -> Option<usize> {
+        let t = self.selected_tunnel()?;
+        self.tunnels.iter().position(|x| std::ptr::eq(x, t))
     }
 
     pub fn selected_tunnel(&self) -> Option<&TunnelProcess> {
-        self.tunnels.get(self.tunnel_selected)
+        self.filtered_tunnels().get(self.tunnel_selected).copied()
+    }
+
+    /// Copy text to the system clipboard, reporting the outcome in the status bar.
+    /// Returns whether the copy succeeded.
+    pub fn copy_to_clipboard(&mut self, what: &str, text: &str) -> bool {
+        if self.clipboard.is_none() {
+            self.clipboard = arboard::Clipboard::new().ok();
+        }
+        let result = match self.clipboard.as_mut() {
+            Some(cb) => cb.set_text(text.to_string()).map_err(|e| e.to_string()),
+            None => Err("no clipboard available".into()),
+        };
+        let ok = result.is_ok();
+        self.status_msg = Some(match result {
+            Ok(()) => format!("Copied {} to clipboard", what),
+            Err(e) => format!("Copy failed: {}", e),
+        });
+        ok
+    }
+
+    /// `y`: copy a value, or show it in a result popup when there is no
+    /// clipboard (headless / SSH sessions) so it can still be selected by hand.
+    pub fn yank(&mut self, what: &str, text: &str) {
+        if !self.copy_to_clipboard(what, text) {
+            self.popup = Popup::Result {
+                title: format!("Copy {}", what),
+                body: text.to_string(),
+                is_error: false,
+            };
+        }
+    }
+
+    /// Record how far the open result / detail / help popup can scroll this frame.
+    pub fn set_scroll_max(&self, max: u16) {
+        let mut hit = self.hit_areas.get();
+        hit.scroll_max = max;
+        self.hit_areas.set(hit);
+    }
+
+    /// Scroll the open result / detail / help popup by `delta` lines, within what the
+    /// last frame could show, so scrolling back up responds straight away.
+    pub fn scroll_result(&mut self, delta: i32) {
+        let max = i32::from(self.hit_areas.get().scroll_max);
+        self.result_scroll = (i32::from(self.result_scroll) + delta).clamp(0, max) as u16;
+    }
+
+    /// Record where the active tab's rows were drawn this frame.
+    pub fn set_row_area(&self, block_area: Rect, header_rows: u16, offset: usize) {
+        let inner = Rect {
+            x: block_area.x + 1,
+            y: block_area.y + 1 + header_rows,
+            width: block_area.width.saturating_sub(2),
+            height: block_area.height.saturating_sub(2 + header_rows),
+        };
+        let mut hit = self.hit_areas.get();
+        hit.rows = inner;
+        hit.row_offset = offset;
+        self.hit_areas.set(hit);
     }
 
     pub fn tick_spinner(&mut self) {
         self.spinner_tick = self.spinner_tick.wrapping_add(1);
-        // Auto-refresh tunnels every ~15 s (200 ms tick × 75 = 15 s)
+        // Auto-refresh tunnels every `tunnel_refresh_secs`.
+        let secs = crate::config::settings().tunnel_refresh_secs.max(1);
+        let every = secs * 1000 / TICK_RATE.as_millis() as u64;
         self.tunnel_refresh_ticks = self.tunnel_refresh_ticks.wrapping_add(1);
-        if self.tunnel_refresh_ticks >= 75 {
+        if u64::from(self.tunnel_refresh_ticks) >= every {
             self.tunnel_refresh_ticks = 0;
             self.refresh_tunnels();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{InstanceState, SsmStatus, TunnelInfo, TunnelStatus};
+    use std::cmp::Ordering as Cmp;
+
+    fn instance(id: &str, name: &str) -> Instance {
+        Instance {
+            id: id.into(),
+            name: name.into(),
+            instance_type: "t3.micro".into(),
+            state: InstanceState::Running,
+            private_ip: None,
+            public_ip: None,
+            ipv6_addresses: vec![],
+            ssm_status: SsmStatus::Online,
+            ssm_last_ping: None,
+            ssm_agent_version: None,
+            tunnel: None,
+            security_groups: vec![],
+            security_group_ids: vec![],
+            availability_zone: None,
+            launch_time: None,
+            tags: Default::default(),
+        }
+    }
+
+    fn tunnel(local_port: u16, instance_name: &str, remote_host: Option<&str>) -> TunnelProcess {
+        TunnelProcess {
+            pid: 1,
+            local_port,
+            remote_port: 5432,
+            remote_host: remote_host.map(String::from),
+            instance_id: format!("i-{}", local_port),
+            instance_name: instance_name.into(),
+            port_open: true,
+            latency_ms: None,
+            document: None,
+            parameters: None,
+        }
+    }
+
+    #[test]
+    fn sort_by_name_ignores_case() {
+        let (a, b) = (instance("i-2", "alpha"), instance("i-1", "Beta"));
+        assert_eq!(SortColumn::Name.compare(&a, &b), Cmp::Less);
+        assert_eq!(SortColumn::Id.compare(&a, &b), Cmp::Greater);
+    }
+
+    #[test]
+    fn sort_private_ip_numerically_missing_last() {
+        let mut a = instance("i-1", "a");
+        let mut b = instance("i-2", "b");
+        let c = instance("i-3", "c");
+        a.private_ip = Some("10.0.0.9".into());
+        b.private_ip = Some("10.0.0.10".into());
+        assert_eq!(SortColumn::PrivateIp.compare(&a, &b), Cmp::Less);
+        assert_eq!(SortColumn::PrivateIp.compare(&c, &b), Cmp::Greater);
+    }
+
+    #[test]
+    fn sort_tunnel_first() {
+        let mut a = instance("i-1", "a");
+        let b = instance("i-2", "b");
+        a.tunnel = Some(TunnelInfo { local_port: 9000, remote_port: 80, remote_host: None, status: TunnelStatus::Active });
+        assert_eq!(SortColumn::Tunnel.compare(&a, &b), Cmp::Less);
+    }
+
+    #[test]
+    fn sort_uptime_stopped_last() {
+        let mut old = instance("i-3", "old");
+        let mut new = instance("i-4", "new");
+        let mut stopped = instance("i-5", "stopped");
+        old.launch_time = Some("2020-01-01T00:00:00Z".into());
+        new.launch_time = Some("2024-01-01T00:00:00Z".into());
+        stopped.state = InstanceState::Stopped;
+        stopped.launch_time = new.launch_time.clone();
+        assert_eq!(SortColumn::Uptime.compare(&new, &old), Cmp::Less);
+        assert_eq!(SortColumn::Uptime.compare(&stopped, &old), Cmp::Greater);
+    }
+
+    #[test]
+    fn filtered_tunnels_matches_every_field() {
+        let mut app = App::empty();
+        app.tunnels = vec![tunnel(8080, "Web-1", None), tunnel(15432, "bastion", Some("db.internal"))];
+        let ports = |app: &App| app.filtered_tunnels().iter().map(|t| t.local_port).collect::<Vec<_>>();
+
+        assert_eq!(ports(&app), vec![8080, 15432]);
+        app.tunnel_filter = "web".into();
+        assert_eq!(ports(&app), vec![8080]);
+        app.tunnel_filter = "DB.INT".into();
+        assert_eq!(ports(&app), vec![15432]);
+        app.tunnel_filter = "i-8080".into();
+        assert_eq!(ports(&app), vec![8080]);
+        app.tunnel_filter = "5432".into();
+        assert_eq!(ports(&app), vec![8080, 15432]);
+        app.tunnel_filter = "nothing".into();
+        assert!(ports(&app).is_empty());
+    }
+
+    #[test]
+    fn log_event_folds_lines_and_drops_oldest() {
+        let mut app = App::empty();
+        app.log_event("  \n ", false);
+        assert!(app.event_log.is_empty());
+
+        app.log_event("SSM session exited:\n  i-1 is not connected \n\n", true);
+        let entry = app.event_log.back().unwrap();
+        assert_eq!(entry.text, "SSM session exited: | i-1 is not connected");
+        assert!(entry.is_error);
+        assert_eq!(entry.time.len(), "HH:MM:SS".len());
+
+        for i in 0..EVENT_LOG_LEN {
+            app.log_event(format!("event {}", i), false);
+        }
+        assert_eq!(app.event_log.len(), EVENT_LOG_LEN);
+        assert_eq!(app.event_log.front().unwrap().text, "event 0");
+        assert_eq!(app.event_log.back().unwrap().text, format!("event {}", EVENT_LOG_LEN - 1));
+    }
+}
@@ -1,3 +1,4 @@
 pub mod app;
 pub mod pages;
+pub mod theme;
 pub mod ui;
@@ -1,17 +1,27 @@
 //! Instances tab: table + key handlers.
 
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::Modifier,
     text::{Line, Span},
-    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table, TableState},
+    widgets::{
+        Block, BorderType, Borders, Cell, Clear, Paragraph, Row, Scrollbar, ScrollbarOrientation,
+        ScrollbarState, Table, TableState,
+    },
     Frame,
 };
 
-use crate::models::{InstanceState, SsmStatus, TunnelStatus};
-use crate::tui::app::{App, BgMessage, ConfirmTag, Popup};
-use crate::tui::ui::{C_BORDER, C_DIM, C_DANGER, C_GOLD, C_OK, C_TEXT};
+use crate::error::Result as AppResult;
+use crate::models::{has_regional_pricing, instance_hourly_cost, Instance, InstanceState, SsmStatus, TunnelStatus, VolumeInfo, PRICING_REGION};
+use crate::tui::app::{App, BgMessage, ConfirmTag, InputTag, Popup, SortColumn, WizardBuf};
+use crate::tui::theme::Theme;
+use crate::tui::ui::centered_rect;
+
+/// Last entry of the `i` picker; opens a free-text prompt.
+const OTHER_TYPE_ITEM: &str = "Other...";
+/// How long `i` waits for a running instance to stop before giving up.
+const SWITCH_STOP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(600);
 
 // ── Render ────────────────────────────────────────────────────────────────────
 
@@ -28,9 +38,9 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
 
     if let Some(fa) = filter_area {
         let bar = Paragraph::new(Line::from(vec![
-            Span::styled(" Filter: ", Style::default().fg(C_GOLD)),
-            Span::styled(&app.instance_filter, Style::default().fg(C_TEXT)),
-            Span::styled("█", Style::default().fg(C_BORDER)),
+            Span::styled(" Filter: ", app.theme.accent),
+            Span::styled(&app.instance_filter, app.theme.text),
+            Span::styled("█", app.theme.border),
         ]));
         f.render_widget(bar, fa);
     }
@@ -39,41 +49,56 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn render_table(f: &mut Frame, app: &App, area: Rect) {
-    let header = Row::new(vec![
-        Cell::from("Instance ID").style(Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD)),
-        Cell::from("Name").style(Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD)),
-        Cell::from("Type").style(Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD)),
-        Cell::from("State").style(Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD)),
-        Cell::from("SSM").style(Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD)),
-        Cell::from("Tunnel").style(Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD)),
-        Cell::from("Private IP").style(Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD)),
-    ]).height(1);
+    let tag_column = crate::config::settings().tag_column.as_deref();
+    let mut header_cells: Vec<Cell> = SortColumn::ALL.iter().map(|&col| {
+        let arrow = match app.sort_column {
+            Some(c) if c == col => if app.sort_desc { " ▼" } else { " ▲" },
+            _ => "",
+        };
+        Cell::from(format!("{}{}", col.label(), arrow))
+            .style(app.theme.accent.add_modifier(Modifier::BOLD))
+    }).collect();
+    // Appended, so keys `1`–`8` still match the first eight columns.
+    if let Some(key) = tag_column {
+        header_cells.push(Cell::from(key.to_string()).style(app.theme.accent.add_modifier(Modifier::BOLD)));
+    }
+    let header = Row::new(header_cells).height(1);
 
     let filtered = app.filtered_instances();
+    let total = filtered.len();
 
     let rows: Vec<Row> = filtered.iter().map(|inst| {
         let state_style = match inst.state {
-            InstanceState::Running  => Style::default().fg(C_OK),
-            InstanceState::Stopped  => Style::default().fg(C_DANGER),
-            _                       => Style::default().fg(Color::Yellow),
+            InstanceState::Running  => app.theme.ok,
+            InstanceState::Stopped  => app.theme.danger,
+            _                       => app.theme.accent,
         };
 
+        let age = crate::aws::ssm_ping_age(inst)
+            .map(|a| format!(" {}", crate::aws::short_age(a)))
+            .unwrap_or_default();
         let ssm_cell = match inst.ssm_status {
-            SsmStatus::Online  => Cell::from("● Online").style(Style::default().fg(C_OK)),
-            SsmStatus::Offline => Cell::from("◌ Offline").style(Style::default().fg(C_DANGER)),
-            SsmStatus::Unknown => Cell::from("-").style(Style::default().fg(C_DIM)),
+            SsmStatus::Online if crate::aws::ssm_ping_stale(inst) => {
+                Cell::from(format!("! Online{}", age)).style(app.theme.accent)
+            }
+            SsmStatus::Online  => Cell::from(format!("● Online{}", age)).style(app.theme.ok),
+            SsmStatus::Offline => Cell::from(format!("◌ Offline{}", age)).style(app.theme.danger),
+            SsmStatus::Unknown => Cell::from("-").style(app.theme.dim),
         };
 
         let tunnel_cell = match &inst.tunnel {
             Some(t) => {
-                let label = format!("{}:{}", t.local_port, t.remote_host.as_deref().unwrap_or("?"));
-                let color = if t.status == TunnelStatus::Active { C_OK } else { C_DANGER };
-                Cell::from(label).style(Style::default().fg(color))
+                let label = match &t.remote_host {
+                    Some(h) => format!("{}→{}:{}", t.local_port, h, t.remote_port),
+                    None => format!("{}→{}", t.local_port, t.remote_port),
+                };
+                let style = if t.status == TunnelStatus::Active { app.theme.ok } else { app.theme.danger };
+                Cell::from(label).style(style)
             }
-            None => Cell::from("-").style(Style::default().fg(C_DIM)),
+            None => Cell::from("-").style(app.theme.dim),
         };
 
-        Row::new(vec![
+        let mut cells = vec![
             Cell::from(inst.id.clone()),
             Cell::from(inst.name.clone()),
             Cell::from(inst.instance_type.clone()),
@@ -81,18 +106,36 @@ fn render_table(f: &mut Frame, app: &App, area: Rect) {
             ssm_cell,
             tunnel_cell,
             Cell::from(inst.private_ip.clone().unwrap_or_else(|| "-".into())),
-        ]).height(1)
+            match crate::aws::uptime_secs(inst) {
+                Some(up) if crate::aws::long_running(inst) => {
+                    Cell::from(format!("! {}", crate::aws::short_age(up))).style(app.theme.danger)
+                }
+                Some(up) => Cell::from(crate::aws::short_age(up)),
+                None => Cell::from("-").style(app.theme.dim),
+            },
+        ];
+        if let Some(key) = tag_column {
+            cells.push(match inst.tags.get(key) {
+                Some(v) => Cell::from(v.clone()),
+                None => Cell::from("-").style(app.theme.dim),
+            });
+        }
+        Row::new(cells).height(1)
     }).collect();
 
-    let widths = [
+    let mut widths = vec![
         Constraint::Length(20),
         Constraint::Percentage(25),
         Constraint::Length(12),
         Constraint::Length(10),
-        Constraint::Length(12),
+        Constraint::Length(15),
         Constraint::Length(20),
         Constraint::Length(16),
+        Constraint::Length(8),
     ];
+    if tag_column.is_some() {
+        widths.push(Constraint::Length(14));
+    }
 
     let table = Table::new(rows, widths)
         .header(header)
@@ -100,21 +143,230 @@ fn render_table(f: &mut Frame, app: &App, area: Rect) {
             Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(C_BORDER))
-                .title(" Instances ")
-                .title_style(Style::default().fg(C_BORDER).add_modifier(Modifier::BOLD)),
+                .border_style(app.theme.border)
+                .title(if app.aws_filters.is_empty() {
+                    " Instances ".to_string()
+                } else {
+                    format!(" Instances [aws: {}] ", app.aws_filters.join(" "))
+                })
+                .title_style(app.theme.border.add_modifier(Modifier::BOLD)),
         )
         .row_highlight_style(
-            Style::default().bg(Color::DarkGray).fg(Color::White).add_modifier(Modifier::BOLD),
+            app.theme.selected_row,
         );
 
-    let mut state = TableState::default();
-    state.select(Some(app.instance_selected));
+    // Borders and header take three rows.
+    let visible = area.height.saturating_sub(3) as usize;
+    // Highlight the same row `selected_instance()` acts on.
+    let selected = app.instance_selected.min(total.saturating_sub(1));
+    let offset = scroll_offset(app.instance_offset.get(), selected, total, visible);
+    app.instance_offset.set(offset);
+
+    let mut state = TableState::default()
+        .with_offset(offset)
+        .with_selected((total > 0).then_some(selected));
     f.render_stateful_widget(table, area, &mut state);
+    app.set_row_area(area, 1, state.offset());
+
+    if total > visible {
+        // One position per possible offset, so the thumb spans exactly the
+        // visible slice of the fleet.
+        let mut scroll = ScrollbarState::new(total - visible + 1)
+            .position(offset)
+            .viewport_content_length(visible);
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .thumb_style(app.theme.border)
+            .track_style(app.theme.dim);
+        // Right border, from the first data row to just above the bottom border.
+        let track = Rect { y: area.y + 2, height: area.height.saturating_sub(3), ..area };
+        f.render_stateful_widget(scrollbar, track, &mut scroll);
+    }
+}
+
+/// First visible row: the previous offset, moved just enough to keep
+/// `selected` in view, and never past the last full page.
+fn scroll_offset(prev: usize, selected: usize, len: usize, visible: usize) -> usize {
+    if visible == 0 { return 0; }
+    let offset = if selected < prev {
+        selected
+    } else if selected >= prev + visible {
+        selected + 1 - visible
+    } else {
+        prev
+    };
+    offset.min(len.saturating_sub(visible))
+}
+
+// ── Detail popup ──────────────────────────────────────────────────────────────
+
+/// Sections of the detail popup as `(heading, [(label, value)])`. `volumes`
+/// is None while they are still being fetched.
+fn detail_sections(
+    inst: &Instance,
+    region: &str,
+    volumes: Option<&AppResult<Vec<VolumeInfo>>>,
+) -> Vec<(&'static str, Vec<(String, String)>)> {
+    let dash = || "-".to_string();
+    let launched = match inst.launch_time.as_deref() {
+        Some(t) => match crate::aws::parse_iso8601_to_unix(t) {
+            Some(ts) if crate::aws::long_running(inst) => {
+                format!("{} ({}, long-running)", t, crate::aws::relative_time(ts))
+            }
+            Some(ts) => format!("{} ({})", t, crate::aws::relative_time(ts)),
+            None => t.to_string(),
+        },
+        None => dash(),
+    };
+
+    let overview = vec![
+        ("ID".to_string(), inst.id.clone()),
+        ("Name".to_string(), if inst.name.is_empty() { dash() } else { inst.name.clone() }),
+        ("Type".to_string(), inst.instance_type.clone()),
+        ("State".to_string(), inst.state.as_str().to_string()),
+        ("Cost".to_string(), match instance_hourly_cost(&inst.instance_type, region) {
+            Some(p) if has_regional_pricing(region) => format!("${:.4}/h on-demand (~${:.0}/month)", p, p * 730.0),
+            Some(p) => format!("~${:.4}/h on-demand (~${:.0}/month, {} price, approximate)", p, p * 730.0, PRICING_REGION),
+            None => "unknown".to_string(),
+        }),
+        ("AZ".to_string(), inst.availability_zone.clone().unwrap_or_else(dash)),
+        ("Launched".to_string(), launched),
+        ("SSM".to_string(), match inst.ssm_status {
+            SsmStatus::Unknown => "Not registered".to_string(),
+            _ => crate::aws::ssm_health(inst, true),
+        }),
+    ];
+
+    let mut network = vec![
+        ("Private IP".to_string(), inst.private_ip.clone().unwrap_or_else(dash)),
+        ("Public IP".to_string(), inst.public_ip.clone().unwrap_or_else(dash)),
+    ];
+    if !inst.ipv6_addresses.is_empty() {
+        network.push(("IPv6".to_string(), inst.ipv6_addresses.join(", ")));
+    }
+    if let Some(t) = &inst.tunnel {
+        network.push(("Tunnel".to_string(), format!(
+            "localhost:{} -> {}:{} ({})",
+            t.local_port,
+            t.remote_host.as_deref().unwrap_or("instance"),
+            t.remote_port,
+            if t.status == TunnelStatus::Active { "active" } else { "down" },
+        )));
+    }
+
+    let volumes = match volumes {
+        None => vec![("Loading...".to_string(), String::new())],
+        Some(Err(e)) => vec![("Error".to_string(), e.to_string())],
+        Some(Ok(vols)) => vols.iter().map(|v| (v.device.clone(), format!(
+            "{}  {} GiB {}{}  {}",
+            v.volume_id,
+            v.size_gib,
+            v.volume_type,
+            v.iops.map(|i| format!(" {} IOPS", i)).unwrap_or_default(),
+            v.state,
+        ))).collect(),
+    };
+
+    let groups = inst
+        .security_groups
+        .iter()
+        .zip(&inst.security_group_ids)
+        .map(|(name, id)| (id.clone(), name.clone()))
+        .collect();
+
+    let tags = inst.tags.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+
+    vec![
+        ("Overview", overview),
+        ("Network", network),
+        ("Volumes", volumes),
+        ("Security groups", groups),
+        ("Tags", tags),
+    ]
+}
+
+/// Plain-text version of the detail popup, for the clipboard.
+pub fn detail_text(inst: &Instance, region: &str, volumes: Option<&AppResult<Vec<VolumeInfo>>>) -> String {
+    let mut out = String::new();
+    for (heading, rows) in detail_sections(inst, region, volumes) {
+        out.push_str(heading);
+        out.push('\n');
+        for (label, value) in rows {
+            out.push_str(&format!("  {:<16} {}\n", label, value));
+        }
+    }
+    out
+}
+
+/// Detail popup; returns the largest scroll offset that still shows content.
+pub fn render_detail(
+    f: &mut Frame,
+    area: Rect,
+    theme: &Theme,
+    inst: &Instance,
+    region: &str,
+    volumes: Option<&AppResult<Vec<VolumeInfo>>>,
+    scroll: u16,
+) -> u16 {
+    let mut lines = Vec::new();
+    for (heading, rows) in detail_sections(inst, region, volumes) {
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(
+            format!(" {} ", heading),
+            theme.accent.add_modifier(Modifier::BOLD),
+        )));
+        if rows.is_empty() {
+            lines.push(Line::from(Span::styled("   (none)", theme.dim)));
+        }
+        let width = rows.iter().map(|(l, _)| l.len()).max().unwrap_or(0).max(12);
+        for (label, value) in rows {
+            lines.push(Line::from(vec![
+                Span::styled(format!("   {:<width$}  ", label), theme.dim),
+                Span::styled(value, theme.text),
+            ]));
+        }
+    }
+
+    let popup_area = centered_rect(70, (lines.len() as u16 + 2).min(area.height.saturating_sub(2)), area);
+    f.render_widget(Clear, popup_area);
+
+    let visible = popup_area.height.saturating_sub(2);
+    let max_scroll = (lines.len() as u16).saturating_sub(visible);
+    let scroll = scroll.min(max_scroll);
+
+    let title = if inst.name.is_empty() { inst.id.clone() } else { format!("{} ({})", inst.name, inst.id) };
+    let mut block = Block::default()
+        .title(format!(" {} ", title))
+        .title_style(theme.accent.add_modifier(Modifier::BOLD))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.border)
+        .title_bottom(Line::from(Span::styled(
+            " [Esc] Close  [c] Copy  [t] Tunnels ",
+            theme.dim,
+        )).right_aligned());
+    if max_scroll > 0 {
+        block = block.title_bottom(Line::from(Span::styled(
+            format!(" [j/k] Scroll {}/{} ", scroll, max_scroll),
+            theme.dim,
+        )));
+    }
+
+    let p = Paragraph::new(lines).scroll((scroll, 0)).block(block);
+    f.render_widget(p, popup_area);
+    max_scroll
 }
 
 // ── Key handling ──────────────────────────────────────────────────────────────
 
+/// Mouse click on a table row.
+pub fn select_row(app: &mut App, idx: usize) {
+    if idx < app.filtered_instances().len() { app.instance_selected = idx; }
+}
+
 pub fn handle_key(app: &mut App, key: KeyEvent) {
     if app.instance_filter_active {
         match key.code {
@@ -124,32 +376,116 @@ pub fn handle_key(app: &mut App, key: KeyEvent) {
             KeyCode::Char(c) => { app.instance_filter.push(c); app.instance_selected = 0; }
             _ => {}
         }
+        app.clamp_instance_selected();
         return;
     }
 
     let count = app.filtered_instances().len();
+    // Rows the table showed on the last frame.
+    let page = (app.hit_areas.get().rows.height as usize).max(1);
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
     match key.code {
-        KeyCode::Up   | KeyCode::Char('k') => { if app.instance_selected > 0 { app.instance_selected -= 1; } }
-        KeyCode::Down | KeyCode::Char('j') => { if app.instance_selected + 1 < count { app.instance_selected += 1; } }
+        KeyCode::Up   | KeyCode::Char('k') if app.instance_selected > 0 => { app.instance_selected -= 1; }
+        KeyCode::Down | KeyCode::Char('j') if app.instance_selected + 1 < count => { app.instance_selected += 1; }
+        KeyCode::PageUp => { app.instance_selected = app.instance_selected.saturating_sub(page); }
+        KeyCode::PageDown => {
+            app.instance_selected = (app.instance_selected + page).min(count.saturating_sub(1));
+        }
+        KeyCode::Char('u') if ctrl => { app.instance_selected = app.instance_selected.saturating_sub(page); }
+        KeyCode::Char('d') if ctrl => {
+            app.instance_selected = (app.instance_selected + page).min(count.saturating_sub(1));
+        }
         KeyCode::Char('g') => { app.instance_selected = 0; }
         KeyCode::Char('G') => { app.instance_selected = count.saturating_sub(1); }
-        KeyCode::Char('r') => { app.refresh_instances(); }
+        KeyCode::Char('r') => { crate::aws::invalidate_cache(); app.refresh_instances(); }
+        KeyCode::Enter => {
+            if let Some(inst) = app.selected_instance().cloned() {
+                let tx = app.tx.clone();
+                let id = inst.id.clone();
+                std::thread::spawn(move || {
+                    let result = crate::aws::get_instance_volumes(&id, None);
+                    let _ = tx.send(BgMessage::VolumesLoaded(id, result));
+                });
+                app.detail_volumes = None;
+                app.popup = Popup::InstanceDetail(Box::new(inst));
+                app.result_scroll = 0;
+            }
+        }
         KeyCode::Char('/') => { app.instance_filter_active = true; app.instance_filter.clear(); }
-        KeyCode::Esc => { if !app.instance_filter.is_empty() { app.instance_filter.clear(); } }
+        KeyCode::Esc if !app.instance_filter.is_empty() => { app.instance_filter.clear(); }
+        KeyCode::Char('a') => {
+            app.show_all_instances = !app.show_all_instances;
+            app.instance_selected = 0;
+            app.status_msg = Some(if app.show_all_instances {
+                "Showing all instances".into()
+            } else {
+                "Showing instances matching instance_name_include".into()
+            });
+        }
+        KeyCode::Char('x') => action_shell(app),
+        KeyCode::Char('y') => {
+            if let Some(id) = app.selected_instance().map(|i| i.id.clone()) {
+                app.yank("instance ID", &id);
+            }
+        }
         KeyCode::Char('s') => action_start(app),
         KeyCode::Char('S') => action_stop(app, false),
         KeyCode::Char('f') => action_stop(app, true),
+        KeyCode::Char('n') => action_launch(app),
+        KeyCode::Char('R') => action_reboot(app),
+        KeyCode::Char('D') => action_terminate(app),
+        KeyCode::Char('i') => action_switch_type(app),
+        KeyCode::Char('c') => action_console(app),
+        KeyCode::Char('T') => {
+            if let Some(inst) = app.selected_instance().cloned() {
+                app.show_instance_tunnels(&inst.id, &inst.name);
+            }
+        }
+        KeyCode::Char('t') => action_tag(app),
+        KeyCode::Char(c @ '1'..='8') => {
+            // Same column again flips the direction.
+            let col = SortColumn::ALL[(c as u8 - b'1') as usize];
+            let desc = app.sort_column == Some(col) && !app.sort_desc;
+            app.set_sort(Some(col), desc);
+        }
+        KeyCode::Char('o') => {
+            // Cycle columns ascending, then back to API order.
+            let next = match app.sort_column {
+                None => Some(SortColumn::ALL[0]),
+                Some(c) => SortColumn::ALL
+                    .iter()
+                    .position(|&x| x == c)
+                    .and_then(|i| SortColumn::ALL.get(i + 1).copied()),
+            };
+            app.set_sort(next, false);
+        }
+        KeyCode::Char('F') => {
+            app.popup = Popup::Input {
+                title: "AWS Filters (space-separated, empty to clear)".into(),
+                placeholder: "e.g. tag:Environment=prod instance-state-name=running".into(),
+                value: app.aws_filters.join(" "),
+                tag: InputTag::InstanceAwsFilters,
+            };
+        }
         _ => {}
     }
 }
 
+fn action_shell(app: &mut App) {
+    let Some(inst) = app.selected_instance() else { return };
+    if inst.ssm_status != SsmStatus::Online {
+        app.notify(format!("SSM agent on {} is not online", inst.name));
+        return;
+    }
+    app.pending_shell = Some((inst.id.clone(), inst.name.clone()));
+}
+
 fn action_start(app: &mut App) {
     if let Some(inst) = app.selected_instance().cloned() {
         let tx = app.tx.clone();
         let id = inst.id.clone();
         let name = inst.name.clone();
-        app.loading = true;
-        app.loading_message = format!("Starting {}...", name);
+        app.start_task(format!("Starting {}...", name));
         std::thread::spawn(move || {
             let result = crate::aws::start_instance(&id, None).map(|_| format!("Started {}", name));
             let _ = tx.send(BgMessage::ActionDone(result));
@@ -164,31 +500,251 @@ fn action_stop(app: &mut App, force: bool) {
         } else {
             format!("Stop '{}'?", inst.name)
         };
+        let target = (inst.id.clone(), inst.name.clone());
         app.popup = Popup::Confirm {
             message: msg,
-            tag: if force { ConfirmTag::ForceStopInstance } else { ConfirmTag::StopInstance },
+            tag: if force {
+                ConfirmTag::ForceStopInstance(target.0, target.1)
+            } else {
+                ConfirmTag::StopInstance(target.0, target.1)
+            },
             selected_yes: false,
         };
     }
 }
 
+fn action_reboot(app: &mut App) {
+    if let Some(inst) = app.selected_instance().cloned() {
+        app.popup = Popup::Confirm {
+            message: format!("Reboot '{}' ({})?", inst.name, inst.id),
+            tag: ConfirmTag::RebootInstance(inst.id.clone(), inst.name.clone()),
+            selected_yes: false,
+        };
+    }
+}
+
+/// Terminate is irreversible, so instead of Yes/No the user must type the
+/// instance name (or ID for untagged instances).
+fn action_terminate(app: &mut App) {
+    if let Some(inst) = app.selected_instance().cloned() {
+        app.popup = Popup::Input {
+            title: format!("TERMINATE {} ({}) — type its name to confirm", inst.name, inst.id),
+            placeholder: confirm_token(&inst).to_string(),
+            value: String::new(),
+            tag: InputTag::TerminateInstanceName(inst.id.clone(), inst.name.clone()),
+        };
+    }
+}
+
+fn confirm_token(inst: &crate::models::Instance) -> &str {
+    if inst.name.is_empty() { &inst.id } else { &inst.name }
+}
+
+/// Picker of recently used types plus the gpu/cpu aliases. Alias items read
+/// "gpu  (g4dn.4xlarge)"; only the first word is used.
+fn action_switch_type(app: &mut App) {
+    if let Some(inst) = app.selected_instance().cloned() {
+        let settings = crate::config::settings();
+        let mut items: Vec<String> = crate::config::recent_instance_types()
+            .into_iter()
+            .filter(|t| *t != inst.instance_type)
+            .collect();
+        items.push(format!("gpu  ({})", settings.gpu_instance_type));
+        items.push(format!("cpu  ({})", settings.cpu_instance_type));
+        items.push(OTHER_TYPE_ITEM.to_string());
+        app.popup = Popup::Select {
+            title: format!("Instance type for {} (now {})", inst.name, inst.instance_type),
+            items,
+            selected: 0,
+            tag: InputTag::SwitchInstanceType(inst.id, inst.name),
+        };
+    }
+}
+
+/// Stop the instance if needed, then change its type. It is left stopped.
+fn switch_type(app: &mut App, id: String, name: String, new_type: String) {
+    let tx = app.tx.clone();
+    app.start_task(format!("Switching {} to {}...", name, new_type));
+    std::thread::spawn(move || {
+        let result = (|| {
+            match crate::aws::get_instance_state(&id, None)? {
+                InstanceState::Stopped => {}
+                InstanceState::Stopping => {
+                    crate::aws::wait_for_state(&id, InstanceState::Stopped, SWITCH_STOP_TIMEOUT, None)?;
+                }
+                state @ (InstanceState::Running | InstanceState::Pending) => {
+                    // EC2 rejects StopInstances while the instance is still pending.
+                    if state == InstanceState::Pending {
+                        crate::aws::wait_for_state(&id, InstanceState::Running, SWITCH_STOP_TIMEOUT, None)?;
+                    }
+                    crate::aws::stop_instance(&id, false, None)?;
+                    crate::aws::wait_for_state(&id, InstanceState::Stopped, SWITCH_STOP_TIMEOUT, None)?;
+                }
+                InstanceState::Other(s) => {
+                    return Err(crate::error::AppError::Other(format!(
+                        "{} is {}; the instance type can only be changed while stopped.", name, s,
+                    )));
+                }
+            }
+            crate::aws::modify_instance_type(&id, &new_type, None)?;
+            Ok(format!("{} is now {} (stopped)", name, new_type))
+        })();
+        let _ = tx.send(BgMessage::ActionDone(result));
+    });
+}
+
+fn action_console(app: &mut App) {
+    if let Some(inst) = app.selected_instance().cloned() {
+        let tx = app.loading_sender();
+        app.show_loading(format!("Fetching console output of {}...", inst.name));
+        std::thread::spawn(move || {
+            let result = crate::aws::get_console_output(&inst.id, None);
+            let _ = tx.send(BgMessage::ConsoleOutput(inst.name, result));
+        });
+    }
+}
+
+/// Console logs carry `\r` and ANSI color codes that would corrupt the
+/// terminal when drawn; keep plain text only.
+pub fn printable(text: &str) -> String {
+    static ANSI: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let ansi = ANSI.get_or_init(|| regex::Regex::new(r"\x1b\[[0-9;?]*[ -/]*[@-~]").unwrap());
+    ansi.replace_all(text, "")
+        .replace('\t', "    ")
+        .chars()
+        .filter(|c| *c == '\n' || !c.is_control())
+        .collect()
+}
+
+fn action_tag(app: &mut App) {
+    if let Some(inst) = app.selected_instance().cloned() {
+        app.popup = Popup::Input {
+            title: format!("Tag {} (Key=Value)", inst.name),
+            placeholder: "e.g. Name=my-server".into(),
+            value: String::new(),
+            tag: InputTag::TagInstance(inst.id, inst.name),
+        };
+    }
+}
+
+fn action_launch(app: &mut App) {
+    app.wizard_buf = WizardBuf::default();
+    app.popup = Popup::Input {
+        title: "Launch — Launch Template (ID or name)".into(),
+        placeholder: "e.g. lt-0123456789abcdef0".into(),
+        value: crate::config::settings().launch_template.clone().unwrap_or_default(),
+        tag: InputTag::LaunchTemplate,
+    };
+}
+
+pub fn handle_input(app: &mut App, tag: InputTag, value: String) {
+    match tag {
+        InputTag::LaunchTemplate => {
+            if value.trim().is_empty() { return; }
+            app.wizard_buf.pattern = value.trim().to_string();
+            app.popup = Popup::Input {
+                title: "Launch — Name Tag".into(),
+                placeholder: "e.g. test-box-1".into(),
+                value: String::new(),
+                tag: InputTag::LaunchName,
+            };
+        }
+        InputTag::LaunchName => {
+            let name = value.trim().to_string();
+            if name.is_empty() { return; }
+            let template = app.wizard_buf.pattern.clone();
+            let tx = app.tx.clone();
+            app.start_task(format!("Launching {}...", name));
+            std::thread::spawn(move || {
+                let result = crate::aws::launch_from_template(&template, &name, None, None)
+                    .map(|id| format!("Launched {} ({})", name, id));
+                let _ = tx.send(BgMessage::ActionDone(result));
+            });
+        }
+        InputTag::InstanceAwsFilters => {
+            let filters: Vec<String> = value.split_whitespace().map(String::from).collect();
+            if let Err(e) = filters.iter().try_for_each(|f| crate::aws::parse_instance_filter(f).map(drop)) {
+                app.popup = Popup::Result { title: "Filter Error".into(), body: e.to_string(), is_error: true };
+                return;
+            }
+            app.aws_filters = filters;
+            app.instance_selected = 0;
+            app.refresh_instances();
+        }
+        InputTag::SwitchInstanceType(id, name) => {
+            let value = value.trim();
+            if value.is_empty() { return; }
+            if value == OTHER_TYPE_ITEM {
+                app.popup = Popup::Input {
+                    title: format!("Instance type for {}", name),
+                    placeholder: "e.g. c6i.xlarge".into(),
+                    value: String::new(),
+                    tag: InputTag::SwitchInstanceType(id, name),
+                };
+                return;
+            }
+            let target = value.split_whitespace().next().unwrap_or_default();
+            match crate::aws::resolve_instance_type(target) {
+                Ok(new_type) => switch_type(app, id, name, new_type),
+                Err(e) => {
+                    app.popup = Popup::Result { title: "Switch Error".into(), body: e.to_string(), is_error: true };
+                }
+            }
+        }
+        InputTag::TagInstance(id, name) => {
+            if value.trim().is_empty() { return; }
+            let tag = match crate::aws::parse_tag(value.trim()) {
+                Ok(tag) => tag,
+                Err(e) => {
+                    app.popup = Popup::Result { title: "Tag Error".into(), body: e.to_string(), is_error: true };
+                    return;
+                }
+            };
+            let tx = app.tx.clone();
+            app.start_task(format!("Tagging {}...", name));
+            std::thread::spawn(move || {
+                let result = crate::aws::create_tags(&id, std::slice::from_ref(&tag), None)
+                    .map(|_| format!("Tagged {} ({}): {}={}", name, id, tag.0, tag.1));
+                let _ = tx.send(BgMessage::ActionDone(result));
+            });
+        }
+        InputTag::TerminateInstanceName(id, name) => {
+            let token = if name.is_empty() { &id } else { &name };
+            if value.trim() == token {
+                handle_confirm(app, ConfirmTag::TerminateInstance(id, name), true);
+            } else {
+                app.notify("Name did not match — terminate cancelled");
+            }
+        }
+        _ => {}
+    }
+}
+
 pub fn handle_confirm(app: &mut App, tag: ConfirmTag, confirmed: bool) {
     if !confirmed { return; }
+    let force = matches!(tag, ConfirmTag::ForceStopInstance(..));
+    let terminate = matches!(tag, ConfirmTag::TerminateInstance(..));
     match tag {
-        ConfirmTag::StopInstance | ConfirmTag::ForceStopInstance => {
-            let force = matches!(tag, ConfirmTag::ForceStopInstance);
-            if let Some(inst) = app.selected_instance().cloned() {
-                let tx = app.tx.clone();
-                let id = inst.id.clone();
-                let name = inst.name.clone();
-                app.loading = true;
-                app.loading_message = if force { "Force-stopping...".into() } else { "Stopping...".into() };
-                std::thread::spawn(move || {
-                    let result = crate::aws::stop_instance(&id, force, None)
-                        .map(|_| format!("{} {}", if force { "Force-stopped" } else { "Stopped" }, name));
-                    let _ = tx.send(BgMessage::ActionDone(result));
-                });
-            }
+        ConfirmTag::StopInstance(id, name) | ConfirmTag::ForceStopInstance(id, name) => {
+            let tx = app.tx.clone();
+            app.start_task(format!("{} {}...", if force { "Force-stopping" } else { "Stopping" }, name));
+            std::thread::spawn(move || {
+                let result = crate::aws::stop_instance(&id, force, None)
+                    .map(|_| format!("{} {}", if force { "Force-stopped" } else { "Stopped" }, name));
+                let _ = tx.send(BgMessage::ActionDone(result));
+            });
+        }
+        ConfirmTag::RebootInstance(id, name) | ConfirmTag::TerminateInstance(id, name) => {
+            let tx = app.tx.clone();
+            app.start_task(format!("{} {}...", if terminate { "Terminating" } else { "Rebooting" }, name));
+            std::thread::spawn(move || {
+                let result = if terminate {
+                    crate::aws::terminate_instance(&id, None).map(|_| format!("Terminated {}", name))
+                } else {
+                    crate::aws::reboot_instance(&id, None).map(|_| format!("Rebooted {}", name))
+                };
+                let _ = tx.send(BgMessage::ActionDone(result));
+            });
         }
         _ => {}
     }
@@ -2,33 +2,70 @@
 
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
-    layout::Constraint,
-    style::{Color, Modifier, Style},
-    widgets::{Block, BorderType, Borders, Cell, Row, Table, TableState},
+    layout::{Constraint, Direction, Flex, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Sparkline, Table, TableState},
     Frame,
 };
 use ratatui::layout::Rect;
 
-use crate::error::Result as AppResult;
+use crate::error::{AppError, Result as AppResult};
 use crate::models::TunnelProcess;
-use crate::tui::app::{App, BgMessage, ConfirmTag, InputTag, Popup, WizardBuf};
-use crate::tui::ui::{C_BORDER, C_DANGER, C_GOLD, C_OK};
+use crate::tui::app::{App, BgMessage, ConfirmTag, InputTag, LoadingSender, Popup, WizardBuf};
 
 pub fn render(f: &mut Frame, app: &App, area: Rect) {
+    let (filter_area, table_area) = if app.tunnel_filter_active || !app.tunnel_filter.is_empty() {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(1)])
+            .split(area);
+        (Some(chunks[0]), chunks[1])
+    } else {
+        (None, area)
+    };
+
+    if let Some(fa) = filter_area {
+        let bar = Paragraph::new(Line::from(vec![
+            Span::styled(" Filter: ", app.theme.accent),
+            Span::styled(&app.tunnel_filter, app.theme.text),
+            Span::styled("█", app.theme.border),
+        ]));
+        f.render_widget(bar, fa);
+    }
+
+    render_table(f, app, table_area);
+}
+
+/// Green below `latency_warn_ms`, yellow up to `latency_slow_ms`, red above.
+fn style_for_latency(app: &App, ms: u64) -> Style {
+    let settings = crate::config::settings();
+    if ms < settings.latency_warn_ms {
+        app.theme.ok
+    } else if ms <= settings.latency_slow_ms {
+        app.theme.accent
+    } else {
+        app.theme.danger
+    }
+}
+
+fn render_table(f: &mut Frame, app: &App, area: Rect) {
+    let tunnels = app.filtered_tunnels();
     let header = Row::new(vec![
-        Cell::from("#").style(Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD)),
-        Cell::from("Local Port").style(Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD)),
-        Cell::from("Remote").style(Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD)),
-        Cell::from("Instance / Bastion").style(Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD)),
-        Cell::from("Status / Latency").style(Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD)),
-        Cell::from("PID").style(Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD)),
+        Cell::from("#").style(app.theme.accent.add_modifier(Modifier::BOLD)),
+        Cell::from("Local Port").style(app.theme.accent.add_modifier(Modifier::BOLD)),
+        Cell::from("Remote").style(app.theme.accent.add_modifier(Modifier::BOLD)),
+        Cell::from("Instance / Bastion").style(app.theme.accent.add_modifier(Modifier::BOLD)),
+        Cell::from("Status / Latency").style(app.theme.accent.add_modifier(Modifier::BOLD)),
+        Cell::from("Trend").style(app.theme.accent.add_modifier(Modifier::BOLD)),
+        Cell::from("PID").style(app.theme.accent.add_modifier(Modifier::BOLD)),
     ]).height(1);
 
-    let rows: Vec<Row> = app.tunnels.iter().enumerate().map(|(i, t)| {
+    let rows: Vec<Row> = tunnels.iter().enumerate().map(|(i, t)| {
         let status_cell = match (t.port_open, t.latency_ms) {
-            (true, Some(ms)) => Cell::from(format!("● OK  {}ms", ms)).style(Style::default().fg(C_OK)),
-            (true, None)     => Cell::from("▲ OPEN").style(Style::default().fg(crate::tui::ui::C_GOLD)),
-            _                => Cell::from("◌ DOWN").style(Style::default().fg(C_DANGER)),
+            (true, Some(ms)) => Cell::from(format!("● OK  {}ms", ms)).style(style_for_latency(app, ms)),
+            (true, None)     => Cell::from("▲ OPEN").style(app.theme.accent),
+            _                => Cell::from("◌ DOWN").style(app.theme.danger),
         };
         let remote = match &t.remote_host {
             Some(h) => format!("{}:{}", h, t.remote_port),
@@ -40,16 +77,18 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
             Cell::from(remote),
             Cell::from(t.instance_name.clone()),
             status_cell,
-            Cell::from(t.pid.to_string()),
+            Cell::from(""), // sparkline drawn over this cell below
+            Cell::from(if t.pid == 0 { "-".to_string() } else { t.pid.to_string() }),
         ]).height(1)
     }).collect();
 
     let widths = [
         Constraint::Length(4),
         Constraint::Length(16),
-        Constraint::Percentage(28),
-        Constraint::Percentage(30),
+        Constraint::Percentage(24),
+        Constraint::Percentage(26),
         Constraint::Length(14),
+        Constraint::Length(crate::tui::app::LATENCY_HISTORY_LEN as u16),
         Constraint::Length(10),
     ];
 
@@ -59,32 +98,166 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
             Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(C_BORDER))
-                .title(" Tunnels ")
-                .title_style(Style::default().fg(C_BORDER).add_modifier(Modifier::BOLD)),
+                .border_style(app.theme.border)
+                .title(if app.auto_reconnect { " Tunnels [auto-reconnect] " } else { " Tunnels " })
+                .title_style(app.theme.border.add_modifier(Modifier::BOLD)),
         )
         .row_highlight_style(
-            Style::default().bg(Color::DarkGray).fg(Color::White).add_modifier(Modifier::BOLD),
+            app.theme.selected_row,
         );
 
     let mut state = TableState::default();
-    if !app.tunnels.is_empty() { state.select(Some(app.tunnel_selected)); }
+    if !tunnels.is_empty() { state.select(Some(app.tunnel_selected)); }
     f.render_stateful_widget(table, area, &mut state);
+    app.set_row_area(area, 1, state.offset());
+    render_sparklines(f, app, &tunnels, area, &widths, state.offset());
+}
+
+/// Table cells only hold text, so latency sparklines are drawn on top of the
+/// empty "Trend" cells, using the same column layout the table computed.
+fn render_sparklines(
+    f: &mut Frame,
+    app: &App,
+    tunnels: &[&TunnelProcess],
+    area: Rect,
+    widths: &[Constraint],
+    offset: usize,
+) {
+    let inner = Block::default().borders(Borders::ALL).inner(area);
+    let columns = Layout::horizontal(widths.iter().copied())
+        .flex(Flex::Start)
+        .spacing(1)
+        .split(inner);
+    let trend = columns[5];
+    let first_row_y = inner.y + 1; // below the header
+
+    for (i, t) in tunnels.iter().enumerate().skip(offset) {
+        let y = first_row_y + (i - offset) as u16;
+        if y >= inner.bottom() { break; }
+        let Some(history) = app.latency_history.get(&t.pid) else { continue };
+        let data: Vec<Option<u64>> = history.iter().copied().collect();
+        let selected = i == app.tunnel_selected;
+        let sparkline = Sparkline::default()
+            .data(data)
+            .style(if selected { app.theme.selected_row.patch(app.theme.ok) } else { app.theme.ok })
+            .absent_value_symbol("▁")
+            .absent_value_style(app.theme.danger);
+        f.render_widget(sparkline, Rect { y, height: 1, ..trend });
+    }
+}
+
+// ── Detail popup ──────────────────────────────────────────────────────────────
+
+/// Body of the `Enter` popup: where the tunnel goes, and the SSM document and
+/// parameters its session was started with.
+fn detail_text(tp: &TunnelProcess) -> String {
+    let status = match (tp.pid, tp.port_open, tp.latency_ms) {
+        (0, _, _) => "DOWN (session process is gone)".to_string(),
+        (_, true, Some(ms)) => format!("OK, {}ms", ms),
+        (_, true, None) => "OK".to_string(),
+        (_, false, _) => "port closed".to_string(),
+    };
+    let target = if tp.instance_name.is_empty() || tp.instance_name == tp.instance_id {
+        tp.instance_id.clone()
+    } else {
+        format!("{} ({})", tp.instance_name, tp.instance_id)
+    };
+    let mut out = format!(
+        "Local:      localhost:{}\n\
+         Target:     {}\n\
+         Remote:     {}:{}\n\
+         PID:        {}\n\
+         Status:     {}\n\n",
+        tp.local_port,
+        target,
+        tp.remote_host.as_deref().unwrap_or("instance"),
+        tp.remote_port,
+        if tp.pid == 0 { "-".to_string() } else { tp.pid.to_string() },
+        status,
+    );
+    match (&tp.document, &tp.parameters) {
+        (Some(doc), params) => {
+            out.push_str(&format!("Document:   {}\n", doc));
+            if let Some(p) = params {
+                let pretty = serde_json::from_str::<serde_json::Value>(p)
+                    .and_then(|v| serde_json::to_string_pretty(&v))
+                    .unwrap_or_else(|_| p.clone());
+                out.push_str("Parameters:\n");
+                for line in pretty.lines() {
+                    out.push_str(&format!("  {}\n", line));
+                }
+            }
+        }
+        (None, _) if tp.pid == 0 => out.push_str("Document:   unknown (no session process to read it from)\n"),
+        (None, _) => out.push_str("Document:   not read yet, press r to refresh\n"),
+    }
+    out
 }
 
 // ── Key handling ──────────────────────────────────────────────────────────────
 
+/// Mouse click on a table row.
+pub fn select_row(app: &mut App, idx: usize) {
+    if idx < app.filtered_tunnels().len() { app.tunnel_selected = idx; }
+}
+
 pub fn handle_key(app: &mut App, key: KeyEvent) {
-    let count = app.tunnels.len();
+    if app.tunnel_filter_active {
+        match key.code {
+            KeyCode::Esc   => { app.tunnel_filter_active = false; app.tunnel_filter.clear(); }
+            KeyCode::Enter => { app.tunnel_filter_active = false; }
+            KeyCode::Backspace => { app.tunnel_filter.pop(); app.tunnel_selected = 0; }
+            KeyCode::Char(c) => { app.tunnel_filter.push(c); app.tunnel_selected = 0; }
+            _ => {}
+        }
+        return;
+    }
+
+    let count = app.filtered_tunnels().len();
     match key.code {
-        KeyCode::Up   | KeyCode::Char('k') => { if app.tunnel_selected > 0 { app.tunnel_selected -= 1; } }
-        KeyCode::Down | KeyCode::Char('j') => { if app.tunnel_selected + 1 < count { app.tunnel_selected += 1; } }
+        KeyCode::Up   | KeyCode::Char('k') if app.tunnel_selected > 0 => { app.tunnel_selected -= 1; }
+        KeyCode::Down | KeyCode::Char('j') if app.tunnel_selected + 1 < count => { app.tunnel_selected += 1; }
         KeyCode::Char('r') => { app.refresh_tunnels(); }
+        KeyCode::Enter => {
+            if let Some(tp) = app.selected_tunnel() {
+                app.popup = Popup::Result {
+                    title: format!("Tunnel — localhost:{}", tp.local_port),
+                    body: detail_text(tp),
+                    is_error: false,
+                };
+            }
+        }
+        KeyCode::Char('/') => { app.tunnel_filter_active = true; app.tunnel_filter.clear(); }
+        KeyCode::Esc if !app.tunnel_filter.is_empty() => { app.tunnel_filter.clear(); }
         KeyCode::Char('n') => start_wizard_by_instance(app),
         KeyCode::Char('u') => start_wizard_by_url(app),
         KeyCode::Char('b') => start_wizard_by_bastion(app),
         KeyCode::Char('d') | KeyCode::Delete => confirm_stop_tunnel(app),
         KeyCode::Char('A') => confirm_stop_all(app),
+        KeyCode::Char('R') => restart_selected(app),
+        KeyCode::Char('B') => bench_selected(app),
+        KeyCode::Char('y') => {
+            if let Some(port) = app.selected_tunnel().map(|t| t.local_port) {
+                app.yank("tunnel URL", &format!("http://localhost:{}", port));
+            }
+        }
+        KeyCode::Char('o') => open_selected(app),
+        KeyCode::Char('C') => {
+            if let Some(tp) = app.selected_tunnel() {
+                app.popup = Popup::Result {
+                    title: format!("Command — localhost:{}", tp.local_port),
+                    body: crate::tunnel::command_strings(tp),
+                    is_error: false,
+                };
+            }
+        }
+        KeyCode::Char('w') => {
+            app.auto_reconnect = !app.auto_reconnect;
+            app.reconnects.clear();
+            app.status_msg = Some(format!(
+                "Auto-reconnect {}", if app.auto_reconnect { "ON" } else { "OFF" }
+            ));
+        }
         _ => {}
     }
 }
@@ -121,10 +294,9 @@ fn start_wizard_by_bastion(app: &mut App) {
 
 fn confirm_stop_tunnel(app: &mut App) {
     if let Some(t) = app.selected_tunnel() {
-        let idx = app.tunnel_selected;
         app.popup = Popup::Confirm {
             message: format!("Stop tunnel localhost:{} -> {}?", t.local_port, t.instance_name),
-            tag: ConfirmTag::StopTunnel(idx),
+            tag: ConfirmTag::StopTunnel(t.local_port),
             selected_yes: false,
         };
     }
@@ -140,24 +312,76 @@ fn confirm_stop_all(app: &mut App) {
     }
 }
 
+/// Re-establish the selected tunnel if it is down, from its recorded parameters.
+fn restart_selected(app: &mut App) {
+    let (Some(t), Some(idx)) = (app.selected_tunnel().cloned(), app.selected_tunnel_index()) else { return };
+    if t.port_open {
+        app.notify(format!("Tunnel on port {} is up — nothing to restart", t.local_port));
+        return;
+    }
+    // A live-but-broken session still holds the port.
+    crate::tunnel::stop_tunnel(t.pid);
+    app.tunnels.remove(idx);
+    app.tunnel_selected = app.tunnel_selected.min(app.filtered_tunnels().len().saturating_sub(1));
+
+    let tx = app.tx.clone();
+    app.start_task(format!("Restarting tunnel on port {}...", t.local_port));
+    std::thread::spawn(move || {
+        if t.pid != 0 {
+            std::thread::sleep(std::time::Duration::from_millis(500));
+        }
+        let result = crate::tunnel::restart_tunnel(t.local_port, None, |msg| {
+            let _ = tx.send(BgMessage::Notice(msg.to_string()));
+        });
+        let _ = tx.send(BgMessage::TunnelStarted(result));
+    });
+}
+
+/// Seconds the `B` benchmark runs for.
+const BENCH_SECS: u64 = 5;
+
+fn bench_selected(app: &mut App) {
+    let Some(t) = app.selected_tunnel() else { return };
+    if !t.port_open {
+        app.notify(format!("Tunnel on port {} is down — nothing to benchmark", t.local_port));
+        return;
+    }
+    let port = t.local_port;
+    let tx = app.tx.clone();
+    app.start_task(format!("Benchmarking port {} ({}s)...", port, BENCH_SECS));
+    std::thread::spawn(move || {
+        let result = crate::tunnel::benchmark_port(port, std::time::Duration::from_secs(BENCH_SECS));
+        let _ = tx.send(BgMessage::TunnelBench(port, result));
+    });
+}
+
 pub fn handle_confirm(app: &mut App, tag: ConfirmTag, confirmed: bool) {
     if !confirmed { return; }
     match tag {
-        ConfirmTag::StopTunnel(idx) => {
-            if let Some(t) = app.tunnels.get(idx) {
+        ConfirmTag::StopTunnel(port) => {
+            if let Some(idx) = app.tunnels.iter().position(|t| t.local_port == port) {
+                let t = &app.tunnels[idx];
                 let pid = t.pid;
                 crate::tunnel::stop_tunnel(pid);
+                crate::tunnel::forget_tunnel(t.local_port);
+                crate::audit::record("tunnel-stop", &t.instance_id, Some(&crate::tunnel::tunnel_detail(t)), None, &Ok(()));
+                let msg = if pid == 0 {
+                    format!("Forgot tunnel on port {}", t.local_port)
+                } else {
+                    format!("Stopped tunnel PID {}", pid)
+                };
                 app.tunnels.remove(idx);
-                app.tunnel_selected = app.tunnel_selected.min(app.tunnels.len().saturating_sub(1));
-                app.status_msg = Some(format!("Stopped tunnel PID {}", pid));
+                app.notify(msg);
+                app.tunnel_selected = app.tunnel_selected.min(app.filtered_tunnels().len().saturating_sub(1));
             }
         }
         ConfirmTag::StopAllTunnels => {
             crate::tunnel::stop_all_tunnels();
             app.tunnels.clear();
             app.tunnel_selected = 0;
-            app.status_msg = Some("All tunnels stopped".into());
+            app.notify("All tunnels stopped");
         }
+        ConfirmTag::ReusePort(step) => launch_wizard_tunnel(app, step),
         _ => {}
     }
 }
@@ -180,21 +404,13 @@ pub fn handle_input(app: &mut App, tag: InputTag, value: String) {
             app.popup = Popup::Input {
                 title: "New Tunnel — Remote Port".into(),
                 placeholder: "e.g. 8000".into(),
-                value: "8000".into(),
+                value: crate::config::settings().default_remote_port.to_string(),
                 tag: InputTag::NewTunnelRemotePort,
             };
         }
         InputTag::NewTunnelRemotePort => {
             app.wizard_buf.remote_port = value;
-            let pattern     = app.wizard_buf.pattern.clone();
-            let local_port: u16  = app.wizard_buf.local_port.parse().unwrap_or(18000);
-            let remote_port: u16 = app.wizard_buf.remote_port.parse().unwrap_or(8000);
-            let tx = app.tx.clone();
-            app.popup = Popup::Loading { message: format!("Connecting to *{}*...", pattern) };
-            std::thread::spawn(move || {
-                let result = crate::tunnel::start_tunnel_by_pattern(&pattern, local_port, remote_port, None);
-                let _ = tx.send(BgMessage::TunnelStarted(result));
-            });
+            check_port_then_launch(app, tag, 18000);
         }
 
         // === By URL: url -> local port ===
@@ -218,17 +434,7 @@ pub fn handle_input(app: &mut App, tag: InputTag, value: String) {
         }
         InputTag::NewTunnelUrlRemotePort => {
             app.wizard_buf.remote_port = value;
-            let url = app.wizard_buf.url.clone();
-            let local_port: u16 = app.wizard_buf.local_port.parse().unwrap_or(8080);
-            let remote_port: Option<u16> = app.wizard_buf.remote_port.parse().ok();
-            let tx = app.tx.clone();
-            app.popup = Popup::Loading { message: "Resolving via ALB / bastions...".into() };
-            std::thread::spawn(move || {
-                let host = crate::aws::strip_url_to_host(&url);
-                // Try smart ALB resolution first
-                let result = try_alb_tunnel_bg(&host, &url, local_port, remote_port);
-                let _ = tx.send(BgMessage::TunnelStarted(result));
-            });
+            check_port_then_launch(app, tag, 8080);
         }
 
         // === By bastion: bastion -> host -> local port -> remote port ===
@@ -255,44 +461,110 @@ pub fn handle_input(app: &mut App, tag: InputTag, value: String) {
             app.popup = Popup::Input {
                 title: "Remote Tunnel — Remote Port".into(),
                 placeholder: "e.g. 8501".into(),
-                value: "8501".into(),
+                value: crate::config::settings().default_remote_host_port.to_string(),
                 tag: InputTag::NewTunnelBastionRemotePort,
             };
         }
         InputTag::NewTunnelBastionRemotePort => {
             app.wizard_buf.remote_port = value;
+            check_port_then_launch(app, tag, 8501);
+        }
+
+        _ => {}
+    }
+}
+
+/// Final wizard step: ask before binding a local port that already answers,
+/// otherwise launch straight away.
+fn check_port_then_launch(app: &mut App, step: InputTag, default_port: u16) {
+    let local_port: u16 = app.wizard_buf.local_port.parse().unwrap_or(default_port);
+    if crate::tunnel::test_port(local_port) {
+        app.popup = Popup::Confirm {
+            message: format!("Port {} already in use — continue anyway?", local_port),
+            tag: ConfirmTag::ReusePort(step),
+            selected_yes: false,
+        };
+    } else {
+        launch_wizard_tunnel(app, step);
+    }
+}
+
+/// Spawn the tunnel described by `wizard_buf` for the given final wizard step.
+fn launch_wizard_tunnel(app: &mut App, step: InputTag) {
+    match step {
+        InputTag::NewTunnelRemotePort => {
+            let pattern     = app.wizard_buf.pattern.clone();
+            let local_port: u16  = app.wizard_buf.local_port.parse().unwrap_or(18000);
+            let remote_port: u16 = app.wizard_buf.remote_port.parse()
+                .unwrap_or(crate::config::settings().default_remote_port);
+            let tx = app.loading_sender();
+            app.show_loading(format!("Connecting to *{}*...", pattern));
+            std::thread::spawn(move || {
+                let result = crate::tunnel::start_tunnel_by_pattern(&pattern, local_port, remote_port, None);
+                let _ = tx.send(BgMessage::TunnelStarted(result));
+            });
+        }
+        InputTag::NewTunnelUrlRemotePort => {
+            let url = app.wizard_buf.url.clone();
+            let local_port: u16 = app.wizard_buf.local_port.parse().unwrap_or(8080);
+            let remote_port: Option<u16> = app.wizard_buf.remote_port.parse().ok();
+            let tx = app.loading_sender();
+            app.show_loading(format!("Resolving {} via ALB / bastions...", url));
+            std::thread::spawn(move || {
+                let host = crate::aws::strip_url_to_host(&url);
+                // Try smart ALB resolution first
+                let result = try_alb_tunnel_bg(&host, &url, local_port, remote_port, &tx);
+                let _ = tx.send(BgMessage::TunnelStarted(result));
+            });
+        }
+        InputTag::NewTunnelBastionRemotePort => {
             let bastion     = app.wizard_buf.bastion.clone();
             let host        = app.wizard_buf.host.clone();
             let local_port: u16  = app.wizard_buf.local_port.parse().unwrap_or(8501);
-            let remote_port: u16 = app.wizard_buf.remote_port.parse().unwrap_or(8501);
-            let tx = app.tx.clone();
-            app.popup = Popup::Loading { message: format!("Connecting via {}...", bastion) };
+            let remote_port: u16 = app.wizard_buf.remote_port.parse()
+                .unwrap_or(crate::config::settings().default_remote_host_port);
+            let tx = app.loading_sender();
+            app.show_loading(format!("Connecting via {}...", bastion));
             std::thread::spawn(move || {
                 let result = crate::tunnel::start_remote_tunnel_via_pattern(&bastion, &host, local_port, remote_port, None);
                 let _ = tx.send(BgMessage::TunnelStarted(result));
             });
         }
-
         _ => {}
     }
 }
 
-/// Try smart ALB resolution, fall back to bastions. Used by the TUI wizard in a bg thread.
-fn try_alb_tunnel_bg(host: &str, url: &str, local_port: u16, remote_port: Option<u16>) -> AppResult<TunnelProcess> {
+/// Try smart ALB resolution, fall back to bastions. Used by the TUI wizard in a
+/// bg thread; stops between steps once the user cancels.
+fn try_alb_tunnel_bg(
+    host: &str,
+    url: &str,
+    local_port: u16,
+    remote_port: Option<u16>,
+    tx: &LoadingSender,
+) -> AppResult<TunnelProcess> {
+    let cancelled = || AppError::Other("Cancelled".into());
     // Try ALB-aware resolution
-    if let Some(alb_arn) = crate::aws::find_alb_for_hostname(host, None).unwrap_or(None) {
-        let targets = crate::aws::get_alb_healthy_targets(&alb_arn, remote_port, None).unwrap_or_default();
-        for (target_ip, target_port) in &targets {
-            let target_sgs = crate::aws::get_target_sg_ids(target_ip, None).unwrap_or_default();
-            if target_sgs.is_empty() { continue; }
-            let allowed = crate::aws::get_allowed_source_sgs(&target_sgs, *target_port, None).unwrap_or_default();
-            if let Some(hop) = crate::aws::find_ssm_hop_by_sgs(&allowed, None).unwrap_or(None) {
-                return crate::tunnel::start_remote_tunnel_via_instance(
-                    &hop.id, &hop.name, target_ip, local_port, *target_port, None,
-                );
-            }
-        }
+    let alb = crate::aws::resolve_alb_hop(host, remote_port, None, |_| {}, || tx.is_cancelled());
+    if let Ok(Some(crate::aws::AlbHop { hop, target, port, .. })) = alb {
+        return crate::tunnel::start_remote_tunnel_via_instance(&hop.id, &hop.name, &target, local_port, port, None);
     }
+    if tx.is_cancelled() { return Err(cancelled()); }
     // Fall back to bastions (auto-detect port if not specified)
-    crate::tunnel::start_url_tunnel_via_any_bastion(url, local_port, remote_port, None)
+    crate::tunnel::start_url_tunnel_via_any_bastion(url, local_port, remote_port, None, |msg| {
+        let _ = tx.send(BgMessage::Progress(msg.to_string()));
+    })
+}
+
+/// `o`: open the selected tunnel in the browser, through its `--proxy`
+/// hostname when it has one.
+fn open_selected(app: &mut App) {
+    let Some(port) = app.selected_tunnel().map(|t| t.local_port) else { return };
+    let url = crate::tunnel::ports_for(port)
+        .into_iter()
+        .find_map(crate::proxy::proxy_for_port)
+        .map(|(host, tls)| format!("{}://{}", if tls { "https" } else { "http" }, host))
+        .unwrap_or_else(|| format!("http://localhost:{}", port));
+    crate::util::open_url_in_browser(&url);
+    app.status_msg = Some(format!("Opened {}", url));
 }
@@ -3,22 +3,26 @@
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Modifier, Style},
+    style::Modifier,
     text::{Line, Span},
     widgets::{Block, BorderType, Borders, List, ListItem, ListState, Paragraph},
     Frame,
 };
 
+use crate::models::VpnPhase;
 use crate::tui::app::{App, BgMessage, InputTag, Popup};
-use crate::tui::ui::{C_BORDER, C_DIM, C_GOLD, C_OK, C_DANGER, C_TEXT};
 
 const VPN_ACTIONS: &[(&str, &str)] = &[
     ("Connect",    "Connect to VPN (enter MFA code)"),
     ("Disconnect", "Disconnect active VPN session"),
     ("Setup",      "Configure SSO credentials and .ovpn path"),
     ("Status",     "Check VPN connection status"),
+    ("Config",     "Switch between saved VPN configs / create a new one"),
 ];
 
+const DEFAULT_CONFIG_ITEM: &str = "(default)";
+const NEW_CONFIG_ITEM: &str = "+ New config...";
+
 pub fn render(f: &mut Frame, app: &App, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
@@ -35,7 +39,7 @@ fn render_menu(f: &mut Frame, app: &App, area: Rect) {
         .map(|(name, _)| {
             ListItem::new(Line::from(vec![
                 Span::raw("  "),
-                Span::styled(*name, Style::default().fg(C_TEXT)),
+                Span::styled(*name, app.theme.text),
             ]))
         })
         .collect();
@@ -45,16 +49,17 @@ fn render_menu(f: &mut Frame, app: &App, area: Rect) {
             Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(C_BORDER))
+                .border_style(app.theme.border)
                 .title(" VPN ")
-                .title_style(Style::default().fg(C_BORDER).add_modifier(Modifier::BOLD)),
+                .title_style(app.theme.border.add_modifier(Modifier::BOLD)),
         )
-        .highlight_style(Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD))
+        .highlight_style(app.theme.accent.add_modifier(Modifier::BOLD))
         .highlight_symbol("▸ ");
 
     let mut state = ListState::default();
     state.select(Some(app.vpn_selected));
     f.render_stateful_widget(list, area, &mut state);
+    app.set_row_area(area, 0, state.offset());
 }
 
 fn render_details(f: &mut Frame, app: &App, area: Rect) {
@@ -63,83 +68,86 @@ fn render_details(f: &mut Frame, app: &App, area: Rect) {
         .copied()
         .unwrap_or(("", ""));
 
-    let status_color = if app.vpn_status.starts_with("CONNECTED") { C_OK } else { C_DANGER };
+    let status_style = if app.vpn_status.starts_with("CONNECTED") { app.theme.ok } else { app.theme.danger };
     let config = &app.vpn_config;
-    let password_display = if config.sso_password.is_empty() { "(not set)" } else { "********" };
+    let password_display = match (config.sso_password.is_empty(), config.password_in_keychain) {
+        (_, true) => "******** (keychain)",
+        (true, false) => "(not set)",
+        (false, false) => "********",
+    };
 
     let lines = vec![
         Line::from(""),
         Line::from(vec![
-            Span::styled("  Status: ", Style::default().fg(C_DIM)),
-            Span::styled(&app.vpn_status, Style::default().fg(status_color).add_modifier(Modifier::BOLD)),
+            Span::styled("  Status: ", app.theme.dim),
+            Span::styled(&app.vpn_status, status_style.add_modifier(Modifier::BOLD)),
         ]),
         Line::from(""),
-        Line::from(Span::styled("  Configuration", Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD))),
         Line::from(vec![
-            Span::styled("  Username:  ", Style::default().fg(C_DIM)),
+            Span::styled("  Configuration ", app.theme.accent.add_modifier(Modifier::BOLD)),
+            Span::styled(
+                format!("[{}]", app.vpn_profile.as_deref().unwrap_or(DEFAULT_CONFIG_ITEM)),
+                app.theme.text,
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  Username:  ", app.theme.dim),
             Span::styled(
                 if config.sso_username.is_empty() { "(not set)" } else { &config.sso_username },
-                Style::default().fg(C_TEXT),
+                app.theme.text,
             ),
         ]),
         Line::from(vec![
-            Span::styled("  Password:  ", Style::default().fg(C_DIM)),
-            Span::styled(password_display, Style::default().fg(C_TEXT)),
+            Span::styled("  Password:  ", app.theme.dim),
+            Span::styled(password_display, app.theme.text),
         ]),
         Line::from(vec![
-            Span::styled("  OVPN file: ", Style::default().fg(C_DIM)),
+            Span::styled("  OVPN file: ", app.theme.dim),
             Span::styled(
                 if config.ovpn_path.is_empty() { "(not set)" } else { &config.ovpn_path },
-                Style::default().fg(C_TEXT),
+                app.theme.text,
             ),
         ]),
         Line::from(vec![
-            Span::styled("  DNS:       ", Style::default().fg(C_DIM)),
-            Span::styled(format!("{} ({})", config.dns_server, config.dns_domain), Style::default().fg(C_TEXT)),
+            Span::styled("  DNS:       ", app.theme.dim),
+            Span::styled(format!("{} ({})", config.dns_server, config.dns_domain), app.theme.text),
         ]),
         Line::from(""),
         Line::from(Span::styled(
             format!("  {} — {}", name, desc),
-            Style::default().fg(C_DIM),
+            app.theme.dim,
         )),
         Line::from(""),
-        Line::from(Span::styled("  Press [Enter] to run.", Style::default().fg(C_TEXT))),
+        Line::from(Span::styled("  Press [Enter] to run.", app.theme.text)),
     ];
 
     let p = Paragraph::new(lines).block(
         Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(C_BORDER)),
+            .border_style(app.theme.border),
     );
     f.render_widget(p, area);
 }
 
 // ── Key handling ──────────────────────────────────────────────────────────────
 
+/// Mouse click on a menu entry.
+pub fn select_row(app: &mut App, idx: usize) {
+    if idx < VPN_ACTIONS.len() { app.vpn_selected = idx; }
+}
+
 pub fn handle_key(app: &mut App, key: KeyEvent) {
     match key.code {
-        KeyCode::Up | KeyCode::Char('k') => {
-            if app.vpn_selected > 0 {
-                app.vpn_selected -= 1;
-            }
+        KeyCode::Up | KeyCode::Char('k') if app.vpn_selected > 0 => {
+            app.vpn_selected -= 1;
         }
-        KeyCode::Down | KeyCode::Char('j') => {
-            if app.vpn_selected + 1 < VPN_ACTIONS.len() {
-                app.vpn_selected += 1;
-            }
+        KeyCode::Down | KeyCode::Char('j') if app.vpn_selected + 1 < VPN_ACTIONS.len() => {
+            app.vpn_selected += 1;
         }
         KeyCode::Enter => execute_action(app),
-        KeyCode::Char('r') => {
-            app.vpn_status = if crate::vpn::is_connected() {
-                format!(
-                    "CONNECTED ({})",
-                    crate::vpn::get_vpn_ip().unwrap_or_else(|| "?".into())
-                )
-            } else {
-                "DISCONNECTED".into()
-            };
-        }
+        KeyCode::Char('l') => show_log(app),
+        KeyCode::Char('r') => app.refresh_vpn_status(),
         _ => {}
     }
 }
@@ -157,7 +165,11 @@ fn execute_action(app: &mut App) {
                 return;
             }
             app.popup = Popup::Input {
-                title: "VPN MFA Code".into(),
+                title: if app.vpn_config.totp_secret.is_some() {
+                    "VPN MFA Code (blank = use stored TOTP secret)".into()
+                } else {
+                    "VPN MFA Code".into()
+                },
                 placeholder: "6-digit code from authenticator".into(),
                 value: String::new(),
                 tag: InputTag::VpnMfaCode,
@@ -165,12 +177,12 @@ fn execute_action(app: &mut App) {
         }
         // Disconnect
         1 => {
-            crate::vpn::disconnect();
-            app.vpn_status = "DISCONNECTED".into();
-            app.popup = Popup::Result {
-                title: "VPN".into(),
-                body: "VPN disconnected.".into(),
-                is_error: false,
+            app.popup = match crate::vpn::disconnect() {
+                Ok(()) => {
+                    app.vpn_status = "DISCONNECTED".into();
+                    Popup::Result { title: "VPN".into(), body: "VPN disconnected.".into(), is_error: false }
+                }
+                Err(e) => Popup::Result { title: "VPN Error".into(), body: e.to_string(), is_error: true },
             };
         }
         // Setup
@@ -201,27 +213,89 @@ fn execute_action(app: &mut App) {
                 is_error: false,
             };
         }
+        // Config selector
+        4 => {
+            let mut items = vec![DEFAULT_CONFIG_ITEM.to_string()];
+            items.extend(crate::vpn::list_configs());
+            items.push(NEW_CONFIG_ITEM.to_string());
+            let selected = app.vpn_profile
+                .as_ref()
+                .and_then(|p| items.iter().position(|i| i == p))
+                .unwrap_or(0);
+            app.popup = Popup::Select {
+                title: "VPN Config".into(),
+                items,
+                selected,
+                tag: InputTag::VpnSelectConfig,
+            };
+        }
         _ => {}
     }
 }
 
+/// Open the most recent openvpn log in a (scrollable) result popup.
+fn show_log(app: &mut App) {
+    app.result_scroll = 0;
+    app.popup = match crate::vpn::latest_log() {
+        Some(path) => match std::fs::read_to_string(&path) {
+            Ok(text) => Popup::Result {
+                title: format!("VPN log — {}", path.display()),
+                body: if text.trim().is_empty() { "(empty)".into() } else { text },
+                is_error: false,
+            },
+            Err(e) => Popup::Result {
+                title: "VPN log".into(),
+                body: format!("Cannot read {}: {}", path.display(), e),
+                is_error: true,
+            },
+        },
+        None => Popup::Result {
+            title: "VPN log".into(),
+            body: "No VPN logs yet — one is written on each connect.".into(),
+            is_error: false,
+        },
+    };
+}
+
+fn switch_profile(app: &mut App, profile: Option<String>) {
+    match crate::vpn::load_config(profile.as_deref()) {
+        Ok(config) => {
+            app.vpn_config = config;
+            app.notify(format!(
+                "VPN config → {}", profile.as_deref().unwrap_or(DEFAULT_CONFIG_ITEM)
+            ));
+            app.vpn_profile = profile;
+        }
+        Err(e) => {
+            app.popup = Popup::Result { title: "VPN Config Error".into(), body: e.to_string(), is_error: true };
+        }
+    }
+}
+
 pub fn handle_input(app: &mut App, tag: InputTag, value: String) {
     match tag {
         InputTag::VpnMfaCode => {
-            let mfa = value.trim().to_string();
+            let mut mfa = value.trim().to_string();
             if mfa.is_empty() {
-                return;
+                match crate::vpn::totp_code(&app.vpn_config) {
+                    Ok(Some(code)) => mfa = code,
+                    Ok(None) => return,
+                    Err(e) => {
+                        app.popup = Popup::Result { title: "VPN Error".into(), body: e.to_string(), is_error: true };
+                        return;
+                    }
+                }
             }
             let config = app.vpn_config.clone();
-            let tx = app.tx.clone();
-            app.popup = Popup::Loading {
-                message: "[1/5] Preparing VPN config...".into(),
-            };
+            let profile = app.vpn_profile.clone();
+            let tx = app.loading_sender();
+            app.vpn_phase = Some(VpnPhase::PreparingConfig);
+            app.show_loading("Preparing VPN config...");
             let tx2 = tx.clone();
             std::thread::spawn(move || {
-                let result = crate::vpn::connect(&config, &mfa, |msg| {
-                    let _ = tx2.send(BgMessage::VpnProgress(msg.to_string()));
-                });
+                let result = crate::vpn::connect(&config, profile.as_deref(), &mfa, |phase, msg| {
+                    let _ = tx2.send(BgMessage::VpnProgress(phase, msg.to_string()));
+                }, || tx.is_cancelled());
                 let msg = match &result {
                     Ok(pid) => {
                         let ip = crate::vpn::get_vpn_ip().unwrap_or_else(|| "?".into());
@@ -232,6 +306,35 @@ pub fn handle_input(app: &mut App, tag: InputTag, value: String) {
                 let _ = tx.send(BgMessage::VpnConnected(msg));
             });
         }
+        InputTag::VpnSelectConfig => {
+            match value.as_str() {
+                "" => {}
+                DEFAULT_CONFIG_ITEM => switch_profile(app, None),
+                NEW_CONFIG_ITEM => {
+                    app.popup = Popup::Input {
+                        title: "New VPN Config Name".into(),
+                        placeholder: "e.g. prod".into(),
+                        value: String::new(),
+                        tag: InputTag::VpnNewConfigName,
+                    };
+                }
+                name => switch_profile(app, Some(name.to_string())),
+            }
+        }
+        InputTag::VpnNewConfigName => {
+            let name = value.trim().to_string();
+            if name.is_empty() { return; }
+            switch_profile(app, Some(name));
+            if matches!(app.popup, Popup::None) {
+                // Straight into setup for the new profile.
+                app.popup = Popup::Input {
+                    title: "SSO Username/Email".into(),
+                    placeholder: "e.g. user@company.com".into(),
+                    value: app.vpn_config.sso_username.clone(),
+                    tag: InputTag::VpnSetupUsername,
+                };
+            }
+        }
         InputTag::VpnSetupUsername => {
             app.vpn_config.sso_username = value;
             app.popup = Popup::Input {
@@ -252,7 +355,7 @@ pub fn handle_input(app: &mut App, tag: InputTag, value: String) {
         }
         InputTag::VpnSetupOvpnPath => {
             app.vpn_config.ovpn_path = value;
-            match crate::vpn::save_config(&app.vpn_config) {
+            match crate::vpn::save_config(&mut app.vpn_config, app.vpn_profile.as_deref()) {
                 Ok(_) => {
                     app.popup = Popup::Result {
                         title: "VPN Setup".into(),
@@ -0,0 +1,79 @@
+//! TUI palette. `color` is the usual cyan/gold scheme; `mono` (chosen with
+//! `--theme mono` or when `NO_COLOR` is set) uses only terminal attributes.
+
+use ratatui::style::{Color, Modifier, Style};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ThemeName {
+    Color,
+    Mono,
+}
+
+/// Styles for each role in the UI. Call sites add `BOLD` etc. on top.
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    /// Frames, block titles, the logo.
+    pub border: Style,
+    /// Headings, key names, highlighted values.
+    pub accent: Style,
+    pub ok: Style,
+    pub danger: Style,
+    /// Labels, hints, inactive tabs.
+    pub dim: Style,
+    pub text: Style,
+    /// Highlighted table row.
+    pub selected_row: Style,
+    /// Inverted label, e.g. the active profile and the focused confirm button.
+    pub badge: Style,
+    /// Inverted label for destructive choices.
+    pub badge_danger: Style,
+    /// Filled part of a progress gauge.
+    pub gauge: Style,
+}
+
+impl Theme {
+    /// `NO_COLOR` (any non-empty value, see no-color.org) wins over `--theme`.
+    pub fn from_env(name: ThemeName) -> Self {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        match name {
+            ThemeName::Color if !no_color => Self::color(),
+            _ => Self::mono(),
+        }
+    }
+
+    pub fn color() -> Self {
+        Self {
+            border:       Style::default().fg(Color::Cyan),
+            accent:       Style::default().fg(Color::Yellow),
+            ok:           Style::default().fg(Color::Green),
+            danger:       Style::default().fg(Color::Red),
+            dim:          Style::default().fg(Color::DarkGray),
+            text:         Style::default().fg(Color::White),
+            selected_row: Style::default().bg(Color::DarkGray).fg(Color::White).add_modifier(Modifier::BOLD),
+            badge:        Style::default().fg(Color::Black).bg(Color::Yellow),
+            badge_danger: Style::default().fg(Color::Black).bg(Color::Red),
+            gauge:        Style::default().fg(Color::Green).bg(Color::Black),
+        }
+    }
+
+    pub fn mono() -> Self {
+        Self {
+            border:       Style::default(),
+            accent:       Style::default().add_modifier(Modifier::BOLD),
+            ok:           Style::default(),
+            danger:       Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            dim:          Style::default().add_modifier(Modifier::DIM),
+            text:         Style::default(),
+            selected_row: Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            badge:        Style::default().add_modifier(Modifier::REVERSED),
+            badge_danger: Style::default().add_modifier(Modifier::REVERSED | Modifier::UNDERLINED),
+            gauge:        Style::default(),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::color()
+    }
+}
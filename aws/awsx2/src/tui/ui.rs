@@ -2,23 +2,15 @@
 
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::Modifier,
     text::{Line, Span},
-    widgets::{Block, BorderType, Borders, Clear, Paragraph, Tabs, Wrap},
+    widgets::{Block, BorderType, Borders, Clear, Gauge, Paragraph, Tabs, Wrap},
     Frame,
 };
 
 use super::app::{App, Popup, Tab};
 use super::pages;
-
-// ── Color palette ─────────────────────────────────────────────────────────────
-
-pub const C_BORDER: Color = Color::Cyan;
-pub const C_GOLD:   Color = Color::Yellow;
-pub const C_OK:     Color = Color::Green;
-pub const C_DANGER: Color = Color::Red;
-pub const C_DIM:    Color = Color::DarkGray;
-pub const C_TEXT:   Color = Color::White;
+use super::theme::Theme;
 
 // ── Spinner frames ────────────────────────────────────────────────────────────
 
@@ -36,7 +28,7 @@ pub fn render(f: &mut Frame, app: &App) {
     let outer_block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(C_BORDER));
+        .border_style(app.theme.border);
     f.render_widget(outer_block, area);
 
     let inner = Rect {
@@ -58,7 +50,17 @@ pub fn render(f: &mut Frame, app: &App) {
 
     render_header(f, app, vchunks[0]);
     render_tabs(f, app, vchunks[1]);
-    render_body(f, app, vchunks[2]);
+    if app.show_event_log {
+        let log_height = (vchunks[2].height / 3).max(5);
+        let body = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(log_height)])
+            .split(vchunks[2]);
+        render_body(f, app, body[0]);
+        render_event_log(f, app, body[1]);
+    } else {
+        render_body(f, app, vchunks[2]);
+    }
     render_status_bar(f, app, vchunks[3]);
 
     if app.loading {
@@ -79,19 +81,19 @@ fn render_header(f: &mut Frame, app: &App, area: Rect) {
     let logo = Paragraph::new(vec![
         Line::from(Span::styled(
             "  █████╗ ██╗    ██╗███████╗██╗  ██╗██████╗",
-            Style::default().fg(C_BORDER).add_modifier(Modifier::BOLD),
+            app.theme.border.add_modifier(Modifier::BOLD),
         )),
         Line::from(Span::styled(
             " ██╔══██╗██║    ██║██╔════╝╚██╗██╔╝╚════██╗",
-            Style::default().fg(C_BORDER).add_modifier(Modifier::BOLD),
+            app.theme.border.add_modifier(Modifier::BOLD),
         )),
         Line::from(Span::styled(
             " ███████║██║ █╗ ██║███████╗ ╚███╔╝  █████╔╝",
-            Style::default().fg(C_BORDER).add_modifier(Modifier::BOLD),
+            app.theme.border.add_modifier(Modifier::BOLD),
         )),
         Line::from(Span::styled(
             " ██╔══██║██║███╗██║╚════██║ ██╔██╗  ╚═══██╗",
-            Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD),
+            app.theme.accent.add_modifier(Modifier::BOLD),
         )),
     ]);
     f.render_widget(logo, hchunks[0]);
@@ -99,20 +101,30 @@ fn render_header(f: &mut Frame, app: &App, area: Rect) {
     // Profile + region info
     let info = Paragraph::new(vec![
         Line::from(vec![
-            Span::styled("Profile:   ", Style::default().fg(C_DIM)),
-            Span::styled(&app.profile, Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD)),
+            Span::styled("Profile:   ", app.theme.dim),
+            Span::styled(&app.profile, app.theme.accent.add_modifier(Modifier::BOLD)),
         ]),
         Line::from(vec![
-            Span::styled("Region:    ", Style::default().fg(C_DIM)),
-            Span::styled(&app.region, Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD)),
+            Span::styled("Region:    ", app.theme.dim),
+            Span::styled(&app.region, app.theme.accent.add_modifier(Modifier::BOLD)),
         ]),
         Line::from(vec![
-            Span::styled("Instances: ", Style::default().fg(C_DIM)),
-            Span::styled(app.instances.len().to_string(), Style::default().fg(C_TEXT)),
-            Span::styled("  Tunnels: ", Style::default().fg(C_DIM)),
-            Span::styled(app.tunnels.len().to_string(), Style::default().fg(C_TEXT)),
+            Span::styled("Instances: ", app.theme.dim),
+            Span::styled(app.instances.len().to_string(), app.theme.text),
+            match app.hidden_instance_count() {
+                0 => Span::raw(""),
+                n => Span::styled(format!(" (filtered: {} hidden)", n), app.theme.accent),
+            },
+            Span::styled("  Tunnels: ", app.theme.dim),
+            Span::styled(app.tunnels.len().to_string(), app.theme.text),
         ]),
-        Line::from(""),
+        match crate::config::env_name() {
+            Some(env) => Line::from(vec![
+                Span::styled("Env:       ", app.theme.dim),
+                Span::styled(env, app.theme.accent.add_modifier(Modifier::BOLD)),
+            ]),
+            None => Line::from(""),
+        },
     ]).alignment(Alignment::Right);
     f.render_widget(info, hchunks[1]);
 }
@@ -125,18 +137,48 @@ fn render_tabs(f: &mut Frame, app: &App, area: Rect) {
     let tabs = Tabs::new(titles)
         .select(app.tab.index())
         .highlight_style(
-            Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD).add_modifier(Modifier::UNDERLINED),
+            app.theme.accent.add_modifier(Modifier::BOLD).add_modifier(Modifier::UNDERLINED),
         )
-        .style(Style::default().fg(C_DIM))
-        .divider(Span::styled(" │ ", Style::default().fg(C_DIM)));
+        .style(app.theme.dim)
+        .divider(Span::styled(" │ ", app.theme.dim));
 
     let tabs_block = Block::default()
         .borders(Borders::BOTTOM)
-        .border_style(Style::default().fg(C_DIM));
+        .border_style(app.theme.dim);
     f.render_widget(tabs_block, area);
 
     let tabs_inner = Rect { x: area.x + 2, y: area.y, width: area.width.saturating_sub(2), height: area.height };
     f.render_widget(tabs, tabs_inner);
+    let mut hit = app.hit_areas.get();
+    hit.tabs = Rect { height: 1, ..tabs_inner };
+    app.hit_areas.set(hit);
+
+    // Active profile, on the right of the tab row.
+    let profile = Paragraph::new(Line::from(vec![
+        Span::styled("[P] ", app.theme.dim),
+        Span::styled(
+            format!(" {} ", app.profile),
+            app.theme.badge.add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(" "),
+    ]))
+    .alignment(Alignment::Right);
+    f.render_widget(profile, Rect { height: 1, ..area });
+}
+
+/// Tab whose title is drawn at column `x` of the tab row starting at `left`.
+/// Mirrors the `Tabs` layout: one column of padding on each side of a title,
+/// and the 3-column divider between titles.
+pub fn tab_at(left: u16, x: u16) -> Option<Tab> {
+    let mut start = left;
+    for (i, title) in Tab::titles().iter().enumerate() {
+        let end = start + title.chars().count() as u16 + 2;
+        if (start..end).contains(&x) {
+            return Some(Tab::from_index(i));
+        }
+        start = end + 3;
+    }
+    None
 }
 
 // ── Body ──────────────────────────────────────────────────────────────────────
@@ -150,23 +192,49 @@ fn render_body(f: &mut Frame, app: &App, area: Rect) {
     }
 }
 
+// ── Event log ─────────────────────────────────────────────────────────────────
+
+/// `L` pane: the event log, newest first, long entries wrapped.
+fn render_event_log(f: &mut Frame, app: &App, area: Rect) {
+    let lines: Vec<Line> = if app.event_log.is_empty() {
+        vec![Line::from(Span::styled("Nothing yet — actions, results and errors show up here.", app.theme.dim))]
+    } else {
+        app.event_log
+            .iter()
+            .rev()
+            .map(|e| Line::from(vec![
+                Span::styled(format!("{} ", e.time), app.theme.dim),
+                Span::styled(e.text.as_str(), if e.is_error { app.theme.danger } else { app.theme.text }),
+            ]))
+            .collect()
+    };
+    let block = Block::default()
+        .title(format!(" Events ({}) ", app.event_log.len()))
+        .title_style(app.theme.accent.add_modifier(Modifier::BOLD))
+        .title_bottom(Line::from(Span::styled(" [L] Hide ", app.theme.dim)).alignment(Alignment::Right))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(app.theme.border);
+    f.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }).block(block), area);
+}
+
 // ── Status bar ────────────────────────────────────────────────────────────────
 
 fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
     let hints = match app.tab {
-        Tab::Instances => " [Tab] Switch  [s] Start  [S] Stop  [f] Force-stop  [r] Refresh  [/] Filter  [?] Help  [q] Quit",
-        Tab::Tunnels   => " [Tab] Switch  [n] By instance  [u] By URL  [b] Via bastion  [d] Stop  [A] Stop all  [r] Refresh  [?] Help  [q] Quit",
-        Tab::Tools     => " [Tab] Switch  [j/k] Navigate  [Enter] Execute  [?] Help  [q] Quit",
-        Tab::Vpn       => " [Tab] Switch  [j/k] Navigate  [Enter] Execute  [r] Refresh status  [?] Help  [q] Quit",
+        Tab::Instances => " [Tab] Switch  [Enter] Details  [y] Copy ID  [x] Shell  [s] Start  [S] Stop  [f] Force-stop  [R] Reboot  [D] Terminate  [i] Type  [t] Tag  [c] Console  [T] Tunnels  [n] Launch  [r] Refresh  [/] Filter  [1-8/o] Sort  [F] AWS filter  [a] All/scoped  [L] Events  [?] Help  [q] Quit",
+        Tab::Tunnels   => " [Tab] Switch  [Enter] Details  [n] By instance  [u] By URL  [b] Via bastion  [d] Stop  [A] Stop all  [R] Restart  [B] Bench  [y] Copy URL  [o] Open  [C] Command  [w] Auto-reconnect  [r] Refresh  [/] Filter  [L] Events  [?] Help  [q] Quit",
+        Tab::Tools     => " [Tab] Switch  [j/k] Navigate  [Enter] Execute  [L] Events  [?] Help  [q] Quit",
+        Tab::Vpn       => " [Tab] Switch  [j/k] Navigate  [Enter] Execute  [r] Refresh status  [l] Log  [L] Events  [?] Help  [q] Quit",
     };
 
     let text = if let Some(ref msg) = app.status_msg {
         Line::from(vec![
-            Span::styled(" > ", Style::default().fg(C_GOLD)),
-            Span::styled(msg.as_str(), Style::default().fg(C_TEXT)),
+            Span::styled(" > ", app.theme.accent),
+            Span::styled(msg.as_str(), app.theme.text),
         ])
     } else {
-        Line::from(Span::styled(hints, Style::default().fg(C_DIM)))
+        Line::from(Span::styled(hints, app.theme.dim))
     };
 
     f.render_widget(Paragraph::new(text), area);
@@ -180,8 +248,8 @@ fn render_loading(f: &mut Frame, app: &App, area: Rect) {
     let msg = format!(" {} {} ", spinner_char(app.spinner_tick), app.loading_message);
     let p = Paragraph::new(msg)
         .alignment(Alignment::Center)
-        .style(Style::default().fg(C_GOLD))
-        .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded).border_style(Style::default().fg(C_BORDER)));
+        .style(app.theme.accent)
+        .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded).border_style(app.theme.border));
     f.render_widget(p, popup_area);
 }
 
@@ -190,46 +258,96 @@ fn render_loading(f: &mut Frame, app: &App, area: Rect) {
 fn render_popup(f: &mut Frame, app: &App, area: Rect) {
     match &app.popup {
         Popup::None => {}
-        Popup::Help => render_help(f, area),
+        Popup::Help => {
+            let max = render_help(f, area, &app.theme, app.result_scroll);
+            app.set_scroll_max(max);
+        }
         Popup::Input { title, placeholder, value, .. } => {
-            render_input_popup(f, area, title, placeholder, value);
+            render_input_popup(f, area, &app.theme, title, placeholder, value);
         }
         Popup::Select { title, items, selected, .. } => {
-            render_select_popup(f, area, title, items, *selected);
+            render_select_popup(f, area, &app.theme, title, items, *selected);
         }
         Popup::Confirm { message, selected_yes, .. } => {
-            render_confirm(f, area, message, *selected_yes);
+            render_confirm(f, area, &app.theme, message, *selected_yes);
         }
         Popup::Result { title, body, is_error } => {
-            render_result(f, area, title, body, *is_error);
+            let max = render_result(f, area, &app.theme, title, body, *is_error, app.result_scroll);
+            app.set_scroll_max(max);
+        }
+        Popup::InstanceDetail(inst) => {
+            let volumes = app.volumes_for(&inst.id);
+            let max = pages::instances::render_detail(f, area, &app.theme, inst, &app.region, volumes, app.result_scroll);
+            app.set_scroll_max(max);
+        }
+        Popup::Loading { message } if app.vpn_phase.is_some() => {
+            render_vpn_progress(f, area, app, message);
         }
         Popup::Loading { message } => {
             let popup_area = centered_rect(50, 3, area);
             f.render_widget(Clear, popup_area);
             let p = Paragraph::new(format!(" {} {} ", spinner_char(app.spinner_tick), message))
                 .alignment(Alignment::Center)
-                .style(Style::default().fg(C_GOLD))
-                .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(C_BORDER)));
+                .style(app.theme.accent)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(app.theme.border)
+                        .title_bottom(Line::from(Span::styled(" [Esc] Cancel ", app.theme.dim)).centered()),
+                );
             f.render_widget(p, popup_area);
         }
     }
 }
 
-fn render_input_popup(f: &mut Frame, area: Rect, title: &str, placeholder: &str, value: &str) {
+/// VPN connect progress: current message plus a gauge over the phases.
+fn render_vpn_progress(f: &mut Frame, area: Rect, app: &App, message: &str) {
+    let Some(phase) = app.vpn_phase else { return };
+    let popup_area = centered_rect(60, 6, area);
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .title(" VPN ")
+        .title_style(app.theme.accent.add_modifier(Modifier::BOLD))
+        .title_bottom(Line::from(Span::styled(" [Esc] Cancel ", app.theme.dim)).centered())
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(app.theme.border);
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Length(1), Constraint::Length(1)])
+        .split(inner);
+
+    let msg = Paragraph::new(format!("{} {}", spinner_char(app.spinner_tick), message))
+        .alignment(Alignment::Center)
+        .style(app.theme.accent);
+    f.render_widget(msg, rows[0]);
+
+    let gauge = Gauge::default()
+        .gauge_style(app.theme.gauge)
+        .ratio(phase.ratio())
+        .label(format!("Step {}/{}", phase.step(), crate::models::VpnPhase::COUNT));
+    f.render_widget(gauge, rows[2]);
+}
+
+fn render_input_popup(f: &mut Frame, area: Rect, theme: &Theme, title: &str, placeholder: &str, value: &str) {
     let popup_area = centered_rect(60, 7, area);
     f.render_widget(Clear, popup_area);
 
     let display = if value.is_empty() {
-        Span::styled(placeholder, Style::default().fg(C_DIM))
+        Span::styled(placeholder, theme.dim)
     } else {
-        Span::styled(value, Style::default().fg(C_TEXT))
+        Span::styled(value, theme.text)
     };
 
     let text = vec![
         Line::from(""),
         Line::from(display),
         Line::from(""),
-        Line::from(Span::styled("[Enter] Confirm  [Esc] Cancel  [Backspace] Delete", Style::default().fg(C_DIM))),
+        Line::from(Span::styled("[Enter] Confirm  [Esc] Cancel  [Backspace] Delete", theme.dim)),
     ];
 
     let p = Paragraph::new(text)
@@ -237,32 +355,32 @@ fn render_input_popup(f: &mut Frame, area: Rect, title: &str, placeholder: &str,
         .block(
             Block::default()
                 .title(format!(" {} ", title))
-                .title_style(Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD))
+                .title_style(theme.accent.add_modifier(Modifier::BOLD))
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(C_BORDER)),
+                .border_style(theme.border),
         );
     f.render_widget(p, popup_area);
 }
 
-fn render_confirm(f: &mut Frame, area: Rect, message: &str, selected_yes: bool) {
+fn render_confirm(f: &mut Frame, area: Rect, theme: &Theme, message: &str, selected_yes: bool) {
     let popup_area = centered_rect(55, 8, area);
     f.render_widget(Clear, popup_area);
 
     let cancel_style = if !selected_yes {
-        Style::default().fg(Color::Black).bg(C_GOLD)
+        theme.badge
     } else {
-        Style::default().fg(C_DIM)
+        theme.dim
     };
     let ok_style = if selected_yes {
-        Style::default().fg(Color::Black).bg(C_DANGER)
+        theme.badge_danger
     } else {
-        Style::default().fg(C_DIM)
+        theme.dim
     };
 
     let text = vec![
         Line::from(""),
-        Line::from(Span::styled(message, Style::default().fg(C_TEXT))),
+        Line::from(Span::styled(message, theme.text)),
         Line::from(""),
         Line::from(vec![
             Span::styled("  [ Cancel ]  ", cancel_style),
@@ -270,7 +388,7 @@ fn render_confirm(f: &mut Frame, area: Rect, message: &str, selected_yes: bool)
             Span::styled("  [ Yes ]  ", ok_style),
         ]),
         Line::from(""),
-        Line::from(Span::styled("[Tab/←/→] Toggle  [Enter] Confirm  [Esc] Cancel", Style::default().fg(C_DIM))),
+        Line::from(Span::styled("[Tab/←/→] Toggle  [Enter] Confirm  [Esc] Cancel", theme.dim)),
     ];
 
     let p = Paragraph::new(text)
@@ -278,41 +396,65 @@ fn render_confirm(f: &mut Frame, area: Rect, message: &str, selected_yes: bool)
         .block(
             Block::default()
                 .title(" Confirm ")
-                .title_style(Style::default().fg(C_DANGER).add_modifier(Modifier::BOLD))
+                .title_style(theme.danger.add_modifier(Modifier::BOLD))
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(C_DANGER)),
+                .border_style(theme.danger),
         );
     f.render_widget(p, popup_area);
 }
 
-fn render_result(f: &mut Frame, area: Rect, title: &str, body: &str, is_error: bool) {
+fn render_result(
+    f: &mut Frame,
+    area: Rect,
+    theme: &Theme,
+    title: &str,
+    body: &str,
+    is_error: bool,
+    scroll: u16,
+) -> u16 {
     let lines: Vec<Line> = body.lines().map(|l| Line::from(l.to_string())).collect();
-    let height = (lines.len() as u16 + 6).min(area.height.saturating_sub(4));
+    let wanted = lines.len() as u16 + 6;
+    let height = wanted.min(area.height.saturating_sub(4));
     let popup_area = centered_rect(65, height, area);
     f.render_widget(Clear, popup_area);
 
-    let border_color = if is_error { C_DANGER } else { C_OK };
+    // Long bodies (logs, reports) scroll and read better left-aligned.
+    let overflow = wanted.saturating_sub(height);
+    let scroll = scroll.min(overflow);
+
+    let border_style = if is_error { theme.danger } else { theme.ok };
     let mut content = vec![Line::from("")];
     content.extend(lines);
     content.push(Line::from(""));
-    content.push(Line::from(Span::styled("[Enter/Esc] Close", Style::default().fg(C_DIM))));
+    content.push(Line::from(Span::styled("[c] Copy  [Enter/Esc] Close", theme.dim)));
+
+    let mut block = Block::default()
+        .title(format!(" {} ", title))
+        .title_style(border_style.add_modifier(Modifier::BOLD))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(border_style);
+    if overflow > 0 {
+        block = block.title_bottom(
+            Line::from(Span::styled(
+                format!(" [j/k PgUp/PgDn g/G] Scroll {}/{} ", scroll, overflow),
+                theme.dim,
+            ))
+            .alignment(Alignment::Right),
+        );
+    }
 
     let p = Paragraph::new(content)
-        .alignment(Alignment::Center)
+        .alignment(if overflow > 0 { Alignment::Left } else { Alignment::Center })
         .wrap(Wrap { trim: false })
-        .block(
-            Block::default()
-                .title(format!(" {} ", title))
-                .title_style(Style::default().fg(border_color).add_modifier(Modifier::BOLD))
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(border_color)),
-        );
+        .scroll((scroll, 0))
+        .block(block);
     f.render_widget(p, popup_area);
+    overflow
 }
 
-fn render_select_popup(f: &mut Frame, area: Rect, title: &str, items: &[String], selected: usize) {
+fn render_select_popup(f: &mut Frame, area: Rect, theme: &Theme, title: &str, items: &[String], selected: usize) {
     const VISIBLE: usize = 12;
     let height = (items.len().min(VISIBLE) as u16 + 4).max(5);
     let popup_area = centered_rect(50, height, area);
@@ -328,10 +470,10 @@ fn render_select_popup(f: &mut Frame, area: Rect, title: &str, items: &[String],
             if i == selected {
                 Line::from(Span::styled(
                     format!("▸ {}", item),
-                    Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD),
+                    theme.accent.add_modifier(Modifier::BOLD),
                 ))
             } else {
-                Line::from(Span::styled(format!("  {}", item), Style::default().fg(C_TEXT)))
+                Line::from(Span::styled(format!("  {}", item), theme.text))
             }
         })
         .collect();
@@ -339,78 +481,126 @@ fn render_select_popup(f: &mut Frame, area: Rect, title: &str, items: &[String],
     lines.push(Line::from(""));
     lines.push(Line::from(Span::styled(
         "  [j/k] Navigate  [Enter] Select  [Esc] Cancel",
-        Style::default().fg(C_DIM),
+        theme.dim,
     )));
 
     let p = Paragraph::new(lines).block(
         Block::default()
             .title(format!(" {} ", title))
-            .title_style(Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD))
+            .title_style(theme.accent.add_modifier(Modifier::BOLD))
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(C_BORDER)),
+            .border_style(theme.border),
     );
     f.render_widget(p, popup_area);
 }
 
-fn render_help(f: &mut Frame, area: Rect) {
-    let popup_area = centered_rect(60, 30, area);
-    f.render_widget(Clear, popup_area);
-
+/// Help overlay; scrolls like the result popup when the terminal is too
+/// short for it. Returns how far it can scroll.
+fn render_help(f: &mut Frame, area: Rect, theme: &Theme, scroll: u16) -> u16 {
     let lines = vec![
         Line::from(""),
-        section_line("Global"),
-        key_line("Tab / Shift+Tab", "Cycle tabs"),
-        key_line("q / Ctrl+c",      "Quit"),
-        key_line("?",               "Toggle this help"),
+        section_line(theme, "Global"),
+        key_line(theme, "Tab / Shift+Tab", "Cycle tabs"),
+        key_line(theme, "P",               "Switch AWS profile (reloads every tab)"),
+        key_line(theme, "L",               "Show / hide the event log (last 100 events)"),
+        key_line(theme, "q / Ctrl+c",      "Quit"),
+        key_line(theme, "?",               "Toggle this help"),
+        key_line(theme, "c (in result)",   "Copy result text to clipboard"),
+        key_line(theme, "Esc (spinner)",   "Stop waiting for a slow task"),
+        key_line(theme, "j/k (in result)", "Scroll long results (PgUp/PgDn, g/G)"),
         Line::from(""),
-        section_line("Instances tab"),
-        key_line("j/k or Up/Down",  "Navigate rows"),
-        key_line("s",               "Start selected instance"),
-        key_line("S",               "Stop selected instance"),
-        key_line("f",               "Force-stop selected instance"),
-        key_line("r",               "Refresh list"),
-        key_line("/",               "Filter by name / ID / type"),
-        key_line("Esc",             "Clear filter"),
+        section_line(theme, "Instances tab"),
+        key_line(theme, "j/k or Up/Down",  "Navigate rows"),
+        key_line(theme, "PgUp/PgDn C-u/d", "Page up / down"),
+        key_line(theme, "Enter",           "Details (tags, IPs, SGs, AZ, launch time)"),
+        key_line(theme, "x",               "Interactive SSM shell (exit to return)"),
+        key_line(theme, "y",               "Copy instance ID to clipboard"),
+        key_line(theme, "s",               "Start selected instance"),
+        key_line(theme, "S",               "Stop selected instance"),
+        key_line(theme, "f",               "Force-stop selected instance"),
+        key_line(theme, "r",               "Refresh list"),
+        key_line(theme, "/",               "Filter by name / ID / type / tag value"),
+        key_line(theme, "F",               "Server-side AWS filters (tag:Key=Value ...)"),
+        key_line(theme, "1-8 / o",         "Sort by column (again: reverse) / cycle"),
+        key_line(theme, "a",               "Toggle instance_name_include scoping"),
+        key_line(theme, "R",               "Reboot selected instance"),
+        key_line(theme, "D",               "Terminate selected instance (type name)"),
+        key_line(theme, "i",               "Change instance type (recent / gpu / cpu)"),
+        key_line(theme, "t",               "Set a tag (Key=Value, e.g. Name=...)"),
+        key_line(theme, "c",               "Show the instance's console log"),
+        key_line(theme, "T",               "Jump to the instance's tunnels (t in Details)"),
+        key_line(theme, "n",               "Launch instance from template"),
+        key_line(theme, "Esc",             "Clear filter"),
         Line::from(""),
-        section_line("Tunnels tab"),
-        key_line("j/k or Up/Down",  "Navigate rows"),
-        key_line("n",               "New tunnel by instance pattern"),
-        key_line("u",               "New tunnel by URL (auto-bastion)"),
-        key_line("b",               "New tunnel via specific bastion"),
-        key_line("d / Del",         "Stop selected tunnel"),
-        key_line("A",               "Stop ALL tunnels"),
-        key_line("r",               "Refresh tunnel list"),
+        section_line(theme, "Tunnels tab"),
+        key_line(theme, "j/k or Up/Down",  "Navigate rows"),
+        key_line(theme, "Enter",           "Details (SSM document and parameters)"),
+        key_line(theme, "n",               "New tunnel by instance pattern"),
+        key_line(theme, "u",               "New tunnel by URL (auto-bastion)"),
+        key_line(theme, "b",               "New tunnel via specific bastion"),
+        key_line(theme, "d / Del",         "Stop selected tunnel"),
+        key_line(theme, "A",               "Stop ALL tunnels"),
+        key_line(theme, "R",               "Restart a DOWN tunnel"),
+        key_line(theme, "B",               "Benchmark selected tunnel (5 s)"),
+        key_line(theme, "y",               "Copy http://localhost:<port> to clipboard"),
+        key_line(theme, "o",               "Open in browser (the --proxy hostname if any)"),
+        key_line(theme, "C",               "Show equivalent awsx2 / aws CLI command"),
+        key_line(theme, "w",               "Toggle auto-reconnect watchdog"),
+        key_line(theme, "r",               "Refresh tunnel list"),
+        key_line(theme, "/",               "Filter by name / ID / remote host / port"),
         Line::from(""),
-        section_line("Tools tab"),
-        key_line("j/k or Up/Down",  "Navigate"),
-        key_line("Enter",           "Execute selected tool"),
+        section_line(theme, "Tools tab"),
+        key_line(theme, "j/k or Up/Down",  "Navigate"),
+        key_line(theme, "Enter",           "Execute selected tool"),
         Line::from(""),
-        Line::from(Span::styled("  [Esc / ?] Close", Style::default().fg(C_DIM))),
+        section_line(theme, "VPN tab"),
+        key_line(theme, "Enter",           "Execute selected action"),
+        key_line(theme, "r",               "Refresh status"),
+        key_line(theme, "l",               "Show latest openvpn log"),
+        Line::from(""),
+        Line::from(Span::styled("  [Esc / ?] Close", theme.dim)),
     ];
 
-    let p = Paragraph::new(lines).block(
-        Block::default()
-            .title(" Help ")
-            .title_style(Style::default().fg(C_BORDER).add_modifier(Modifier::BOLD))
-            .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(C_BORDER)),
-    );
+    let popup_area = centered_rect(60, (lines.len() as u16 + 2).min(area.height.saturating_sub(2)), area);
+    f.render_widget(Clear, popup_area);
+
+    let visible = popup_area.height.saturating_sub(2);
+    let max_scroll = (lines.len() as u16).saturating_sub(visible);
+    let scroll = scroll.min(max_scroll);
+
+    let mut block = Block::default()
+        .title(" Help ")
+        .title_style(theme.border.add_modifier(Modifier::BOLD))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.border);
+    if max_scroll > 0 {
+        block = block.title_bottom(
+            Line::from(Span::styled(
+                format!(" [j/k PgUp/PgDn g/G] Scroll {}/{} ", scroll, max_scroll),
+                theme.dim,
+            ))
+            .alignment(Alignment::Right),
+        );
+    }
+
+    let p = Paragraph::new(lines).scroll((scroll, 0)).block(block);
     f.render_widget(p, popup_area);
+    max_scroll
 }
 
-fn section_line(title: &'static str) -> Line<'static> {
+fn section_line(theme: &Theme, title: &'static str) -> Line<'static> {
     Line::from(Span::styled(
         format!("  {} ", title),
-        Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD),
+        theme.accent.add_modifier(Modifier::BOLD),
     ))
 }
 
-fn key_line(key: &'static str, desc: &'static str) -> Line<'static> {
+fn key_line(theme: &Theme, key: &'static str, desc: &'static str) -> Line<'static> {
     Line::from(vec![
-        Span::styled(format!("  {:<22}", key), Style::default().fg(C_TEXT)),
-        Span::styled(desc, Style::default().fg(C_DIM)),
+        Span::styled(format!("  {:<22}", key), theme.text),
+        Span::styled(desc, theme.dim),
     ])
 }
 
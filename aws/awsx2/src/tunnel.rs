@@ -2,12 +2,17 @@
 
 use std::io::{Read, Write};
 use std::net::TcpStream;
+use std::path::PathBuf;
 use std::process::{Child, Command, Stdio};
-use std::time::{Duration, Instant};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
 
 use crate::aws;
 use crate::error::{AppError, Result};
-use crate::models::{TunnelProcess, TunnelTarget};
+use crate::models::{BenchResult, Instance, TunnelInfo, TunnelProcess, TunnelStatus, TunnelTarget};
 
 // ── Port testing ──────────────────────────────────────────────────────────────
 
@@ -44,62 +49,556 @@ fn probe_remote(port: u16) -> Option<u64> {
     }
 }
 
-/// Wait for the SSM port to open, then probe the remote service.
+/// GET `path` through the tunnel on `port` and return the HTTP status code.
+/// Unlike `probe_remote`, this needs a real HTTP response, so it tells an app
+/// that serves apart from one that merely accepts connections.
+pub fn health_check(port: u16, path: &str, host: &str) -> Result<u16> {
+    let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+    let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(5))?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    stream.set_write_timeout(Some(Duration::from_secs(2)))?;
+    let path = if path.starts_with('/') { path.to_string() } else { format!("/{}", path) };
+    write!(stream, "GET {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n", path, host)?;
+    let mut buf = [0u8; 256];
+    let n = stream.read(&mut buf)?;
+    // Status line: `HTTP/1.1 200 OK`
+    String::from_utf8_lossy(&buf[..n])
+        .lines()
+        .next()
+        .filter(|l| l.starts_with("HTTP/"))
+        .and_then(|l| l.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| AppError::Tunnel(format!("GET {} did not get an HTTP response", path)))
+}
+
+/// One benchmark request: GET / through the tunnel, read the whole response.
+/// Returns (ms to first byte, bytes read), or None if the remote never answered.
+fn bench_request(port: u16) -> Option<(u64, u64)> {
+    let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+    let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(5)).ok()?;
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let _ = stream.set_write_timeout(Some(Duration::from_secs(2)));
+    let t0 = Instant::now();
+    stream.write_all(b"GET / HTTP/1.0\r\nHost: localhost\r\nConnection: close\r\n\r\n").ok()?;
+    let mut buf = [0u8; 64 * 1024];
+    let first = match stream.read(&mut buf) {
+        Ok(n) if n > 0 => n as u64,
+        _ => return None,
+    };
+    let ttfb = t0.elapsed().as_millis() as u64;
+    let mut total = first;
+    while let Ok(n) = stream.read(&mut buf) {
+        if n == 0 { break; }
+        total += n as u64;
+    }
+    Some((ttfb, total))
+}
+
+/// Consecutive failed requests after which a benchmark gives up.
+const BENCH_MAX_FAILURES_IN_A_ROW: u32 = 10;
+/// Pause after a failed benchmark request.
+const BENCH_FAILURE_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Send back-to-back HTTP requests through the tunnel on `port` for `duration`
+/// and report latency and throughput. Best suited to HTTP services; others
+/// usually answer with a banner or error page, which still gives latency.
+/// Failed requests are retried after a short pause, up to a limit in a row.
+pub fn benchmark_port(port: u16, duration: Duration) -> Result<BenchResult> {
+    if !test_port(port) {
+        return Err(AppError::PortClosed(port));
+    }
+    let start = Instant::now();
+    let (mut ok, mut failed, mut bytes) = (0u32, 0u32, 0u64);
+    let (mut min, mut max, mut sum) = (u64::MAX, 0u64, 0u64);
+    let mut failed_in_a_row = 0u32;
+    while start.elapsed() < duration {
+        match bench_request(port) {
+            Some((ms, n)) => {
+                ok += 1;
+                bytes += n;
+                min = min.min(ms);
+                max = max.max(ms);
+                sum += ms;
+                failed_in_a_row = 0;
+            }
+            None => {
+                failed += 1;
+                failed_in_a_row += 1;
+                // A refused or reset connection fails instantly; don't spin on it.
+                if failed_in_a_row >= BENCH_MAX_FAILURES_IN_A_ROW {
+                    return Err(AppError::Other(format!(
+                        "Benchmark of port {} stopped: {} requests in a row failed ({} succeeded)",
+                        port, failed_in_a_row, ok
+                    )));
+                }
+                std::thread::sleep(BENCH_FAILURE_BACKOFF);
+            }
+        }
+    }
+    if ok == 0 {
+        return Err(AppError::Other(format!(
+            "No responses through port {} ({} requests failed)", port, failed
+        )));
+    }
+    Ok(BenchResult {
+        requests: ok,
+        failures: failed,
+        min_ms: min,
+        max_ms: max,
+        avg_ms: sum / ok as u64,
+        bytes,
+        elapsed: start.elapsed(),
+    })
+}
+
+/// Wait for the SSM port to open, then probe the remote service. Returns the
+/// session's PID and the probe latency; the session is left running.
 /// Only kills the tunnel if the SSM session itself fails to open (port never binds).
 /// A silent remote (service down/restarting) is not a reason to tear down the tunnel.
-fn wait_and_probe(port: u16, pid: u32, timeout: Duration) -> Result<u64> {
-    if let Err(e) = wait_for_port(port, timeout) {
-        stop_tunnel(pid);
-        return Err(e);
-    }
+fn wait_and_probe(port: u16, child: Child, timeout: Duration) -> Result<(u32, u64)> {
+    let pid = wait_for_port(port, child, timeout).map_err(|(e, _)| e)?;
     // Remote probe is best-effort: service may be temporarily unavailable.
     // Keep the tunnel alive regardless — it will work once the service is back.
-    Ok(probe_remote(port).unwrap_or(0))
+    Ok((pid, probe_remote(port).unwrap_or(0)))
 }
 
-fn wait_for_port(port: u16, timeout: Duration) -> Result<()> {
+/// Wait for a start-session process to open `port` and return its pid. On
+/// failure the flag is true when the port just never opened in time (a slow
+/// SSM handshake, worth another go) rather than the session exiting.
+fn wait_for_port(port: u16, mut child: Child, timeout: Duration) -> std::result::Result<u32, (AppError, bool)> {
+    let pid = child.id();
+    let stderr = stderr_lines(&mut child);
     let start = Instant::now();
-    while start.elapsed() < timeout {
-        if test_port(port) { return Ok(()); }
+    while !test_port(port) {
+        if let Ok(Some(_)) = child.try_wait() {
+            return Err((session_exit_error(&stderr, None), false));
+        }
+        if start.elapsed() >= timeout {
+            stop_tunnel(pid);
+            return Err((session_exit_error(&stderr, Some(port)), true));
+        }
         std::thread::sleep(Duration::from_millis(500));
     }
-    Err(AppError::PortClosed(port))
+    // The session outlives this process; don't reap it.
+    std::mem::forget(child);
+    Ok(pid)
 }
 
-// ── Detect running tunnels ────────────────────────────────────────────────────
+/// How many trailing stderr lines a failed session reports.
+const SESSION_ERROR_LINES: usize = 3;
 
-pub fn detect_tunnels() -> Vec<TunnelProcess> {
-    let out = match Command::new("ps").args(["-ww", "-eo", "pid,args"]).output() {
-        Ok(o) => o,
-        Err(_) => return vec![],
-    };
-    let stdout = String::from_utf8_lossy(&out.stdout);
-    let mut tunnels = Vec::new();
-
-    for line in stdout.lines() {
-        if !line.contains("session-manager-plugin") { continue; }
-        let pid_str = line.trim().split_whitespace().next().unwrap_or("");
-        let pid: u32 = match pid_str.parse() { Ok(p) => p, Err(_) => continue };
-        if let Some(tp) = parse_tunnel_line(line, pid) {
-            tunnels.push(tp);
+/// Forward the session's stderr lines from a thread, so they can be read
+/// without blocking on a process that is still running. The thread keeps
+/// draining to EOF once nobody listens, so a session that later writes to
+/// stderr never hits a full or closed pipe.
+fn stderr_lines(child: &mut Child) -> std::sync::mpsc::Receiver<String> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    if let Some(err) = child.stderr.take() {
+        std::thread::spawn(move || {
+            use std::io::BufRead;
+            for line in std::io::BufReader::new(err).lines().map_while(|l| l.ok()) {
+                let _ = tx.send(line);
+            }
+        });
+    }
+    rx
+}
+
+/// Why a start-session process failed to open its port: the last lines of its
+/// stderr (e.g. "i-… is not connected", AccessDenied). `timed_out` is the port
+/// when it never opened, rather than the process exiting. The aws CLI accepts
+/// the session without the plugin and only then gives up, so that is caught here.
+fn session_exit_error(stderr: &std::sync::mpsc::Receiver<String>, timed_out: Option<u16>) -> AppError {
+    // Give the reader a moment to catch the final lines of a dying process.
+    let mut lines = Vec::new();
+    while let Ok(line) = stderr.recv_timeout(Duration::from_millis(300)) {
+        lines.push(line);
+    }
+    let text = lines.join("\n");
+    if text.contains("SessionManagerPlugin is not found") {
+        return AppError::MissingDependency(aws::SSM_PLUGIN.into());
+    }
+    let tail: Vec<&str> = lines.iter().map(|l| l.trim()).filter(|l| !l.is_empty()).collect();
+    let tail = tail[tail.len().saturating_sub(SESSION_ERROR_LINES)..].join(" | ");
+    match (timed_out, tail.is_empty()) {
+        (Some(port), true) => AppError::PortClosed(port),
+        (Some(port), false) => AppError::Tunnel(format!("port {} never opened: {}", port, tail)),
+        (None, true) => AppError::Tunnel("SSM session exited before the port opened".into()),
+        (None, false) => AppError::Tunnel(format!("SSM session exited: {}", tail)),
+    }
+}
+
+// ── Tunnel registry (tunnels.json) ────────────────────────────────────────────
+//
+// `ps` only tells us ports, instance IDs and raw hosts. The registry remembers
+// what each tunnel was started for (instance Name tag, bastion name) so that a
+// restarted TUI can show the same friendly names, and so that a dropped tunnel
+// can be re-established with `restart_tunnel`.
+
+/// How long the entry of a dead tunnel is kept around for `tunnel-restart`.
+const RESTARTABLE_SECS: u64 = 24 * 3600;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RegistryEntry {
+    /// PID of the `aws ssm start-session` process (parent of the plugin).
+    pid: u32,
+    local_port: u16,
+    remote_port: u16,
+    remote_host: Option<String>,
+    instance_id: String,
+    instance_name: String,
+    /// Unix seconds.
+    created_at: u64,
+    /// SSM document and parameters JSON the session was started with, so a
+    /// restart reuses a one-off `--document`.
+    document: Option<String>,
+    parameters: Option<String>,
+    /// `--bind` forwarder in front of the tunnel, as (address, listen port).
+    #[serde(default)]
+    bind: Option<(String, u16)>,
+    /// `--proxy` hostname pointing at the tunnel, and whether it serves HTTPS.
+    #[serde(default)]
+    proxy: Option<(String, bool)>,
+}
+
+/// Serializes read-modify-write of tunnels.json across worker threads.
+static REGISTRY_LOCK: Mutex<()> = Mutex::new(());
+
+fn registry_path() -> PathBuf {
+    crate::config::config_dir().join("tunnels.json")
+}
+
+pub fn pid_alive(pid: u32) -> bool {
+    #[cfg(unix)]
+    { unsafe { libc::kill(pid as libc::pid_t, 0) == 0 } }
+    #[cfg(not(unix))]
+    { let _ = pid; true }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Load the registry, dropping entries whose process is gone, unless they are
+/// recent enough to still be worth restarting.
+fn load_registry() -> Vec<RegistryEntry> {
+    let entries: Vec<RegistryEntry> = std::fs::read_to_string(registry_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
+    let now = now_secs();
+    entries
+        .into_iter()
+        .filter(|e| pid_alive(e.pid) || now.saturating_sub(e.created_at) < RESTARTABLE_SECS)
+        .collect()
+}
+
+fn save_registry(entries: &[RegistryEntry]) {
+    let path = registry_path();
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(entries) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+/// Record a freshly started tunnel and hand it back.
+fn registered(tp: TunnelProcess) -> TunnelProcess {
+    let _guard = REGISTRY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut entries = load_registry();
+    entries.retain(|e| e.local_port != tp.local_port);
+    entries.push(RegistryEntry {
+        pid: tp.pid,
+        local_port: tp.local_port,
+        remote_port: tp.remote_port,
+        remote_host: tp.remote_host.clone(),
+        instance_id: tp.instance_id.clone(),
+        instance_name: tp.instance_name.clone(),
+        created_at: now_secs(),
+        document: tp.document.clone(),
+        parameters: tp.parameters.clone(),
+        bind: None,
+        proxy: None,
+    });
+    save_registry(&entries);
+    crate::audit::record("tunnel", &tp.instance_id, Some(&tunnel_detail(&tp)), None, &Ok(()));
+    if keepalive_enabled() {
+        spawn_keepalive(tp.local_port, tp.pid);
+    }
+    tp
+}
+
+/// `localhost:8080 -> web-1:8000` for the audit log.
+pub fn tunnel_detail(tp: &TunnelProcess) -> String {
+    let remote = tp.remote_host.as_deref().unwrap_or(&tp.instance_name);
+    forward_detail(tp.local_port, remote, tp.remote_port)
+}
+
+fn forward_detail(local_port: u16, remote: &str, remote_port: u16) -> String {
+    format!("localhost:{} -> {}:{}", local_port, remote, remote_port)
+}
+
+/// Audit a tunnel that failed to start; `registered` audits the ones that did.
+fn audit_failure<T>(target: &str, detail: &str, result: Result<T>) -> Result<T> {
+    if result.is_err() {
+        crate::audit::record("tunnel", target, Some(detail), None, &result);
+    }
+    result
+}
+
+/// Restore friendly names on `ps`-detected tunnels from the registry, and
+/// rewrite the registry without dead entries.
+fn merge_registry(tunnels: &mut [TunnelProcess]) {
+    let _guard = REGISTRY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let entries = load_registry();
+    for tp in tunnels.iter_mut() {
+        // The registry holds the aws CLI pid while ps reports the plugin's,
+        // so match on the (unique) local port instead.
+        let entry = entries.iter().find(|e| {
+            e.local_port == tp.local_port
+                && e.remote_port == tp.remote_port
+                && (tp.instance_id.is_empty() || e.instance_id == tp.instance_id)
+        });
+        if let Some(e) = entry {
+            tp.instance_name = e.instance_name.clone();
+            if tp.instance_id.is_empty() { tp.instance_id = e.instance_id.clone(); }
+            if tp.remote_host.is_none() { tp.remote_host = e.remote_host.clone(); }
         }
     }
+    save_registry(&entries);
+}
+
+/// Registered tunnels with no live session on their local port. Returned as
+/// DOWN rows with `pid == 0` so they can be restarted or forgotten.
+pub fn dead_tunnels(live: &[TunnelProcess]) -> Vec<TunnelProcess> {
+    let _guard = REGISTRY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    load_registry()
+        .into_iter()
+        .filter(|e| !live.iter().any(|t| t.local_port == e.local_port))
+        .map(|e| TunnelProcess {
+            pid: 0,
+            local_port: e.local_port,
+            remote_port: e.remote_port,
+            remote_host: e.remote_host,
+            instance_id: e.instance_id,
+            instance_name: e.instance_name,
+            port_open: false,
+            latency_ms: None,
+            document: e.document,
+            parameters: e.parameters,
+        })
+        .collect()
+}
+
+/// Set each instance's `tunnel` from `tunnels`, matched by the instance the
+/// session runs on, so a tunnel through a bastion shows on the bastion's row.
+/// With several tunnels on one instance, the first live one wins.
+pub fn attach_tunnels(instances: &mut [Instance], tunnels: &[TunnelProcess]) {
+    for inst in instances {
+        let mine: Vec<&TunnelProcess> = tunnels.iter().filter(|t| t.instance_id == inst.id).collect();
+        let shown = mine.iter().find(|t| t.port_open).or(mine.first());
+        inst.tunnel = shown.map(|t| TunnelInfo {
+            local_port: t.local_port,
+            remote_port: t.remote_port,
+            remote_host: t.remote_host.clone(),
+            status: if t.port_open { TunnelStatus::Active } else { TunnelStatus::Down },
+        });
+    }
+}
+
+/// Drop the registry entry for a local port (used when dismissing a dead tunnel).
+pub fn forget_tunnel(local_port: u16) {
+    let _guard = REGISTRY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut entries = load_registry();
+    entries.retain(|e| e.local_port != local_port);
+    save_registry(&entries);
+}
+
+/// Apply `f` to the registry entry of the tunnel on `local_port`, if any.
+fn update_entry(local_port: u16, f: impl FnOnce(&mut RegistryEntry)) {
+    let _guard = REGISTRY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut entries = load_registry();
+    if let Some(entry) = entries.iter_mut().find(|e| e.local_port == local_port) {
+        f(entry);
+        save_registry(&entries);
+    }
+}
+
+/// Remember that a `--proxy` for `hostname` points at the tunnel on
+/// `local_port`, so `restart_tunnel` can tell whether it is still attached.
+pub fn record_proxy(local_port: u16, hostname: &str, tls: bool) {
+    update_entry(local_port, |e| e.proxy = Some((hostname.to_string(), tls)));
+}
+
+/// Re-establish a registered tunnel with the parameters it was started with,
+/// including its `--bind` forwarder. `progress` hears about the forwarder and
+/// about a `--proxy` that is no longer attached (re-adding one needs sudo, so
+/// that is left to the user).
+pub fn restart_tunnel(local_port: u16, profile: Option<&str>, progress: impl FnMut(&str)) -> Result<TunnelProcess> {
+    let entry = {
+        let _guard = REGISTRY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        load_registry().into_iter().find(|e| e.local_port == local_port)
+    }
+    .ok_or_else(|| AppError::Tunnel(format!("No recorded tunnel on local port {}", local_port)))?;
+
+    let remote = entry.remote_host.as_deref().unwrap_or(&entry.instance_name);
+    let detail = forward_detail(local_port, remote, entry.remote_port);
+    audit_failure(&entry.instance_id, &detail, restartable(&entry, profile))?;
+    let kind = if entry.remote_host.is_some() { DOC_REMOTE } else { DOC_DIRECT };
+    let choice = recorded_document(kind, entry.document.as_deref(), entry.parameters.as_deref());
+    let tp = open_tunnel(
+        &entry.instance_id, &entry.instance_name, entry.remote_host.as_deref(),
+        local_port, entry.remote_port, &choice, profile,
+    )?;
+    // `registered` wrote a fresh entry; keep what was attached for the next restart.
+    update_entry(local_port, |e| {
+        e.bind = entry.bind.clone();
+        e.proxy = entry.proxy.clone();
+    });
+    restore_attachments(&entry, progress);
+    Ok(tp)
+}
+
+/// Restart the `--bind` forwarder of a restarted tunnel if it is gone, and
+/// report a recorded `--proxy` that no longer points at it.
+fn restore_attachments(entry: &RegistryEntry, mut progress: impl FnMut(&str)) {
+    let local_port = entry.local_port;
+    if let Some((addr, listen)) = &entry.bind {
+        if !bind_forwarders().iter().any(|f| f.1 == *listen && f.2 == local_port) {
+            match start_bind_forwarder(addr, *listen, local_port) {
+                Ok(pid) => progress(&format!("Restored forwarder {}:{} (pid {})", addr, listen, pid)),
+                Err(e) => progress(&format!("Forwarder {}:{} was not restored: {}", addr, listen, e)),
+            }
+        }
+    }
+    if let Some((host, tls)) = &entry.proxy {
+        let attached = ports_for(local_port)
+            .into_iter()
+            .any(|p| crate::proxy::proxy_for_port(p).is_some_and(|(h, _)| &h == host));
+        if !attached {
+            progress(&format!(
+                "Reverse proxy for {} was not restored; start the tunnel again with --proxy{}",
+                host, if *tls { " --tls" } else { "" }
+            ));
+        }
+    }
+}
+
+/// Why `entry` cannot be restarted right now, if anything.
+fn restartable(entry: &RegistryEntry, profile: Option<&str>) -> Result<()> {
+    let local_port = entry.local_port;
+    if test_port(local_port) {
+        return Err(AppError::Tunnel(format!(
+            "Port {} is still in use — stop the old tunnel first", local_port
+        )));
+    }
+
+    aws::invalidate_cache();
+    let inst = aws::list_instances_cached(profile)?
+        .into_iter()
+        .find(|i| i.id == entry.instance_id)
+        .ok_or_else(|| AppError::NoInstance(format!("{} ({})", entry.instance_name, entry.instance_id)))?;
+    if inst.state != crate::models::InstanceState::Running {
+        return Err(AppError::Tunnel(format!(
+            "{} ({}) is {} — start it before restarting the tunnel",
+            inst.name, inst.id, inst.state.as_str()
+        )));
+    }
+    Ok(())
+}
+
+// ── Detect running tunnels ────────────────────────────────────────────────────
+
+/// Running `session-manager-plugin` processes as (pid, joined argv).
+fn plugin_processes() -> Vec<(u32, String)> {
+    processes_matching("session-manager-plugin")
+}
+
+/// `--bind` forwarders (see `start_bind_forwarder`) as (pid, listen port, target port).
+fn bind_forwarders() -> Vec<(u32, u16, u16)> {
+    processes_matching("socat")
+        .into_iter()
+        // The program itself, not e.g. a shell whose command line mentions socat.
+        .filter(|(_, argv)| argv.split(' ').next().is_some_and(|a| a.rsplit('/').next() == Some("socat")))
+        .filter_map(|(pid, argv)| {
+            let port_after = |prefix: &str| {
+                let rest = &argv[argv.find(prefix)? + prefix.len()..];
+                let end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+                rest[..end].parse::<u16>().ok()
+            };
+            Some((pid, port_after("TCP-LISTEN:")?, port_after("TCP:127.0.0.1:")?))
+        })
+        .collect()
+}
+
+/// `local_port` followed by the listen ports of any `--bind` forwarders in
+/// front of it (those are what a reverse proxy points at).
+pub fn ports_for(local_port: u16) -> Vec<u16> {
+    let mut ports = vec![local_port];
+    ports.extend(bind_forwarders().into_iter().filter(|f| f.2 == local_port).map(|f| f.1));
+    ports
+}
+
+/// Running processes whose argv contains `needle`, as (pid, joined argv).
+/// sysinfo reads the full argv from the OS, so nothing is truncated the way
+/// `ps` output columns can be, and it works the same on Linux/macOS/Windows.
+fn processes_matching(needle: &str) -> Vec<(u32, String)> {
+    use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_cmd(UpdateKind::Always),
+    );
+
+    let mut procs: Vec<(u32, String)> = sys
+        .processes()
+        .iter()
+        .filter_map(|(pid, p)| {
+            let argv = p.cmd()
+                .iter()
+                .map(|a| a.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" ");
+            argv.contains(needle).then(|| (pid.as_u32(), argv))
+        })
+        .collect();
+    procs.sort_by_key(|(pid, _)| *pid);
+    procs
+}
+
+pub fn detect_tunnels() -> Vec<TunnelProcess> {
+    let mut tunnels: Vec<TunnelProcess> = plugin_processes()
+        .iter()
+        .filter_map(|(pid, line)| parse_tunnel_line(line, *pid))
+        .collect();
+    merge_registry(&mut tunnels);
     tunnels
 }
 
 fn parse_tunnel_line(line: &str, pid: u32) -> Option<TunnelProcess> {
-    let after = line.splitn(2, "session-manager-plugin").nth(1)?;
+    let after = line.split_once("session-manager-plugin")?.1;
 
     let mut local_port: u16 = 0;
     let mut remote_port: u16 = 0;
     let mut remote_host: Option<String> = None;
     let mut instance_id = String::new();
+    let mut document: Option<String> = None;
+    let mut parameters: Option<String> = None;
 
     for json_str in extract_json_objects(after) {
         if let Ok(val) = serde_json::from_str::<serde_json::Value>(&json_str) {
             if let Some(t) = val.get("Target").and_then(|v| v.as_str()) {
                 instance_id = t.to_string();
             }
+            if let Some(d) = val.get("DocumentName").and_then(|v| v.as_str()) {
+                document = Some(d.to_string());
+            }
+            if let Some(p) = val.get("Parameters") {
+                parameters = Some(p.to_string());
+            }
             // Parameters may be at the top level OR nested under "Parameters"
             let params = val.get("Parameters").unwrap_or(&val);
             if let Some(arr) = params.get("localPortNumber").and_then(|v| v.as_array()) {
@@ -125,7 +624,10 @@ fn parse_tunnel_line(line: &str, pid: u32) -> Option<TunnelProcess> {
     let instance_name = remote_host.clone().unwrap_or_else(|| instance_id.clone());
     let port_open = test_port(local_port);
     let latency_ms = if port_open { probe_remote(local_port) } else { None };
-    Some(TunnelProcess { pid, local_port, remote_port, remote_host, instance_id, instance_name, port_open, latency_ms })
+    Some(TunnelProcess {
+        pid, local_port, remote_port, remote_host, instance_id, instance_name, port_open, latency_ms,
+        document, parameters,
+    })
 }
 
 fn extract_json_objects(s: &str) -> Vec<String> {
@@ -157,42 +659,212 @@ fn make_ssm_cmd(instance_id: &str, doc_name: &str, params: &str, profile: Option
         .map(|s| s.to_string())
         .or_else(|| std::env::var("AWS_PROFILE").ok().filter(|s| !s.is_empty()));
     if let Some(p) = p { cmd.args(["--profile", &p]); }
+    if let Some(r) = aws::region_override() { cmd.args(["--region", &r]); }
     cmd.args(["ssm", "start-session",
         "--target", instance_id,
         "--document-name", doc_name,
         "--parameters", params]);
-    cmd.stdout(Stdio::piped());
+    // Only stderr says why a session failed; stdout is never read.
+    cmd.stdout(Stdio::null());
     cmd.stderr(Stdio::piped());
     cmd
 }
 
+/// Spawn a start-session command; `--dry-run` prints it and stops here.
+fn spawn_ssm(mut cmd: Command) -> Result<Child> {
+    if aws::dry_run_skip(&cmd) {
+        return Err(AppError::DryRun);
+    }
+    aws::log_command(&cmd);
+    cmd.spawn().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => AppError::MissingDependency("aws".into()),
+        _ => AppError::Io(e),
+    })
+}
+
 // ── Start tunnels ─────────────────────────────────────────────────────────────
 
-pub fn start_direct_tunnel(
-    instance_id: &str,
+const DOC_DIRECT: &str = "AWS-StartPortForwardingSession";
+const DOC_REMOTE: &str = "AWS-StartPortForwardingSessionToRemoteHost";
+
+/// Build the `--parameters` JSON for a port-forwarding document, rejecting
+/// host/document mismatches before SSM gets a chance to fail cryptically.
+fn forward_params(doc_name: &str, host: Option<&str>, local_port: u16, remote_port: u16) -> Result<String> {
+    let host = host.map(str::trim);
+    match (doc_name, host) {
+        (DOC_DIRECT, Some(h)) => Err(AppError::Tunnel(format!(
+            "{} forwards to the instance itself and does not take a host (got '{}')",
+            DOC_DIRECT, h
+        ))),
+        (DOC_REMOTE, None) | (DOC_REMOTE, Some("")) => Err(AppError::Tunnel(format!(
+            "{} requires a remote host", DOC_REMOTE
+        ))),
+        (DOC_DIRECT, None) => Ok(format!(
+            r#"{{"portNumber":["{}"],"localPortNumber":["{}"]}}"#,
+            remote_port, local_port
+        )),
+        (DOC_REMOTE, Some(h)) => Ok(format!(
+            r#"{{"host":["{}"],"portNumber":["{}"],"localPortNumber":["{}"]}}"#,
+            h, remote_port, local_port
+        )),
+        _ => Err(AppError::Tunnel(format!("Unsupported SSM document: {}", doc_name))),
+    }
+}
+
+/// A spawned start-session and the document/parameters it runs with.
+struct Session {
+    child: Child,
+    document: String,
+    parameters: String,
+}
+
+fn start_remote_tunnel(
+    bastion_id: &str,
+    host: &str,
     local_port: u16,
     remote_port: u16,
     profile: Option<&str>,
-) -> Result<Child> {
-    let params = format!(
-        r#"{{"portNumber":["{}"],"localPortNumber":["{}"]}}"#,
-        remote_port, local_port
-    );
-    Ok(make_ssm_cmd(instance_id, "AWS-StartPortForwardingSession", &params, profile).spawn()?)
+) -> Result<Session> {
+    start_session(bastion_id, Some(host), local_port, remote_port, &current_document(), profile)
 }
 
-pub fn start_remote_tunnel(
-    bastion_id: &str,
-    host: &str,
+/// Start a session on `target`, forwarding to `host` through it when given.
+fn start_session(
+    target: &str,
+    host: Option<&str>,
     local_port: u16,
     remote_port: u16,
+    choice: &DocumentChoice,
     profile: Option<&str>,
-) -> Result<Child> {
-    let params = format!(
-        r#"{{"host":["{}"],"portNumber":["{}"],"localPortNumber":["{}"]}}"#,
-        host, remote_port, local_port
-    );
-    Ok(make_ssm_cmd(bastion_id, "AWS-StartPortForwardingSessionToRemoteHost", &params, profile).spawn()?)
+) -> Result<Session> {
+    let kind = if host.is_some() { DOC_REMOTE } else { DOC_DIRECT };
+    let params = forward_params(kind, host, local_port, remote_port)?;
+    let (document, parameters) = session_document(kind, &params, choice, profile)?;
+    let child = spawn_ssm(make_ssm_cmd(target, &document, &parameters, profile))?;
+    Ok(Session { child, document, parameters })
+}
+
+/// Start a session on `instance_id`, wait for its port and register the
+/// tunnel. A failure is audited along the way.
+fn open_tunnel(
+    instance_id: &str,
+    instance_name: &str,
+    host: Option<&str>,
+    local_port: u16,
+    remote_port: u16,
+    choice: &DocumentChoice,
+    profile: Option<&str>,
+) -> Result<TunnelProcess> {
+    let started = start_session(instance_id, host, local_port, remote_port, choice, profile).and_then(|s| {
+        let (pid, latency_ms) = wait_and_probe(local_port, s.child, Duration::from_secs(20))?;
+        Ok(TunnelProcess {
+            pid, local_port, remote_port,
+            remote_host: host.map(str::to_string),
+            instance_id: instance_id.to_string(),
+            instance_name: instance_name.to_string(),
+            port_open: true, latency_ms: Some(latency_ms),
+            document: Some(s.document), parameters: Some(s.parameters),
+        })
+    });
+    let detail = forward_detail(local_port, host.unwrap_or(instance_name), remote_port);
+    audit_failure(instance_id, &detail, started).map(registered)
+}
+
+// ── Custom SSM documents ──────────────────────────────────────────────────────
+
+/// `--document` / `--document-param` from the tunnel commands, or the
+/// document a registered tunnel was started with.
+#[derive(Clone, Default)]
+struct DocumentChoice {
+    name: Option<String>,
+    extra: Vec<(String, String)>,
+}
+
+static DOCUMENT: Mutex<DocumentChoice> = Mutex::new(DocumentChoice { name: None, extra: Vec::new() });
+
+/// Use `name` (when given) instead of the configured or AWS-managed document
+/// for sessions started by this process, adding `extra` parameters.
+pub fn set_document(name: Option<String>, extra: Vec<(String, String)>) {
+    *DOCUMENT.lock().unwrap_or_else(|e| e.into_inner()) = DocumentChoice { name, extra };
+}
+
+fn current_document() -> DocumentChoice {
+    DOCUMENT.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// The choice that recreates a session recorded as `document` + `parameters`
+/// JSON: the same document with the same extra parameters.
+fn recorded_document(kind: &str, document: Option<&str>, parameters: Option<&str>) -> DocumentChoice {
+    let params: serde_json::Map<String, serde_json::Value> =
+        parameters.and_then(|p| serde_json::from_str(p).ok()).unwrap_or_default();
+    let extra = params
+        .into_iter()
+        .filter(|(k, _)| !filled_params(kind).contains(&k.as_str()))
+        .filter_map(|(k, v)| Some((k, v.get(0)?.as_str()?.to_string())))
+        .collect();
+    DocumentChoice { name: document.map(str::to_string), extra }
+}
+
+/// Parameters awsx2 sets itself for a session of this kind.
+fn filled_params(kind: &str) -> &'static [&'static str] {
+    if kind == DOC_REMOTE { &["localPortNumber", "host", "portNumber"] } else { &["localPortNumber", "portNumber"] }
+}
+
+/// The document and parameters JSON for a session of `kind` (`DOC_DIRECT` or
+/// `DOC_REMOTE`). A custom document — from `choice`, else `ssm_document` /
+/// `ssm_remote_document` from config.toml — must declare every parameter
+/// awsx2 fills in and every extra one it is given, which is checked with
+/// `ssm describe-document` before the session starts.
+fn session_document(
+    kind: &str,
+    params: &str,
+    choice: &DocumentChoice,
+    profile: Option<&str>,
+) -> Result<(String, String)> {
+    let settings = crate::config::settings();
+    let configured = if kind == DOC_REMOTE { &settings.ssm_remote_document } else { &settings.ssm_document };
+    let name = choice.name.clone().or_else(|| configured.clone());
+    let cli_extra = choice.extra.clone();
+    let Some(name) = name.filter(|n| n != kind) else {
+        if !cli_extra.is_empty() {
+            return Err(AppError::Tunnel(format!(
+                "--document-param needs a custom --document; {} takes no extra parameters", kind
+            )));
+        }
+        return Ok((kind.to_string(), params.to_string()));
+    };
+
+    let mut extra: Vec<(String, String)> = settings
+        .ssm_document_parameters
+        .iter()
+        .filter(|(k, _)| !cli_extra.iter().any(|(ck, _)| ck == *k))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    extra.extend(cli_extra);
+
+    let declared = aws::document_parameter_names(&name, profile)?;
+    for p in filled_params(kind) {
+        if !declared.iter().any(|d| d == p) {
+            return Err(AppError::Tunnel(format!(
+                "SSM document {} has no `{}` parameter, which awsx2 needs to set. \
+                 Is it a port-forwarding document{}?",
+                name, p, if kind == DOC_REMOTE { " to a remote host" } else { "" }
+            )));
+        }
+    }
+    let mut map: serde_json::Map<String, serde_json::Value> = serde_json::from_str(params)?;
+    for (k, v) in extra {
+        if filled_params(kind).contains(&k.as_str()) {
+            return Err(AppError::Tunnel(format!("`{}` is set by awsx2 and cannot be overridden", k)));
+        }
+        if !declared.contains(&k) {
+            return Err(AppError::Tunnel(format!(
+                "SSM document {} has no `{}` parameter (it takes: {})", name, k, declared.join(", ")
+            )));
+        }
+        map.insert(k, serde_json::json!([v]));
+    }
+    Ok((name, serde_json::Value::Object(map).to_string()))
 }
 
 // ── High-level tunnel creation ────────────────────────────────────────────────
@@ -203,114 +875,207 @@ pub fn start_tunnel_by_pattern(
     remote_port: u16,
     profile: Option<&str>,
 ) -> Result<TunnelProcess> {
-    let inst = aws::find_instance_by_name(pattern, profile)?;
-    let child = start_direct_tunnel(&inst.id, local_port, remote_port, profile)?;
-    let pid = child.id();
-    std::mem::forget(child);
-    let latency_ms = wait_and_probe(local_port, pid, Duration::from_secs(20))?;
-    Ok(TunnelProcess {
-        pid, local_port, remote_port, remote_host: None,
-        instance_id: inst.id, instance_name: inst.name,
-        port_open: true, latency_ms: Some(latency_ms),
+    let detail = forward_detail(local_port, pattern, remote_port);
+    let inst = audit_failure(pattern, &detail, aws::find_instance_by_name(pattern, profile))?;
+    start_tunnel_to_instance(&inst, local_port, remote_port, profile)
+}
+
+fn start_tunnel_to_instance(
+    inst: &Instance,
+    local_port: u16,
+    remote_port: u16,
+    profile: Option<&str>,
+) -> Result<TunnelProcess> {
+    open_tunnel(&inst.id, &inst.name, None, local_port, remote_port, &current_document(), profile)
+}
+
+/// Open a direct tunnel to each `(instance, local_port)` pair at once. The
+/// sessions start in parallel, so the whole batch takes about as long as the
+/// slowest one; results come back in input order.
+pub fn start_tunnels_to_instances(
+    targets: &[(Instance, u16)],
+    remote_port: u16,
+    profile: Option<&str>,
+) -> Vec<Result<TunnelProcess>> {
+    std::thread::scope(|s| {
+        let handles: Vec<_> = targets
+            .iter()
+            .map(|(inst, port)| s.spawn(move || start_tunnel_to_instance(inst, *port, remote_port, profile)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap_or_else(|_| Err(AppError::Tunnel("tunnel thread panicked".into()))))
+            .collect()
     })
 }
 
+/// Sessions tried per bastion before moving on, when the port just never opens.
+const BASTION_ATTEMPTS: u32 = 2;
+
+/// Tunnel to `url` through the first SSM-online bastion that opens a session.
+/// With several bastions they are pre-ranked by SSM round trip so the fastest
+/// is tried first. A bastion whose port never opens gets another try; one that
+/// SSM refuses, or that cannot reach the target, is skipped for the next.
+/// `progress` receives one-line status updates.
 pub fn start_url_tunnel_via_any_bastion(
     url: &str,
     local_port: u16,
     remote_port: Option<u16>,
     profile: Option<&str>,
+    progress: impl FnMut(&str),
+) -> Result<TunnelProcess> {
+    let result = tunnel_via_any_bastion(url, local_port, remote_port, profile, progress);
+    audit_failure(url, &format!("localhost:{} -> {}", local_port, url), result)
+}
+
+fn tunnel_via_any_bastion(
+    url: &str,
+    local_port: u16,
+    remote_port: Option<u16>,
+    profile: Option<&str>,
+    mut progress: impl FnMut(&str),
 ) -> Result<TunnelProcess> {
     let host = aws::strip_url_to_host(url);
     let bastions = aws::find_bastions(profile)?;
     let online_bastions: Vec<_> = bastions.into_iter().filter(|b| b.ssm_online).collect();
     if online_bastions.is_empty() { return Err(AppError::NoBastions); }
 
+    let ranked = if online_bastions.len() > 1 {
+        let target_az = aws::target_availability_zone(&host, profile);
+        if let Some(az) = &target_az {
+            progress(&format!("{} is in {}; preferring bastions there", host, az));
+        }
+        progress(&format!("Ranking {} bastions by SSM round trip...", online_bastions.len()));
+        let ranked = aws::rank_bastions(online_bastions, target_az.as_deref(), profile);
+        progress(&format!(
+            "Bastion order: {}",
+            ranked
+                .iter()
+                .map(|(b, rtt)| {
+                    let az = b.availability_zone.as_deref().unwrap_or("?");
+                    match rtt {
+                        Some(d) => format!("{} ({}, {:.1}s)", b.name, az, d.as_secs_f64()),
+                        None => format!("{} ({}, no answer)", b.name, az),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        ranked
+    } else {
+        online_bastions.into_iter().map(|b| (b, None)).collect()
+    };
+
     // Determine remote port: explicit > auto-detect > scheme default
     let remote_port = match remote_port {
         Some(rp) => rp,
         None => {
             let default_port: u16 = if url.starts_with("https://") { 443 } else { 80 };
-            println!("\x1b[90m  Auto-detecting port on {}...\x1b[0m", host);
+            progress(&format!("Auto-detecting port on {}...", host));
             match aws::probe_ports_via_bastion(
-                &online_bastions[0].id, &host, aws::COMMON_PORTS, profile,
+                &ranked[0].0.id, &host, aws::COMMON_PORTS, profile,
             ) {
                 Ok(ref open) if !open.is_empty() => {
-                    println!(
-                        "\x1b[90m  Open ports: {}\x1b[0m",
+                    progress(&format!(
+                        "Open ports: {}",
                         open.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", ")
-                    );
+                    ));
                     if open.contains(&default_port) { default_port } else { open[0] }
                 }
                 Ok(_) => {
-                    println!("\x1b[90m  No open ports found, falling back to {}\x1b[0m", default_port);
+                    progress(&format!("No open ports found, falling back to {}", default_port));
                     default_port
                 }
                 Err(e) => {
-                    println!("\x1b[90m  Port probe failed ({}), falling back to {}\x1b[0m", e, default_port);
+                    progress(&format!("Port probe failed ({}), falling back to {}", e, default_port));
                     default_port
                 }
             }
         }
     };
 
-    for bastion in &online_bastions {
-        let child = start_remote_tunnel(&bastion.id, &host, local_port, remote_port, profile)?;
-        let pid = child.id();
-        std::mem::forget(child);
-        // wait_and_probe only fails if the SSM session itself never opened.
-        // Remote-service unavailability is tolerated — the tunnel stays alive.
-        match wait_and_probe(local_port, pid, Duration::from_secs(10)) {
-            Ok(latency_ms) => {
-                return Ok(TunnelProcess {
+    let mut failures = Vec::new();
+    for (i, (bastion, rtt)) in ranked.iter().enumerate() {
+        progress(&match (i, rtt) {
+            (0, Some(d)) if ranked.len() > 1 => {
+                format!("Trying {} ({:.1}s round trip)...", bastion.name, d.as_secs_f64())
+            }
+            (0, _) => format!("Trying {}...", bastion.name),
+            _ => format!("Trying next bastion {}...", bastion.name),
+        });
+        let last = i + 1 == ranked.len();
+        for attempt in 1..=BASTION_ATTEMPTS {
+            let Session { child, document, parameters } = start_remote_tunnel(&bastion.id, &host, local_port, remote_port, profile)?;
+            let latency_ms = match wait_for_port(local_port, child, Duration::from_secs(10)) {
+                Ok(pid) => match probe_remote(local_port) {
+                    Some(ms) => Some((pid, ms)),
+                    // Another bastion may have the route this one lacks. With
+                    // none left, keep the tunnel: the service may just be down.
+                    None if !last => {
+                        stop_tunnel(pid);
+                        progress(&format!(
+                            "{}:{} did not answer through {}; moving on", host, remote_port, bastion.name
+                        ));
+                        failures.push(format!("{}: {}:{} unreachable", bastion.name, host, remote_port));
+                        break;
+                    }
+                    None => {
+                        progress(&format!("{}:{} did not answer yet; keeping the tunnel", host, remote_port));
+                        Some((pid, 0))
+                    }
+                },
+                // Every other bastion would fail the same way.
+                Err((e @ AppError::MissingDependency(_), _)) => return Err(e),
+                // The session may just have been slow to come up — go again.
+                Err((e, true)) if attempt < BASTION_ATTEMPTS => {
+                    progress(&format!("{}: {}; retrying", bastion.name, e));
+                    std::thread::sleep(Duration::from_secs(2));
+                    continue;
+                }
+                // SSM gave a reason (not connected, access denied…): retrying won't help.
+                Err((e, _)) => {
+                    progress(&format!("{} did not open a session: {}", bastion.name, e));
+                    failures.push(format!("{}: {}", bastion.name, e));
+                    if !last { std::thread::sleep(Duration::from_secs(2)); }
+                    break;
+                }
+            };
+            if let Some((pid, latency_ms)) = latency_ms {
+                progress(&match attempt {
+                    1 => format!("Connected through {}", bastion.name),
+                    n => format!("Connected through {} on attempt {}", bastion.name, n),
+                });
+                return Ok(registered(TunnelProcess {
                     pid, local_port, remote_port,
                     remote_host: Some(host),
                     instance_id: bastion.id.clone(), instance_name: bastion.name.clone(),
                     port_open: true, latency_ms: Some(latency_ms),
-                });
-            }
-            Err(_) => {
-                // SSM session failed to bind — try next bastion.
-                std::thread::sleep(Duration::from_secs(2));
+                    document: Some(document), parameters: Some(parameters),
+                }));
             }
         }
     }
     Err(AppError::Tunnel(format!(
-        "All {} bastion(s) failed to establish SSM tunnel to {}:{}", online_bastions.len(), host, remote_port
+        "All {} bastion(s) failed to establish SSM tunnel to {}:{} ({})",
+        ranked.len(), host, remote_port, failures.join("; ")
     )))
 }
 
-pub fn start_dns_tunnel(
-    url: &str,
+/// Open a tunnel to a target from `aws::resolve_dns_to_target`.
+pub fn start_target_tunnel(
+    target: TunnelTarget,
     local_port: u16,
     remote_port: u16,
     profile: Option<&str>,
 ) -> Result<TunnelProcess> {
-    let target = aws::resolve_dns_to_target(url, profile)?;
+    let choice = current_document();
     match target {
         TunnelTarget::Ec2 { instance_id, name } => {
-            let child = start_direct_tunnel(&instance_id, local_port, remote_port, profile)?;
-            let pid = child.id();
-            std::mem::forget(child);
-            let latency_ms = wait_and_probe(local_port, pid, Duration::from_secs(20))?;
-            Ok(TunnelProcess {
-                pid, local_port, remote_port, remote_host: None,
-                instance_id, instance_name: name,
-                port_open: true, latency_ms: Some(latency_ms),
-            })
-        }
-        TunnelTarget::RemoteViaBastion { bastion_id, bastion_name, target_host, .. } => {
-            let child = start_remote_tunnel(&bastion_id, &target_host, local_port, remote_port, profile)?;
-            let pid = child.id();
-            std::mem::forget(child);
-            let latency_ms = wait_and_probe(local_port, pid, Duration::from_secs(20))?;
-            Ok(TunnelProcess {
-                pid, local_port, remote_port,
-                remote_host: Some(target_host),
-                instance_id: bastion_id, instance_name: bastion_name,
-                port_open: true, latency_ms: Some(latency_ms),
-            })
+            open_tunnel(&instance_id, &name, None, local_port, remote_port, &choice, profile)
         }
+        TunnelTarget::RemoteViaBastion { bastion_id, bastion_name, target_host, .. } => open_tunnel(
+            &bastion_id, &bastion_name, Some(&target_host), local_port, remote_port, &choice, profile,
+        ),
     }
 }
 
@@ -321,17 +1086,9 @@ pub fn start_remote_tunnel_via_pattern(
     remote_port: u16,
     profile: Option<&str>,
 ) -> Result<TunnelProcess> {
-    let bastion = aws::find_instance_by_name(bastion_pattern, profile)?;
-    let child = start_remote_tunnel(&bastion.id, host, local_port, remote_port, profile)?;
-    let pid = child.id();
-    std::mem::forget(child);
-    let latency_ms = wait_and_probe(local_port, pid, Duration::from_secs(20))?;
-    Ok(TunnelProcess {
-        pid, local_port, remote_port,
-        remote_host: Some(host.to_string()),
-        instance_id: bastion.id, instance_name: bastion.name,
-        port_open: true, latency_ms: Some(latency_ms),
-    })
+    let detail = forward_detail(local_port, host, remote_port);
+    let bastion = audit_failure(bastion_pattern, &detail, aws::find_instance_by_name(bastion_pattern, profile))?;
+    open_tunnel(&bastion.id, &bastion.name, Some(host), local_port, remote_port, &current_document(), profile)
 }
 
 pub fn start_remote_tunnel_via_instance(
@@ -342,22 +1099,71 @@ pub fn start_remote_tunnel_via_instance(
     remote_port: u16,
     profile: Option<&str>,
 ) -> Result<TunnelProcess> {
-    let child = start_remote_tunnel(instance_id, host, local_port, remote_port, profile)?;
-    let pid = child.id();
-    std::mem::forget(child);
-    let latency_ms = wait_and_probe(local_port, pid, Duration::from_secs(20))?;
-    Ok(TunnelProcess {
-        pid, local_port, remote_port,
-        remote_host: Some(host.to_string()),
-        instance_id: instance_id.to_string(),
-        instance_name: instance_name.to_string(),
-        port_open: true, latency_ms: Some(latency_ms),
+    open_tunnel(instance_id, instance_name, Some(host), local_port, remote_port, &current_document(), profile)
+}
+
+// ── Equivalent commands ───────────────────────────────────────────────────────
+
+/// The `awsx2` invocation that recreates `tp` (bound to 127.0.0.1 like TUI
+/// tunnels). None when the instance name is unknown, e.g. a `ps`-detected
+/// tunnel missing from the registry.
+pub fn awsx2_command_string(tp: &TunnelProcess) -> Option<String> {
+    if tp.instance_name.is_empty() { return None; }
+    let mut cmd = Command::new("awsx2");
+    if let Some(env) = crate::config::env_name() { cmd.args(["--env", env]); }
+    if let Some(r) = aws::region_override() { cmd.args(["--region", &r]); }
+    let (local, remote) = (tp.local_port.to_string(), tp.remote_port.to_string());
+    let kind = match &tp.remote_host {
+        None => { cmd.args(["tunnel", &tp.instance_name, &local, &remote, "--bind", "127.0.0.1"]); DOC_DIRECT }
+        Some(host) => { cmd.args(["tunnel-remote", &tp.instance_name, host, &local, &remote]); DOC_REMOTE }
+    };
+    if let Some(doc) = tp.document.as_deref().filter(|d| *d != kind) {
+        cmd.args(["--document", doc]);
+        let params: serde_json::Map<String, serde_json::Value> = tp
+            .parameters
+            .as_deref()
+            .and_then(|p| serde_json::from_str(p).ok())
+            .unwrap_or_default();
+        for (k, v) in params.iter().filter(|(k, _)| !filled_params(kind).contains(&k.as_str())) {
+            if let Some(v) = v.get(0).and_then(|v| v.as_str()) {
+                cmd.args(["--document-param", &format!("{}={}", k, v)]);
+            }
+        }
+    }
+    let line = aws::command_line(&cmd);
+    Some(match std::env::var("AWS_PROFILE").ok().filter(|p| !p.is_empty()) {
+        Some(p) => format!("AWS_PROFILE={} {}", p, line),
+        None => line,
     })
 }
 
+/// The bare `aws ssm start-session` behind `tp` (no registry entry, stays in
+/// the foreground).
+pub fn ssm_command_string(tp: &TunnelProcess) -> String {
+    // What the running session actually used, custom documents included.
+    if let (Some(doc), Some(params)) = (&tp.document, &tp.parameters) {
+        return aws::command_line(&make_ssm_cmd(&tp.instance_id, doc, params, None));
+    }
+    let doc = if tp.remote_host.is_some() { DOC_REMOTE } else { DOC_DIRECT };
+    let params = forward_params(doc, tp.remote_host.as_deref(), tp.local_port, tp.remote_port)
+        .unwrap_or_default();
+    aws::command_line(&make_ssm_cmd(&tp.instance_id, doc, &params, None))
+}
+
+/// Both of the above, as shown by the TUI `C` key and after the wizard.
+pub fn command_strings(tp: &TunnelProcess) -> String {
+    let mut out = String::new();
+    if let Some(line) = awsx2_command_string(tp) {
+        out.push_str(&format!("# awsx2\n{}\n\n", line));
+    }
+    out.push_str(&format!("# aws CLI\n{}", ssm_command_string(tp)));
+    out
+}
+
 // ── Bind forwarder (socat) ────────────────────────────────────────────────────
 
-/// Start a socat process to forward from bind_addr:port to 127.0.0.1:target_port.
+/// Start a socat process to forward from bind_addr:port to 127.0.0.1:target_port,
+/// recorded against the tunnel on `target_port` for `restart_tunnel`.
 /// Returns the socat process PID.
 pub fn start_bind_forwarder(bind_addr: &str, listen_port: u16, target_port: u16) -> Result<u32> {
     // Check if socat is available
@@ -379,6 +1185,7 @@ pub fn start_bind_forwarder(bind_addr: &str, listen_port: u16, target_port: u16)
     let child = cmd.spawn()?;
     let pid = child.id();
     std::mem::forget(child);
+    update_entry(target_port, |e| e.bind = Some((bind_addr.to_string(), listen_port)));
 
     // Wait briefly for socat to bind
     std::thread::sleep(Duration::from_millis(200));
@@ -395,16 +1202,190 @@ pub fn find_available_port(start: u16) -> u16 {
     start + 100 // fallback
 }
 
+// ── Keepalive ─────────────────────────────────────────────────────────────────
+
+/// Set by `--keepalive` on the tunnel commands.
+static KEEPALIVE: AtomicBool = AtomicBool::new(false);
+
+/// How often `keepalive_loop` checks whether the tunnel is still there.
+const KEEPALIVE_CHECK: Duration = Duration::from_secs(2);
+
+pub fn set_keepalive(on: bool) {
+    KEEPALIVE.store(on, Ordering::Relaxed);
+}
+
+fn keepalive_enabled() -> bool {
+    KEEPALIVE.load(Ordering::Relaxed) || crate::config::settings().tunnel_keepalive
+}
+
+/// Start a detached `awsx2 keepalive` helper for a new tunnel. A process
+/// rather than a thread, because tunnels outlive the CLI command (and the
+/// TUI) that started them. Best effort: a failed spawn only loses the
+/// keepalive.
+fn spawn_keepalive(local_port: u16, pid: u32) {
+    let Ok(exe) = std::env::current_exe() else { return };
+    let interval = crate::config::settings().tunnel_keepalive_secs.max(10);
+    let mut cmd = Command::new(exe);
+    cmd.args([
+        "keepalive",
+        "--port", &local_port.to_string(),
+        "--pid", &pid.to_string(),
+        "--interval", &interval.to_string(),
+    ]);
+    if let Some(env) = crate::config::env_name() {
+        cmd.args(["--env", env]);
+    }
+    cmd.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+    if let Ok(mut child) = cmd.spawn() {
+        // Reap it when it ends, or a long-running TUI collects one zombie
+        // per tunnel. A CLI command exits first and leaves it to init.
+        std::thread::spawn(move || { let _ = child.wait(); });
+    }
+}
+
+/// Body of `awsx2 keepalive`: push a `probe_remote` request through the
+/// tunnel every `interval` so the SSM session never reaches its idle timeout
+/// (20 minutes by default). Returns as soon as the tunnel process exits, or
+/// its port stops accepting connections, whichever is seen first.
+pub fn keepalive_loop(local_port: u16, pid: u32, interval: Duration) {
+    loop {
+        let deadline = Instant::now() + interval;
+        while Instant::now() < deadline {
+            if !pid_alive(pid) {
+                return;
+            }
+            std::thread::sleep(KEEPALIVE_CHECK);
+        }
+        // A stopped session can linger as a zombie of the TUI that started
+        // it, so a closed port also ends the loop.
+        if probe_remote(local_port).is_none() && !test_port(local_port) {
+            return;
+        }
+    }
+}
+
 // ── Stop tunnels ──────────────────────────────────────────────────────────────
 
 pub fn stop_tunnel(pid: u32) {
+    // pid 0 marks a registry-only (dead) tunnel; kill(0, …) would hit our own group.
+    if pid == 0 { return; }
     #[cfg(unix)]
     unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM); }
     #[cfg(not(unix))]
     { let _ = Command::new("taskkill").args(["/PID", &pid.to_string(), "/F"]).status(); }
 }
 
+/// Stop one tunnel, chosen by local port or by pid (as shown by `tunnel-list`),
+/// along with any `--bind` forwarder in front of it. A port held by a forwarder
+/// is followed to the session behind it. Returns the tunnel and every local
+/// port that was freed.
+pub fn stop_one_tunnel(port: Option<u16>, pid: Option<u32>) -> Result<(TunnelProcess, Vec<u16>)> {
+    let mut tunnels = detect_tunnels();
+    let dead = dead_tunnels(&tunnels);
+    tunnels.extend(dead);
+    let forwarders = bind_forwarders();
+
+    let session_port = port.map(|p| {
+        forwarders.iter().find(|f| f.1 == p).map_or(p, |f| f.2)
+    });
+    let tp = tunnels
+        .into_iter()
+        .find(|t| match (session_port, pid) {
+            (Some(p), _) => t.local_port == p,
+            (None, Some(pid)) => pid != 0 && t.pid == pid,
+            (None, None) => false,
+        })
+        .ok_or_else(|| AppError::Tunnel(match (port, pid) {
+            (Some(p), _) => format!("No tunnel on local port {} (see awsx2 tunnel-list)", p),
+            (None, Some(pid)) => format!("No tunnel with PID {} (see awsx2 tunnel-list)", pid),
+            (None, None) => "Give a port or a PID".into(),
+        }))?;
+
+    stop_tunnel(tp.pid);
+    let mut freed = vec![tp.local_port];
+    for &(fwd_pid, listen, target) in &forwarders {
+        if target == tp.local_port {
+            stop_tunnel(fwd_pid);
+            freed.push(listen);
+        }
+    }
+    forget_tunnel(tp.local_port);
+    crate::audit::record("tunnel-stop", &tp.instance_id, Some(&tunnel_detail(&tp)), None, &Ok(()));
+    Ok((tp, freed))
+}
+
 pub fn stop_all_tunnels() {
-    for t in detect_tunnels() { stop_tunnel(t.pid); }
-    let _ = Command::new("pkill").args(["-f", "session-manager-plugin"]).status();
+    // Every plugin process, not just parsed tunnels — e.g. half-started sessions.
+    for (pid, _) in plugin_processes() { stop_tunnel(pid); }
+    // Deliberately stopped — nothing left to restart.
+    let _guard = REGISTRY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    save_registry(&[]);
+    crate::audit::record("tunnel-stop", "all", None, None, &Ok(()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forward_params_per_document() {
+        assert_eq!(
+            forward_params(DOC_DIRECT, None, 8080, 80).unwrap(),
+            r#"{"portNumber":["80"],"localPortNumber":["8080"]}"#
+        );
+        assert_eq!(
+            forward_params(DOC_REMOTE, Some(" db.internal "), 15432, 5432).unwrap(),
+            r#"{"host":["db.internal"],"portNumber":["5432"],"localPortNumber":["15432"]}"#
+        );
+    }
+
+    #[test]
+    fn forward_params_rejects_mismatches() {
+        assert!(forward_params(DOC_DIRECT, Some("db.internal"), 8080, 80).is_err());
+        assert!(forward_params(DOC_REMOTE, None, 8080, 80).is_err());
+        assert!(forward_params(DOC_REMOTE, Some("  "), 8080, 80).is_err());
+        assert!(forward_params("Org-PortForwarding", None, 8080, 80).is_err());
+    }
+
+    #[test]
+    fn session_document_keeps_aws_managed_documents() {
+        let params = forward_params(DOC_DIRECT, None, 8080, 80).unwrap();
+        let choice = DocumentChoice { name: Some(DOC_DIRECT.into()), extra: vec![] };
+        assert_eq!(
+            session_document(DOC_DIRECT, &params, &choice, None).unwrap(),
+            (DOC_DIRECT.to_string(), params.clone())
+        );
+
+        let choice = DocumentChoice { name: Some(DOC_DIRECT.into()), extra: vec![("reason".into(), "x".into())] };
+        assert!(session_document(DOC_DIRECT, &params, &choice, None).is_err());
+    }
+
+    #[test]
+    fn recorded_document_recovers_extra_parameters() {
+        let params = r#"{"host":["db"],"localPortNumber":["1"],"portNumber":["2"],"reason":["ticket 42"]}"#;
+        let choice = recorded_document(DOC_REMOTE, Some("Org-PF"), Some(params));
+        assert_eq!(choice.name.as_deref(), Some("Org-PF"));
+        assert_eq!(choice.extra, vec![("reason".to_string(), "ticket 42".to_string())]);
+
+        let choice = recorded_document(DOC_DIRECT, None, None);
+        assert!(choice.name.is_none() && choice.extra.is_empty());
+    }
+
+    #[test]
+    fn registry_entry_keeps_attachments() {
+        // Entries written before bind/proxy were recorded still load.
+        let old = r#"{"pid":1,"local_port":8080,"remote_port":80,"remote_host":null,
+            "instance_id":"i-1","instance_name":"web","created_at":0,"document":null,"parameters":null}"#;
+        let entry: RegistryEntry = serde_json::from_str(old).unwrap();
+        assert!(entry.bind.is_none() && entry.proxy.is_none());
+
+        let entry = RegistryEntry {
+            bind: Some(("0.0.0.0".into(), 8080)),
+            proxy: Some(("app.internal".into(), true)),
+            ..entry
+        };
+        let back: RegistryEntry = serde_json::from_str(&serde_json::to_string(&entry).unwrap()).unwrap();
+        assert_eq!(back.bind, Some(("0.0.0.0".to_string(), 8080)));
+        assert_eq!(back.proxy, Some(("app.internal".to_string(), true)));
+    }
 }
@@ -0,0 +1,10 @@
+//! Small helpers shared by the CLI, the TUI and the VPN flow.
+
+use std::process::{Command, Stdio};
+
+/// Open `url` in the default browser (`open` on macOS, `xdg-open` elsewhere).
+/// Fire and forget: the launcher's output and exit status are ignored.
+pub fn open_url_in_browser(url: &str) {
+    let cmd = if cfg!(target_os = "macos") { "open" } else { "xdg-open" };
+    let _ = Command::new(cmd).arg(url).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn();
+}
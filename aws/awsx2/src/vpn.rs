@@ -12,10 +12,13 @@ use std::time::{Duration, Instant};
 
 use regex::Regex;
 
-use crate::error::{AppError, Result};
-use crate::models::VpnConfig;
+use crate::error::{redact, AppError, Result};
+use crate::models::{TunnelMode, VpnConfig, VpnPhase, VpnStatus};
 
-const SAML_LISTEN_PORT: u16 = 35001;
+/// Default SAML callback port (the AWS Client VPN convention).
+pub const DEFAULT_SAML_PORT: u16 = 35001;
+/// Ports tried from the configured one upwards when it is already taken.
+const SAML_PORT_ATTEMPTS: u16 = 5;
 
 fn is_macos() -> bool {
     cfg!(target_os = "macos")
@@ -23,30 +26,77 @@ fn is_macos() -> bool {
 
 // ── Config persistence ───────────────────────────────────────────────────────
 
-fn config_path() -> PathBuf {
-    let base = dirs::config_dir()
-        .unwrap_or_else(|| {
-            PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| "/root".into()))
-                .join(".config")
-        });
-    base.join("awsx2").join("vpn.json")
+/// `vpn.json` for the default profile, `vpn/<name>.json` for named ones.
+pub fn config_path(profile: Option<&str>) -> PathBuf {
+    let dir = crate::config::config_dir();
+    match profile {
+        Some(name) => dir.join("vpn").join(format!("{}.json", name)),
+        None => dir.join("vpn.json"),
+    }
+}
+
+fn check_profile_name(profile: Option<&str>) -> Result<()> {
+    match profile {
+        Some(name) if name.is_empty()
+            || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+        {
+            Err(AppError::Vpn(format!(
+                "Invalid VPN config name '{}' (use letters, digits, '-' or '_')", name
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Names of the saved named VPN profiles (the default profile is not listed).
+pub fn list_configs() -> Vec<String> {
+    let dir = crate::config::config_dir().join("vpn");
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .map(|rd| {
+            rd.flatten()
+                .filter_map(|e| {
+                    let name = e.file_name().to_string_lossy().to_string();
+                    name.strip_suffix(".json").map(|n| n.to_string())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
 }
 
-pub fn load_config() -> Result<VpnConfig> {
-    let path = config_path();
+pub fn load_config(profile: Option<&str>) -> Result<VpnConfig> {
+    check_profile_name(profile)?;
+    let path = config_path(profile);
     if !path.exists() {
         return Ok(VpnConfig::default());
     }
     let content = std::fs::read_to_string(&path)?;
-    serde_json::from_str(&content).map_err(|e| AppError::Vpn(format!("Bad vpn.json: {}", e)))
+    let config: VpnConfig = serde_json::from_str(&content)
+        .map_err(|e| AppError::Vpn(format!("Bad {}: {}", path.display(), e)))?;
+    Ok(config)
 }
 
-pub fn save_config(config: &VpnConfig) -> Result<()> {
-    let path = config_path();
+/// Writes the config, moving the password into the OS keychain when one is
+/// available; `config.password_in_keychain` records where it went.
+pub fn save_config(config: &mut VpnConfig, profile: Option<&str>) -> Result<()> {
+    check_profile_name(profile)?;
+    let path = config_path(profile);
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
     }
-    let json = serde_json::to_string_pretty(config)
+    // Prefer the keychain; keep the plaintext field when it is unavailable
+    // (no Secret Service on a headless server, or built without `keychain`).
+    // An empty password with the flag set was never read from the keychain;
+    // keep pointing at the stored entry.
+    if !config.sso_password.is_empty() {
+        config.password_in_keychain = keychain_set(profile, &config.sso_password).is_ok();
+    }
+    let mut on_disk = config.clone();
+    if on_disk.password_in_keychain {
+        on_disk.sso_password.clear();
+    }
+    let json = serde_json::to_string_pretty(&on_disk)
         .map_err(|e| AppError::Vpn(format!("Serialize error: {}", e)))?;
     std::fs::write(&path, &json)?;
     #[cfg(unix)]
@@ -57,6 +107,67 @@ pub fn save_config(config: &VpnConfig) -> Result<()> {
     Ok(())
 }
 
+// ── Keychain ─────────────────────────────────────────────────────────────────
+
+/// Keychain service the SSO password is stored under; the account is the
+/// config name (`vpn` for the default one).
+const KEYCHAIN_SERVICE: &str = "awsx2-vpn";
+
+#[cfg(feature = "keychain")]
+fn keychain_entry(profile: Option<&str>) -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, profile.unwrap_or("vpn"))
+        .map_err(|e| AppError::Vpn(format!("Keychain unavailable: {}", e)))
+}
+
+#[cfg(feature = "keychain")]
+fn keychain_get(profile: Option<&str>) -> Result<String> {
+    keychain_entry(profile)?
+        .get_password()
+        .map_err(|e| AppError::Vpn(format!("Cannot read password from keychain: {}", e)))
+}
+
+#[cfg(feature = "keychain")]
+fn keychain_set(profile: Option<&str>, password: &str) -> Result<()> {
+    keychain_entry(profile)?
+        .set_password(password)
+        .map_err(|e| AppError::Vpn(format!("Cannot store password in keychain: {}", e)))
+}
+
+#[cfg(not(feature = "keychain"))]
+fn keychain_get(_profile: Option<&str>) -> Result<String> {
+    Err(AppError::Vpn(format!("Built without keychain support ({})", KEYCHAIN_SERVICE)))
+}
+
+#[cfg(not(feature = "keychain"))]
+fn keychain_set(_profile: Option<&str>, _password: &str) -> Result<()> {
+    keychain_get(None).map(|_| ())
+}
+
+// ── TOTP ─────────────────────────────────────────────────────────────────────
+
+/// Current 6-digit MFA code from the stored secret, or `None` when no secret
+/// is configured (callers then fall back to asking the user).
+pub fn totp_code(config: &VpnConfig) -> Result<Option<String>> {
+    let secret = match config.totp_secret.as_deref().map(str::trim) {
+        Some(s) if !s.is_empty() => s,
+        _ => return Ok(None),
+    };
+    // Authenticator apps show the secret grouped and sometimes lowercased.
+    let normalized: String = secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    let bytes = totp_rs::Secret::Encoded(normalized)
+        .to_bytes()
+        .map_err(|e| AppError::Vpn(format!("Invalid TOTP secret (expected base32): {:?}", e)))?;
+    let totp = totp_rs::TOTP::new_unchecked(totp_rs::Algorithm::SHA1, 6, 1, 30, bytes);
+    let code = totp
+        .generate_current()
+        .map_err(|e| AppError::Vpn(format!("TOTP clock error: {}", e)))?;
+    Ok(Some(code))
+}
+
 // ── OpenVPN binary detection (platform-aware) ────────────────────────────────
 
 /// Paths to the AWS-patched OpenVPN binary bundled with AWS VPN Client.
@@ -146,10 +257,36 @@ pub struct SamlChallenge {
     pub server_ip: Option<String>,
 }
 
-pub fn fetch_saml_challenge(ovpn_config_path: &str) -> Result<SamlChallenge> {
-    let creds = write_creds("N/A", &format!("ACS::{}", SAML_LISTEN_PORT))?;
+/// Bind the local SAML callback listener on `preferred`, or the next free port
+/// within `SAML_PORT_ATTEMPTS`. Returns the server and the port it got.
+fn bind_saml_listener(preferred: u16) -> Result<(tiny_http::Server, u16)> {
+    let mut last_err = String::new();
+    for port in (preferred..=u16::MAX).take(SAML_PORT_ATTEMPTS as usize) {
+        match tiny_http::Server::http(format!("127.0.0.1:{}", port)) {
+            Ok(server) => {
+                // Port 0 asks the OS for any port; report the one it picked.
+                let bound = server.server_addr().to_ip().map_or(port, |a| a.port());
+                return Ok((server, bound));
+            }
+            Err(e) => last_err = e.to_string(),
+        }
+    }
+    Err(AppError::Vpn(format!(
+        "Cannot bind SAML listener on ports {}-{}: {}",
+        preferred,
+        preferred.saturating_add(SAML_PORT_ATTEMPTS - 1),
+        last_err
+    )))
+}
+
+/// Phase 2. `saml_port` is sent as the ACS port, so the server redirects the
+/// browser to the listener bound on that port.
+pub fn fetch_saml_challenge(ovpn_config_path: &str, saml_port: u16) -> Result<SamlChallenge> {
+    let creds = write_creds("N/A", &format!("ACS::{}", saml_port))?;
 
-    let mut child = openvpn_cmd(ovpn_config_path, creds.path().to_str().unwrap())
+    let mut cmd = openvpn_cmd(ovpn_config_path, creds.path().to_str().unwrap());
+    crate::aws::log_command(&cmd);
+    let mut child = cmd
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()?;
@@ -186,7 +323,7 @@ pub fn fetch_saml_challenge(ovpn_config_path: &str) -> Result<SamlChallenge> {
         .ok_or_else(|| {
             AppError::SamlAuth(format!(
                 "Could not extract SAML URL from VPN server output.\nLast lines:\n{}",
-                combined.lines().rev().take(5).collect::<Vec<_>>().into_iter().rev().collect::<Vec<_>>().join("\n")
+                redact(&combined.lines().rev().take(5).collect::<Vec<_>>().into_iter().rev().collect::<Vec<_>>().join("\n"))
             ))
         })?;
 
@@ -226,11 +363,51 @@ fn extract_saml_from_query(url_str: &str) -> Option<String> {
 
 // ── Phase 3: Browser automation (headless Chrome, optional) ──────────────────
 
+/// Username field selectors, tuned for AWS IAM Identity Center.
+/// Overridden by `username_selectors` in the VPN config.
+#[cfg(feature = "vpn-browser")]
+pub const DEFAULT_USERNAME_SELECTORS: &[&str] = &[
+    "input[type='email']",
+    "input[name='username']",
+    "input[name='email']",
+    "#awsui-input-0",
+    "input[data-testid='username-input']",
+];
+
+/// Password field selectors. Overridden by `password_selectors`.
+#[cfg(feature = "vpn-browser")]
+pub const DEFAULT_PASSWORD_SELECTORS: &[&str] = &[
+    "input[type='password']",
+    "input[name='password']",
+    "#awsui-input-1",
+    "input[data-testid='password-input']",
+];
+
+/// MFA code field selectors. Overridden by `mfa_selectors`.
+#[cfg(feature = "vpn-browser")]
+pub const DEFAULT_MFA_SELECTORS: &[&str] = &[
+    "input[placeholder='Enter code']",
+    "input[placeholder*='code']",
+    "input[name='mfaCode']",
+    "input[name='totp']",
+    "input[type='tel']",
+    "input[data-testid='mfa-code-input']",
+    "input[inputmode='numeric']",
+];
+
+/// The configured selector list if present, otherwise the built-in defaults.
+#[cfg(feature = "vpn-browser")]
+fn selectors_or<'a>(custom: &'a Option<Vec<String>>, defaults: &'a [&'a str]) -> Vec<&'a str> {
+    match custom {
+        Some(list) if !list.is_empty() => list.iter().map(String::as_str).collect(),
+        _ => defaults.to_vec(),
+    }
+}
+
 #[cfg(feature = "vpn-browser")]
 fn complete_saml_auth(
     saml_url: &str,
-    sso_user: &str,
-    sso_pass: &str,
+    config: &VpnConfig,
     mfa_code: &str,
 ) -> Result<()> {
     use headless_chrome::{Browser, LaunchOptions};
@@ -260,34 +437,27 @@ fn complete_saml_auth(
     std::thread::sleep(Duration::from_secs(3));
 
     // Step A: Username
-    fill_field_and_submit(&tab, &[
-        "input[type='email']",
-        "input[name='username']",
-        "input[name='email']",
-        "#awsui-input-0",
-        "input[data-testid='username-input']",
-    ], sso_user)?;
+    fill_field_and_submit(
+        &tab,
+        &selectors_or(&config.username_selectors, DEFAULT_USERNAME_SELECTORS),
+        &config.sso_username,
+    )?;
     std::thread::sleep(Duration::from_secs(3));
 
     // Step B: Password
-    fill_field_and_submit(&tab, &[
-        "input[type='password']",
-        "input[name='password']",
-        "#awsui-input-1",
-        "input[data-testid='password-input']",
-    ], sso_pass)?;
+    fill_field_and_submit(
+        &tab,
+        &selectors_or(&config.password_selectors, DEFAULT_PASSWORD_SELECTORS),
+        &config.sso_password,
+    )?;
     std::thread::sleep(Duration::from_secs(4));
 
     // Step C: MFA
-    fill_field_and_submit(&tab, &[
-        "input[placeholder='Enter code']",
-        "input[placeholder*='code']",
-        "input[name='mfaCode']",
-        "input[name='totp']",
-        "input[type='tel']",
-        "input[data-testid='mfa-code-input']",
-        "input[inputmode='numeric']",
-    ], mfa_code)?;
+    fill_field_and_submit(
+        &tab,
+        &selectors_or(&config.mfa_selectors, DEFAULT_MFA_SELECTORS),
+        mfa_code,
+    )?;
     std::thread::sleep(Duration::from_secs(4));
 
     // Check if page has SAMLResponse form and submit it
@@ -342,8 +512,7 @@ fn fill_field_and_submit(
 #[cfg(not(feature = "vpn-browser"))]
 fn complete_saml_auth(
     _saml_url: &str,
-    _sso_user: &str,
-    _sso_pass: &str,
+    _config: &VpnConfig,
     _mfa_code: &str,
 ) -> Result<()> {
     Err(AppError::Browser(
@@ -351,11 +520,6 @@ fn complete_saml_auth(
     ))
 }
 
-fn open_url_in_browser(url: &str) {
-    let cmd = if is_macos() { "open" } else { "xdg-open" };
-    let _ = Command::new(cmd).arg(url).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn();
-}
-
 // ── Phase 4: Connect VPN with SAML token ─────────────────────────────────────
 
 /// Pin the config to a specific server IP for phase 4 reconnection.
@@ -378,12 +542,73 @@ fn pin_config_to_server(ovpn_config_path: &str, ip: &str, port: &str) -> Result<
     Ok(tmp)
 }
 
+/// Keep this many openvpn logs in `<config dir>/logs/`.
+const VPN_LOGS_KEPT: usize = 5;
+
+/// Create a fresh `logs/vpn-<unix time in ms>.log`, pruning older logs so that at
+/// most `VPN_LOGS_KEPT` remain including the new one.
+fn open_vpn_log() -> Result<(PathBuf, std::fs::File)> {
+    let dir = crate::config::config_dir().join("logs");
+    std::fs::create_dir_all(&dir)?;
+
+    let mut old: Vec<PathBuf> = std::fs::read_dir(&dir)?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("vpn-") && n.ends_with(".log"))
+        })
+        .collect();
+    old.sort();
+    let excess = (old.len() + 1).saturating_sub(VPN_LOGS_KEPT);
+    for p in old.into_iter().take(excess) {
+        let _ = std::fs::remove_file(p);
+    }
+
+    // Milliseconds, bumped past any existing name, so a quick retry never
+    // truncates the previous attempt's log and names still sort by age.
+    let mut ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let (path, file) = loop {
+        let path = dir.join(format!("vpn-{}.log", ts));
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => break (path, file),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => ts += 1,
+            Err(e) => return Err(e.into()),
+        }
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok((path, file))
+}
+
+/// Most recent openvpn log, if any connect attempt has been made.
+pub fn latest_log() -> Option<PathBuf> {
+    let dir = crate::config::config_dir().join("logs");
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("vpn-") && n.ends_with(".log"))
+        })
+        .max()
+}
+
 fn start_vpn_process(
     ovpn_config_path: &str,
     sid: &str,
     saml_response: &str,
     server_ip: Option<&str>,
-) -> Result<(u32, tempfile::NamedTempFile, tempfile::NamedTempFile, Option<tempfile::NamedTempFile>)> {
+) -> Result<(u32, tempfile::NamedTempFile, PathBuf, Option<tempfile::NamedTempFile>)> {
     let cred_password = format!("CRV1::{}::{}", sid, saml_response);
     let creds = write_creds("N/A", &cred_password)?;
     let creds_path = creds.path().to_str().unwrap().to_string();
@@ -428,16 +653,16 @@ fn start_vpn_process(
         }
     };
 
-    let stderr_log = tempfile::NamedTempFile::new()?;
-    let stderr_file = stderr_log.reopen()?;
-    let stdout_log = tempfile::NamedTempFile::new()?;
-    let stdout_file = stdout_log.reopen()?;
+    // openvpn's stdout and stderr both go to a persistent log for post-mortems.
+    let (log_path, log_file) = open_vpn_log()?;
+    let log_file_err = log_file.try_clone()?;
 
     use std::os::unix::process::CommandExt;
+    crate::aws::log_command(&cmd);
     let mut child = cmd
         .stdin(Stdio::null())
-        .stdout(stdout_file)
-        .stderr(stderr_file)
+        .stdout(log_file)
+        .stderr(log_file_err)
         .process_group(0) // detach into own process group
         .spawn()?;
 
@@ -445,23 +670,20 @@ fn start_vpn_process(
     std::thread::sleep(Duration::from_secs(2));
     if let Ok(Some(status)) = child.try_wait() {
         let mut log = String::new();
-        if let Ok(mut f) = std::fs::File::open(stderr_log.path()) {
-            let _ = f.read_to_string(&mut log);
-        }
-        if let Ok(mut f) = std::fs::File::open(stdout_log.path()) {
+        if let Ok(mut f) = std::fs::File::open(&log_path) {
             let _ = f.read_to_string(&mut log);
         }
         let last_lines: String = log.lines().rev().take(10).collect::<Vec<_>>().into_iter().rev().collect::<Vec<_>>().join("\n");
         return Err(AppError::Vpn(format!(
-            "openvpn exited immediately ({})\n{}",
-            status, last_lines
+            "openvpn exited immediately ({})\n{}\nFull log: {}",
+            status, redact(&last_lines), log_path.display()
         )));
     }
 
     let pid = child.id();
     std::mem::forget(child);
 
-    Ok((pid, creds, stderr_log, pinned_config))
+    Ok((pid, creds, log_path, pinned_config))
 }
 
 // ── Phase 5: TUN interface detection (platform-aware) ────────────────────────
@@ -504,7 +726,7 @@ fn find_tun_interface() -> Option<String> {
                 .stdout(Stdio::null())
                 .stderr(Stdio::null())
                 .status();
-            if status.map_or(false, |s| s.success()) {
+            if status.is_ok_and(|s| s.success()) {
                 return Some(iface);
             }
         }
@@ -626,6 +848,59 @@ pub fn get_vpn_ip() -> Option<String> {
     }
 }
 
+/// Destinations of the routes that go through `iface`: `ip -4 route show dev`
+/// on Linux, the matching `Netif` rows of `netstat -rn` on macOS.
+pub fn vpn_routes(iface: &str) -> Vec<String> {
+    let output = if is_macos() {
+        Command::new("netstat").args(["-rn", "-f", "inet"]).output()
+    } else {
+        Command::new("ip").args(["-4", "route", "show", "dev", iface]).output()
+    };
+    let Ok(output) = output else { return Vec::new() };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            match cols.first() {
+                // macOS: Destination Gateway Flags Netif [Expire]
+                Some(dest) if is_macos() => (cols.get(3) == Some(&iface)).then(|| dest.to_string()),
+                Some(dest) => Some(dest.to_string()),
+                None => None,
+            }
+        })
+        .collect()
+}
+
+/// Full tunnel when there is a default route through the VPN, either plainly
+/// or as the `0/1` + `128/1` pair OpenVPN's `redirect-gateway def1` installs.
+pub fn tunnel_mode(routes: &[String]) -> TunnelMode {
+    let has = |names: &[&str]| routes.iter().any(|r| names.contains(&r.as_str()));
+    if has(&["default", "0.0.0.0/0", "0/0"])
+        || (has(&["0.0.0.0/1", "0/1"]) && has(&["128.0.0.0/1", "128.0/1", "128/1"]))
+    {
+        TunnelMode::Full
+    } else {
+        TunnelMode::Split
+    }
+}
+
+/// Connection details for `vpn status`; DNS settings come from `config`.
+pub fn status(config: &VpnConfig) -> VpnStatus {
+    let interface = find_tun_interface();
+    let routes = interface.as_deref().map(vpn_routes).unwrap_or_default();
+    VpnStatus {
+        connected: interface.is_some(),
+        ip: interface.as_ref().and_then(|_| get_vpn_ip()),
+        pid: interface.as_ref().and_then(|_| find_vpn_pid()),
+        tunnel: interface.as_ref().map(|_| tunnel_mode(&routes)),
+        interface,
+        dns_server: config.dns_server.clone(),
+        dns_domain: config.dns_domain.clone(),
+        routes,
+    }
+}
+
 pub fn find_vpn_pid() -> Option<u32> {
     let output = Command::new("pgrep")
         .args(["-f", "acvc-openvpn|openvpn.*--config"])
@@ -638,7 +913,9 @@ pub fn find_vpn_pid() -> Option<u32> {
         .and_then(|l| l.trim().parse().ok())
 }
 
-pub fn disconnect() {
+/// Stop openvpn and undo the macOS DNS setup. Fails when openvpn is still
+/// running a few seconds later (e.g. it runs as root and we are not).
+pub fn disconnect() -> Result<()> {
     if let Some(pid) = find_vpn_pid() {
         #[cfg(unix)]
         unsafe {
@@ -652,13 +929,18 @@ pub fn disconnect() {
 
     // macOS: clean up resolver files created by configure_dns_macos and flush DNS
     if is_macos() {
-        if let Ok(config) = load_config() {
-            if !config.dns_domain.is_empty() {
-                let domain = config.dns_domain.trim_start_matches('~');
-                let resolver_path = format!("/etc/resolver/{}", domain);
-                let _ = Command::new("sudo")
-                    .args(["rm", "-f", &resolver_path])
-                    .status();
+        // We don't know which profile was connected — clean up after all of them.
+        let names = list_configs();
+        let profiles = std::iter::once(None).chain(names.iter().map(|n| Some(n.as_str())));
+        for profile in profiles {
+            if let Ok(config) = load_config(profile) {
+                if !config.dns_domain.is_empty() {
+                    let domain = config.dns_domain.trim_start_matches('~');
+                    let resolver_path = format!("/etc/resolver/{}", domain);
+                    let _ = Command::new("sudo")
+                        .args(["rm", "-f", &resolver_path])
+                        .status();
+                }
             }
         }
         let _ = Command::new("sudo")
@@ -668,43 +950,202 @@ pub fn disconnect() {
             .args(["killall", "-HUP", "mDNSResponder"])
             .status();
     }
+
+    let start = Instant::now();
+    let mut left = find_vpn_pid();
+    while left.is_some() && start.elapsed() < Duration::from_secs(3) {
+        std::thread::sleep(Duration::from_millis(200));
+        left = find_vpn_pid();
+    }
+    let result = match left {
+        Some(pid) => Err(AppError::Vpn(format!("openvpn (pid {}) is still running", pid))),
+        None => Ok(()),
+    };
+    crate::audit::record("vpn-disconnect", "vpn", None, None, &result);
+    result
+}
+
+// ── Diagnostics ──────────────────────────────────────────────────────────────
+
+/// One line of `vpn doctor` output.
+#[derive(Debug, Clone)]
+pub struct DoctorCheck {
+    pub label: String,
+    pub ok: bool,
+    pub detail: String,
+    /// `connect` cannot succeed while a required check fails.
+    pub required: bool,
+}
+
+fn find_on_path(bin: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(bin))
+        .find(|p| p.is_file())
+}
+
+fn saml_port_free(port: u16) -> bool {
+    std::net::TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
+
+/// PID and command name of whatever listens on `port`, via lsof (best effort).
+fn port_holder(port: u16) -> Option<String> {
+    let output = Command::new("lsof")
+        .args(["-nP", &format!("-iTCP:{}", port), "-sTCP:LISTEN", "-Fpc"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let pid = stdout.lines().find_map(|l| l.strip_prefix('p'))?;
+    let cmd = stdout.lines().find_map(|l| l.strip_prefix('c')).unwrap_or("?");
+    Some(format!("PID {} ({})", pid, cmd))
+}
+
+/// Checks for everything `connect` depends on, in the order it needs them.
+pub fn doctor(config: &VpnConfig) -> Vec<DoctorCheck> {
+    let mut checks = Vec::new();
+    let mut check = |label: &str, ok: bool, detail: String, required: bool| {
+        checks.push(DoctorCheck { label: label.into(), ok, detail, required });
+    };
+
+    let ovpn = &config.ovpn_path;
+    if ovpn.is_empty() {
+        check(".ovpn file", false, "not configured — run 'awsx2 vpn setup'".into(), true);
+    } else {
+        match std::fs::File::open(ovpn) {
+            Ok(_) => check(".ovpn file", true, ovpn.clone(), true),
+            Err(e) => check(".ovpn file", false, format!("{}: {}", ovpn, e), true),
+        }
+    }
+
+    match find_aws_openvpn() {
+        Some(args) => check("openvpn", true, format!("acvc-openvpn ({})", args.last().unwrap()), true),
+        None => match find_on_path("openvpn") {
+            Some(p) => check("openvpn", true, format!("stock openvpn ({}) — SAML may need acvc-openvpn", p.display()), true),
+            None => check("openvpn", false, "neither acvc-openvpn nor openvpn found".into(), true),
+        },
+    }
+
+    let port = config.saml_port.unwrap_or(crate::config::settings().saml_listen_port);
+    if saml_port_free(port) {
+        check("SAML port", true, format!("{} is free", port), false);
+    } else {
+        let holder = port_holder(port).map(|h| format!(" by {}", h)).unwrap_or_default();
+        check("SAML port", false, format!(
+            "{} is in use{} (stale connect?) — the next free port will be tried", port, holder
+        ), false);
+    }
+
+    let mut tools = vec!["session-manager-plugin", "dig"];
+    if !is_macos() {
+        tools.push("resolvectl");
+    }
+    for tool in tools {
+        match find_on_path(tool) {
+            Some(p) => check(tool, true, p.display().to_string(), false),
+            None => check(tool, false, "not found on PATH".into(), false),
+        }
+    }
+
+    checks
 }
 
 // ── High-level orchestration ─────────────────────────────────────────────────
 
 /// Full VPN connection flow. Returns the openvpn PID on success.
-pub fn connect<F>(config: &VpnConfig, mfa_code: &str, mut progress: F) -> Result<u32>
+///
+/// `progress` is called with the current phase and a message; the first
+/// message of each phase describes the phase, later ones are details.
+pub fn connect<F, C>(
+    config: &VpnConfig,
+    profile: Option<&str>,
+    mfa_code: &str,
+    progress: F,
+    cancelled: C,
+) -> Result<u32>
 where
-    F: FnMut(&str),
+    F: FnMut(VpnPhase, &str),
+    C: Fn() -> bool,
 {
+    let mut config = config.clone();
+    if config.password_in_keychain && config.sso_password.is_empty() {
+        // Left empty on failure: `connect_inner` then reports the missing password.
+        if let Ok(password) = keychain_get(profile) {
+            config.sso_password = password;
+        }
+    }
+    let config = &config;
+    let result = connect_inner(config, mfa_code, progress, cancelled);
+    let detail = result.as_ref().ok().map(|pid| format!("pid {}", pid));
+    crate::audit::record("vpn-connect", &config.ovpn_path, detail.as_deref(), None, &result);
+    result
+}
+
+/// `cancelled` is polled between phases and while waiting, so a connect the
+/// user gave up on stops instead of bringing the VPN up behind their back.
+fn connect_inner<F, C>(config: &VpnConfig, mfa_code: &str, mut progress: F, cancelled: C) -> Result<u32>
+where
+    F: FnMut(VpnPhase, &str),
+    C: Fn() -> bool,
+{
+    let check = || if cancelled() { Err(AppError::Vpn("Cancelled".into())) } else { Ok(()) };
     if config.ovpn_path.is_empty() {
         return Err(AppError::Vpn("No .ovpn file path configured. Run 'awsx2 vpn setup' first.".into()));
     }
+    if config.password_in_keychain && config.sso_password.is_empty() {
+        return Err(AppError::Vpn(
+            "SSO password is stored in the OS keychain but could not be read. \
+             Unlock the keychain or run 'awsx2 vpn setup --password ...' again.".into()
+        ));
+    }
     if config.sso_username.is_empty() || config.sso_password.is_empty() {
         return Err(AppError::Vpn("SSO credentials not configured. Run 'awsx2 vpn setup' first.".into()));
     }
 
-    progress("[1/5] Preparing VPN config...");
+    progress(VpnPhase::PreparingConfig, "Preparing VPN config...");
+    let mut blocking = Vec::new();
+    for c in doctor(config).into_iter().filter(|c| !c.ok) {
+        if c.required {
+            blocking.push(format!("  ✗ {}: {}", c.label, c.detail));
+        } else if c.label == "SAML port" {
+            progress(VpnPhase::PreparingConfig, &c.detail);
+        }
+    }
+    if !blocking.is_empty() {
+        return Err(AppError::Vpn(format!(
+            "Pre-flight checks failed:\n{}\nRun 'awsx2 vpn doctor' for details.",
+            blocking.join("\n")
+        )));
+    }
+    check()?;
     let modified_config = prepare_ovpn_config(&config.ovpn_path)?;
     let config_path = modified_config.path().to_str().unwrap().to_string();
 
-    progress("[2/5] Fetching SAML URL from VPN server...");
-    let challenge = fetch_saml_challenge(&config_path)?;
-    progress(&format!("  SAML URL received ({} chars), SID: {}...",
-        challenge.saml_url.len(),
-        &challenge.sid[..challenge.sid.len().min(30)]));
+    // Bind the callback listener first: the port it gets is the one the VPN
+    // server must redirect to, so it has to be known before phase 2.
+    let preferred_port = config.saml_port.unwrap_or(crate::config::settings().saml_listen_port);
+    let (server, saml_port) = bind_saml_listener(preferred_port)?;
+
+    progress(VpnPhase::FetchingSaml, "Fetching SAML URL from VPN server...");
+    if saml_port != preferred_port {
+        progress(VpnPhase::FetchingSaml, &format!(
+            "Port {} is busy, listening for SAML callback on {}", preferred_port, saml_port));
+    }
+    let challenge = fetch_saml_challenge(&config_path, saml_port)?;
+    check()?;
+    progress(VpnPhase::FetchingSaml, &format!("SAML URL received ({} chars), SID received ({} chars)",
+        challenge.saml_url.len(), challenge.sid.len()));
 
-    progress("[3/5] Completing SAML authentication (headless browser)...");
+    progress(VpnPhase::Authenticating, "Completing SAML authentication (headless browser)...");
 
     let saml_url = challenge.saml_url.clone();
-    let user = config.sso_username.clone();
-    let pass = config.sso_password.clone();
+    let browser_config = config.clone();
     let mfa = mfa_code.to_string();
 
     let browser_failed = Arc::new(AtomicBool::new(false));
     let bf = browser_failed.clone();
     let browser_handle = std::thread::spawn(move || {
-        let result = complete_saml_auth(&saml_url, &user, &pass, &mfa);
+        let result = complete_saml_auth(&saml_url, &browser_config, &mfa);
         if result.is_err() {
             bf.store(true, Ordering::SeqCst);
         }
@@ -713,14 +1154,13 @@ where
 
     // Wait for SAML callback with system browser fallback.
     // If headless Chrome fails or takes too long, open the real browser.
-    let server = tiny_http::Server::http(format!("127.0.0.1:{}", SAML_LISTEN_PORT))
-        .map_err(|e| AppError::Vpn(format!("Cannot bind SAML listener on port {}: {}", SAML_LISTEN_PORT, e)))?;
 
     let deadline = Instant::now() + Duration::from_secs(120);
     let fallback_at = Instant::now() + Duration::from_secs(25);
     let mut fallback_opened = false;
 
     let saml_response = loop {
+        check()?;
         if Instant::now() > deadline {
             return Err(AppError::SamlAuth("SAML callback timeout (no response received)".into()));
         }
@@ -729,8 +1169,8 @@ where
         if !fallback_opened
             && (browser_failed.load(Ordering::SeqCst) || Instant::now() > fallback_at)
         {
-            progress("  Headless browser did not complete. Opening system browser...");
-            open_url_in_browser(&challenge.saml_url);
+            progress(VpnPhase::Authenticating, "Headless browser did not complete. Opening system browser...");
+            crate::util::open_url_in_browser(&challenge.saml_url);
             fallback_opened = true;
         }
 
@@ -761,16 +1201,17 @@ where
         }
     };
 
-    progress(&format!("  SAML response captured ({} chars)", saml_response.len()));
+    progress(VpnPhase::Authenticating, &format!("SAML response captured ({} chars)", saml_response.len()));
 
     let _ = browser_handle.join().map_err(|_| AppError::Browser("Browser thread panicked".into()))?;
 
-    progress("[4/5] Connecting VPN with SAML token (sudo required)...");
+    progress(VpnPhase::Connecting, "Connecting VPN with SAML token (sudo required)...");
     let openvpn_type = if find_aws_openvpn().is_some() { "acvc-openvpn" } else { "stock openvpn" };
-    progress(&format!("  Using {}, pinned to server: {}",
+    progress(VpnPhase::Connecting, &format!("Using {}, pinned to server: {}",
         openvpn_type,
         challenge.server_ip.as_deref().unwrap_or("(DNS, not pinned)")));
 
+    check()?;
     // Prime sudo credentials so the openvpn spawn doesn't silently wait for a password
     let sudo_status = Command::new("sudo")
         .args(["-v"])
@@ -780,7 +1221,7 @@ where
         return Err(AppError::Vpn("sudo authentication failed".into()));
     }
 
-    let (pid, _creds, _stderr_log, _pinned_config) = start_vpn_process(
+    let (pid, _creds, log_path, _pinned_config) = start_vpn_process(
         &config_path,
         &challenge.sid,
         &saml_response,
@@ -790,15 +1231,18 @@ where
     // Keep temp files alive so openvpn can read them
     std::mem::forget(modified_config);
     std::mem::forget(_creds);
-    std::mem::forget(_stderr_log);
     if let Some(pc) = _pinned_config { std::mem::forget(pc); }
 
-    progress("[5/5] Waiting for TUN interface and configuring DNS...");
+    progress(VpnPhase::ConfiguringDns, "Waiting for TUN interface and configuring DNS...");
 
     // Wait for TUN interface to come up, checking that openvpn is still alive.
     let start = Instant::now();
     let mut tun_found = false;
     while start.elapsed() < Duration::from_secs(20) {
+        if cancelled() {
+            let _ = disconnect();
+            return Err(AppError::Vpn("Cancelled".into()));
+        }
         if find_tun_interface().is_some() {
             tun_found = true;
             break;
@@ -809,25 +1253,27 @@ where
             .stdout(Stdio::null())
             .stderr(Stdio::null())
             .status()
-            .map_or(false, |s| s.success());
+            .is_ok_and(|s| s.success());
         if !alive {
             return Err(AppError::Vpn(format!(
                 "openvpn process (PID {}) exited before TUN interface came up. \
-                 Try running with: sudo awsx2 vpn connect",
-                pid
+                 Try running with: sudo awsx2 vpn connect\nLog: {}",
+                pid, log_path.display()
             )));
         }
         std::thread::sleep(Duration::from_secs(1));
     }
 
     if !tun_found {
-        return Err(AppError::Vpn("TUN interface did not come up within 20 seconds".into()));
+        return Err(AppError::Vpn(format!(
+            "TUN interface did not come up within 20 seconds\nLog: {}", log_path.display()
+        )));
     }
 
     configure_dns(&config.dns_server, &config.dns_domain)?;
 
     let ip = get_vpn_ip().unwrap_or_else(|| "unknown".into());
-    progress(&format!("VPN connected! IP: {}, PID: {}", ip, pid));
+    progress(VpnPhase::Done, &format!("VPN connected! IP: {}, PID: {}", ip, pid));
 
     Ok(pid)
 }